@@ -0,0 +1,310 @@
+use crate::{BigDecimal, BigInt, BigUint, Body, Date, DateTime, Header, Serializer};
+use rand::Rng;
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+use std::{
+    collections::BTreeMap,
+    iter,
+    time::{Duration, SystemTime},
+};
+use time::{ext::NumericalDuration, OffsetDateTime};
+
+const MAX_LEN: usize = 8;
+
+// `InternedString` is left out, as it only decodes with string interning set on both sides.
+pub fn gen_header<R: Rng + ?Sized>(rng: &mut R, depth: usize) -> Header {
+    let code = if depth == 0 {
+        rng.gen_range(0..26)
+    } else {
+        rng.gen_range(0..41)
+    };
+    match code {
+        0 => Header::Unit,
+        1 => Header::Boolean,
+        2 => Header::UInt8,
+        3 => Header::UInt16,
+        4 => Header::UInt32,
+        5 => Header::UInt64,
+        6 => Header::Int8,
+        7 => Header::Int16,
+        8 => Header::Int32,
+        9 => Header::Int64,
+        10 => Header::Float32,
+        11 => Header::Float64,
+        12 => Header::BigUInt,
+        13 => Header::BigInt,
+        14 => Header::BigDecimal,
+        15 => Header::String,
+        16 => Header::Binary,
+        17 => Header::Date,
+        18 => Header::DateTime,
+        19 => Header::Null,
+        20 => Header::FixedPoint {
+            scale: rng.gen_range(-4..8),
+        },
+        21 => Header::Duration,
+        22 => Header::SystemTime,
+        23 => Header::TypedBinary,
+        24 => Header::DurationNanos128,
+        25 => Header::Decimal {
+            scale: rng.gen_range(0..20),
+        },
+        26 => Header::Optional(Box::new(gen_header(rng, depth - 1))),
+        27 => Header::Array(Box::new(gen_header(rng, depth - 1))),
+        28 => Header::Tuple(gen_headers(rng, depth - 1, 0)),
+        29 => Header::Map(Box::new(gen_header(rng, depth - 1))),
+        30 => Header::Enum(gen_headers(rng, depth - 1, 1)),
+        31 => Header::Annotated(Box::new(gen_header(rng, depth - 1)), gen_string(rng)),
+        32 => Header::Tagged(Box::new(gen_header(rng, depth - 1)), rng.gen()),
+        33 => Header::BitmapStruct(gen_headers(rng, depth - 1, 0)),
+        34 => Header::Columnar(Box::new(Header::Tuple(gen_headers(rng, depth - 1, 0)))),
+        // Delta and run-length encoding only change how integer elements are written, so mostly
+        // generate those.
+        35 => Header::DeltaArray(Box::new(gen_integer_header(rng, depth - 1))),
+        36 => Header::RleArray(Box::new(gen_integer_header(rng, depth - 1))),
+        37 => Header::SegmentedArray(Box::new(gen_header(rng, depth - 1))),
+        38 => Header::Reserved(Box::new(gen_header(rng, depth - 1))),
+        39 => Header::FlattenedMap(
+            gen_headers(rng, depth - 1, 0)
+                .into_iter()
+                .enumerate()
+                .map(|(i, inner)| (format!("f{}", i), inner))
+                .collect(),
+            Box::new(gen_header(rng, depth - 1)),
+        ),
+        _ => Header::OpenEnum(gen_headers(rng, depth - 1, 1)),
+    }
+}
+
+fn gen_headers<R: Rng + ?Sized>(rng: &mut R, depth: usize, min_len: usize) -> Vec<Header> {
+    (0..rng.gen_range(min_len..MAX_LEN))
+        .map(|_| gen_header(rng, depth))
+        .collect()
+}
+
+fn gen_integer_header<R: Rng + ?Sized>(rng: &mut R, depth: usize) -> Header {
+    match rng.gen_range(0..10) {
+        0 => Header::UInt8,
+        1 => Header::UInt16,
+        2 => Header::UInt32,
+        3 => Header::UInt64,
+        4 => Header::Int8,
+        5 => Header::Int16,
+        6 => Header::Int32,
+        7 => Header::Int64,
+        8 => Header::Tagged(Box::new(Header::UInt32), rng.gen()),
+        _ => gen_header(rng, depth),
+    }
+}
+
+pub fn gen_body<R: Rng + ?Sized>(header: &Header, rng: &mut R) -> Body {
+    match header {
+        Header::Unit => Body::Unit,
+        Header::Null => Body::Null,
+        Header::Optional(inner) => Body::Optional(if rng.gen() {
+            Some(Box::new(gen_body(inner, rng)))
+        } else {
+            None
+        }),
+        Header::Boolean => Body::Boolean(rng.gen()),
+        Header::UInt8 => Body::UInt8(rng.gen()),
+        Header::UInt16 => Body::UInt16(rng.gen()),
+        Header::UInt32 => Body::UInt32(rng.gen()),
+        Header::UInt64 => Body::UInt64(rng.gen()),
+        Header::Int8 => Body::Int8(rng.gen()),
+        Header::Int16 => Body::Int16(rng.gen()),
+        Header::Int32 => Body::Int32(rng.gen()),
+        Header::Int64 => Body::Int64(rng.gen()),
+        // NaN never compares equal to itself, so keep generated floats comparable.
+        Header::Float32 => Body::Float32(
+            Some(f32::from_bits(rng.gen()))
+                .filter(|v| !v.is_nan())
+                .unwrap_or_default(),
+        ),
+        Header::Float64 => Body::Float64(
+            Some(f64::from_bits(rng.gen()))
+                .filter(|v| !v.is_nan())
+                .unwrap_or_default(),
+        ),
+        Header::BigUInt => Body::BigUInt(BigUint::from(num_bigint::BigUint::from_bytes_le(
+            &gen_bytes_raw(rng),
+        ))),
+        Header::BigInt => Body::BigInt(BigInt::from(num_bigint::BigInt::from_signed_bytes_le(
+            &gen_bytes_raw(rng),
+        ))),
+        Header::BigDecimal => Body::BigDecimal(BigDecimal::from(bigdecimal::BigDecimal::new(
+            num_bigint::BigInt::from_signed_bytes_le(&gen_bytes_raw(rng)),
+            rng.gen_range(-1000..1000),
+        ))),
+        Header::FixedPoint { scale } => Body::FixedPoint(
+            crate::fixed_point::decode(rng.gen_range(-(1 << 40)..(1 << 40)), *scale),
+            *scale,
+        ),
+        Header::String | Header::InternedString => Body::String(gen_string(rng)),
+        Header::Binary => Body::Binary(ByteBuf::from(gen_bytes_raw(rng))),
+        Header::TypedBinary => Body::TypedBinary(rng.gen(), ByteBuf::from(gen_bytes_raw(rng))),
+        Header::Array(inner) => Body::Array(
+            (0..rng.gen_range(0..MAX_LEN))
+                .map(|_| gen_body(inner, rng))
+                .collect(),
+        ),
+        Header::Columnar(inner) => Body::Columnar(
+            (0..rng.gen_range(0..MAX_LEN))
+                .map(|_| gen_body(inner, rng))
+                .collect(),
+        ),
+        Header::DeltaArray(inner) => Body::DeltaArray(
+            (0..rng.gen_range(0..MAX_LEN))
+                .map(|_| gen_body(inner, rng))
+                .collect(),
+        ),
+        Header::SegmentedArray(inner) => Body::SegmentedArray(
+            (0..rng.gen_range(0..MAX_LEN))
+                .map(|_| gen_body(inner, rng))
+                .collect(),
+        ),
+        // Repeat each value a few times so the runs are longer than one element.
+        Header::RleArray(inner) => Body::RleArray(
+            (0..rng.gen_range(0..MAX_LEN))
+                .flat_map(|_| iter::repeat_n(gen_body(inner, rng), rng.gen_range(1..4)))
+                .collect(),
+        ),
+        Header::Tuple(inner) => Body::Tuple(inner.iter().map(|v| gen_body(v, rng)).collect()),
+        Header::BitmapStruct(inner) => {
+            Body::BitmapStruct(inner.iter().map(|v| gen_body(v, rng)).collect())
+        }
+        Header::Map(inner) => Body::Map(
+            (0..rng.gen_range(0..MAX_LEN))
+                .map(|_| (gen_string(rng), gen_body(inner, rng)))
+                .collect(),
+        ),
+        Header::FlattenedMap(fields, extra) => {
+            let mut map = fields
+                .iter()
+                .map(|(name, inner)| (name.clone(), gen_body(inner, rng)))
+                .collect::<BTreeMap<_, _>>();
+            for _ in 0..rng.gen_range(0..MAX_LEN) {
+                let key = gen_string(rng);
+                map.entry(key).or_insert_with(|| gen_body(extra, rng));
+            }
+            Body::Map(map)
+        }
+        Header::Enum(inner) => {
+            let index = rng.gen_range(0..inner.len());
+            Body::Enum(index as u32, Box::new(gen_body(&inner[index], rng)))
+        }
+        Header::OpenEnum(inner) => {
+            let index = rng.gen_range(0..inner.len() + 1);
+            match inner.get(index) {
+                Some(inner) => Body::OpenEnum(index as u32, Box::new(gen_body(inner, rng))),
+                None => Body::UnknownVariant(
+                    index as u32,
+                    (0..rng.gen_range(0..MAX_LEN)).map(|_| rng.gen()).collect(),
+                ),
+            }
+        }
+        Header::Date => Body::Date(Date::from(
+            time::Date::from_julian_day(
+                rng.gen_range(time::Date::MIN.to_julian_day()..=time::Date::MAX.to_julian_day()),
+            )
+            .unwrap(),
+        )),
+        Header::DateTime => Body::DateTime(DateTime::from(
+            OffsetDateTime::from_unix_timestamp(rng.gen_range(-(1 << 36)..(1 << 36))).unwrap()
+                + rng.gen_range(0..1_000_000_000).nanoseconds(),
+        )),
+        Header::Duration => {
+            Body::Duration(Duration::new(rng.gen(), rng.gen_range(0..1_000_000_000)))
+        }
+        Header::SystemTime => Body::SystemTime(
+            SystemTime::UNIX_EPOCH
+                + Duration::new(rng.gen_range(0..1 << 36), rng.gen_range(0..1_000_000_000)),
+        ),
+        Header::DurationNanos128 => Body::DurationNanos128(rng.gen()),
+        Header::Decimal { scale } => Body::Decimal(rng.gen(), *scale),
+        Header::Annotated(inner, _) | Header::Tagged(inner, _) | Header::Reserved(inner) => {
+            gen_body(inner, rng)
+        }
+    }
+}
+
+pub fn gen_bytes<R: Rng + ?Sized>(header: &Header, rng: &mut R) -> Vec<u8> {
+    let mut buf = Vec::new();
+    gen_body(header, rng)
+        .serialize(&mut Serializer::new(&mut buf))
+        .unwrap();
+    buf
+}
+
+fn gen_string<R: Rng + ?Sized>(rng: &mut R) -> String {
+    (0..rng.gen_range(0..MAX_LEN))
+        .map(|_| rng.gen::<char>())
+        .collect()
+}
+
+fn gen_bytes_raw<R: Rng + ?Sized>(rng: &mut R) -> Vec<u8> {
+    (0..rng.gen_range(0..MAX_LEN)).map(|_| rng.gen()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gen_body, gen_bytes, gen_header};
+    use crate::{Body, Deserializer, Serializer};
+    use rand::{rngs::StdRng, SeedableRng};
+    use serde::Serialize;
+
+    #[test]
+    fn gen_body_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let header = gen_header(&mut rng, 4);
+            let body = gen_body(&header, &mut rng);
+            assert!(body.validate(&header));
+
+            let mut buf = Vec::new();
+            body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(Body::deserialize(&header, &mut deserializer), Ok(body));
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn optimize_preserves_bodies() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..1000 {
+            let header = gen_header(&mut rng, 4);
+            let optimized = header.optimize();
+            let (mut original_size, mut optimized_size) = (Vec::new(), Vec::new());
+            header.serialize(&mut original_size).unwrap();
+            optimized.serialize(&mut optimized_size).unwrap();
+            assert!(optimized_size.len() <= original_size.len());
+
+            for _ in 0..4 {
+                let buf = gen_bytes(&header, &mut rng);
+                let decode = |header| {
+                    let mut reader = buf.as_slice();
+                    let mut deserializer = Deserializer::new(&mut reader);
+                    let body = Body::deserialize(header, &mut deserializer).unwrap();
+                    assert_eq!(deserializer.end(), Ok(()));
+                    body
+                };
+                assert!(decode(&optimized).semantically_eq(&decode(&header)));
+            }
+        }
+    }
+
+    #[test]
+    fn gen_bytes_decodes() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let header = gen_header(&mut rng, 4);
+            let buf = gen_bytes(&header, &mut rng);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert!(Body::deserialize(&header, &mut deserializer).is_ok());
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+    }
+}
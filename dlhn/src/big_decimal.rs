@@ -7,6 +7,8 @@ use serde::{
     Deserialize, Serialize,
 };
 
+pub(crate) const BIG_DECIMAL_NAME: &str = "$dlhn::BigDecimal";
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct BigDecimal {
     signed_bytes: Vec<u8>,
@@ -41,6 +43,76 @@ impl Into<bigdecimal::BigDecimal> for BigDecimal {
     }
 }
 
+impl BigDecimal {
+    // Compares the numeric values, so an unnormalized `10` with scale 1 equals `1` with scale 0.
+    #[cfg(all(feature = "num-traits", feature = "num-bigint", feature = "bigdecimal"))]
+    pub(crate) fn value_eq(&self, other: &Self) -> bool {
+        Into::<bigdecimal::BigDecimal>::into(self.clone()) == other.clone().into()
+    }
+
+    #[cfg(not(all(feature = "num-traits", feature = "num-bigint", feature = "bigdecimal")))]
+    pub(crate) fn value_eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl BigDecimal {
+    // The same value without trailing zeros in the coefficient, as `From` produces and
+    // `format::big_decimal` writes. A decoded value may carry such zeros.
+    pub(crate) fn normalized(&self) -> Self {
+        let negative = self.signed_bytes.last().is_some_and(|v| v & 0x80 != 0);
+        let mut magnitude = self.signed_bytes.clone();
+        if negative {
+            negate(&mut magnitude);
+        }
+        let mut scale = self.scale;
+        let mut divided = false;
+        while let Some(next) = scale.checked_sub(1) {
+            let mut quotient = magnitude.clone();
+            let mut rem = 0u16;
+            for v in quotient.iter_mut().rev() {
+                let n = rem << 8 | *v as u16;
+                *v = (n / 10) as u8;
+                rem = n % 10;
+            }
+            if rem != 0 || quotient.iter().all(|v| *v == 0) {
+                break;
+            }
+            magnitude = quotient;
+            scale = next;
+            divided = true;
+        }
+        if !divided {
+            return self.clone();
+        }
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        if negative {
+            negate(&mut magnitude);
+            if magnitude.last().is_some_and(|v| v & 0x80 == 0) {
+                magnitude.push(0xff);
+            }
+        } else if magnitude.last().is_some_and(|v| v & 0x80 != 0) {
+            magnitude.push(0);
+        }
+        Self {
+            signed_bytes: magnitude,
+            scale,
+        }
+    }
+}
+
+// Two's complement negation of a little-endian integer, in place.
+fn negate(bytes: &mut [u8]) {
+    let mut carry = true;
+    for v in bytes.iter_mut() {
+        let (sum, overflow) = (!*v).overflowing_add(carry as u8);
+        *v = sum;
+        carry = overflow;
+    }
+}
+
 impl Serialize for BigDecimal {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -48,11 +120,12 @@ impl Serialize for BigDecimal {
     {
         let mut seq = serializer.serialize_seq(None)?;
 
-        if self.signed_bytes == [] {
+        if self.signed_bytes.is_empty() {
             seq.serialize_element(&0u8)?;
         } else {
-            seq.serialize_element(&self.signed_bytes)?;
-            seq.serialize_element(&self.scale)?;
+            let v = self.normalized();
+            seq.serialize_element(&v.signed_bytes)?;
+            seq.serialize_element(&v.scale)?;
         }
 
         seq.end()
@@ -74,16 +147,20 @@ impl<'de> Visitor<'de> for BigDecimalVisitor {
     {
         let signed_bytes = seq
             .next_element::<Vec<u8>>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
+            .ok_or(de::Error::invalid_value(
+                Unexpected::Seq,
+                &Error::Read { offset: 0 },
+            ))?;
         if signed_bytes.is_empty() {
             Ok(BigDecimal {
                 signed_bytes,
                 scale: 0,
             })
         } else {
-            let scale = seq
-                .next_element::<i64>()?
-                .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
+            let scale = seq.next_element::<i64>()?.ok_or(de::Error::invalid_value(
+                Unexpected::Seq,
+                &Error::Read { offset: 0 },
+            ))?;
             Ok(BigDecimal {
                 signed_bytes,
                 scale,
@@ -100,6 +177,22 @@ impl<'de> Deserialize<'de> for BigDecimal {
     }
 }
 
+// A `BigDecimal` decoded under dlhn's `BigDecimalLimits`. Body decoding reaches the limits
+// through the name, which only dlhn's deserializer looks at, so `BigDecimal` itself stays a
+// plain tuple in every format.
+pub(crate) struct LimitedBigDecimal(pub(crate) BigDecimal);
+
+impl<'de> Deserialize<'de> for LimitedBigDecimal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_tuple_struct(BIG_DECIMAL_NAME, 2, BigDecimalVisitor)
+            .map(LimitedBigDecimal)
+    }
+}
+
 #[cfg(all(feature = "num-traits", feature = "num-bigint", feature = "bigdecimal"))]
 #[cfg(test)]
 mod tests {
@@ -119,6 +212,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn value_eq() {
+        let one = BigDecimal::from(bigdecimal::BigDecimal::from(1));
+        let unnormalized = BigDecimal {
+            signed_bytes: vec![100],
+            scale: 2,
+        };
+        assert_ne!(one, unnormalized);
+        assert!(one.value_eq(&unnormalized));
+        assert!(!one.value_eq(&BigDecimal {
+            signed_bytes: vec![100],
+            scale: 1,
+        }));
+    }
+
+    #[test]
+    fn normalized() {
+        for (v, scale) in [
+            (123i64, 0i64),
+            (1200, 2),
+            (-1200, 3),
+            (-128, 0),
+            (-1280, 1),
+            (1280, 1),
+            (i64::MIN, 7),
+        ] {
+            let unnormalized = BigDecimal {
+                signed_bytes: BigInt::from(v).to_signed_bytes_le(),
+                scale,
+            };
+            let expected = BigDecimal::from(bigdecimal::BigDecimal::new(BigInt::from(v), scale));
+            assert_eq!(unnormalized.normalized(), expected);
+        }
+        // The scale stops at its lower bound.
+        let v = BigDecimal {
+            signed_bytes: vec![100],
+            scale: i64::MIN + 1,
+        };
+        assert_eq!(
+            v.normalized(),
+            BigDecimal {
+                signed_bytes: vec![10],
+                scale: i64::MIN,
+            }
+        );
+    }
+
     #[test]
     fn into() {
         let v: bigdecimal::BigDecimal = BigDecimal::from(bigdecimal::BigDecimal::from(-123)).into();
@@ -7,6 +7,8 @@ use serde::{
     Deserialize, Serialize,
 };
 
+pub(crate) const BIG_INT_NAME: &str = "$dlhn::BigInt";
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct BigInt(Vec<u8>);
 
@@ -56,7 +58,10 @@ impl<'de> Visitor<'de> for BigIntVisitor {
     {
         let v = seq
             .next_element::<Vec<u8>>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
+            .ok_or(de::Error::invalid_value(
+                Unexpected::Seq,
+                &Error::Read { offset: 0 },
+            ))?;
         Ok(BigInt(v))
     }
 }
@@ -66,7 +71,7 @@ impl<'de> Deserialize<'de> for BigInt {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_tuple(1, BigIntVisitor)
+        deserializer.deserialize_tuple_struct(BIG_INT_NAME, 1, BigIntVisitor)
     }
 }
 
@@ -7,6 +7,8 @@ use serde::{
     Deserialize, Serialize,
 };
 
+pub(crate) const BIG_UINT_NAME: &str = "$dlhn::BigUint";
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct BigUint(Vec<u8>);
 
@@ -56,7 +58,10 @@ impl<'de> Visitor<'de> for BigUintVisitor {
     {
         let v = seq
             .next_element::<Vec<u8>>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
+            .ok_or(de::Error::invalid_value(
+                Unexpected::Seq,
+                &Error::Read { offset: 0 },
+            ))?;
         Ok(BigUint(v))
     }
 }
@@ -66,7 +71,7 @@ impl<'de> Deserialize<'de> for BigUint {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_tuple(1, BigUintVisitor)
+        deserializer.deserialize_tuple_struct(BIG_UINT_NAME, 1, BigUintVisitor)
     }
 }
 
@@ -0,0 +1,88 @@
+// Helpers shared by the conversions between `Body` and the value types of text formats.
+use std::{convert::TryFrom, time::SystemTime};
+use time::OffsetDateTime;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(super) fn date_time(v: OffsetDateTime) -> String {
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:09}Z",
+        v.date(),
+        v.hour(),
+        v.minute(),
+        v.second(),
+        v.nanosecond()
+    )
+}
+
+// Widens through the shortest decimal form of `v`, so `0.1f32` becomes `0.1` rather than
+// `0.10000000149011612`.
+pub(super) fn f32_to_f64(v: f32) -> f64 {
+    v.to_string().parse().unwrap_or(v as f64)
+}
+
+// `OffsetDateTime::from` panics on a `SystemTime` outside the years `time` supports, so such a
+// time is returned as the (possibly negative) seconds since the epoch instead.
+pub(super) fn system_time(v: SystemTime) -> Result<OffsetDateTime, f64> {
+    let (nanos, secs) = match v.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => (i128::try_from(d.as_nanos()).ok(), d.as_secs_f64()),
+        Err(e) => (
+            i128::try_from(e.duration().as_nanos()).ok().map(|v| -v),
+            -e.duration().as_secs_f64(),
+        ),
+    };
+    nanos
+        .and_then(|v| OffsetDateTime::from_unix_timestamp_nanos(v).ok())
+        .ok_or(secs)
+}
+
+pub(super) fn base64(bytes: &[u8]) -> String {
+    let mut buf = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, v)| n | (*v as u32) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                buf.push(BASE64_ALPHABET[(n >> (18 - i * 6)) as usize & 0x3f] as char);
+            } else {
+                buf.push('=');
+            }
+        }
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn f32_to_f64() {
+        for (input, output) in [
+            (0.1f32, 0.1f64),
+            (1.5, 1.5),
+            (-3.4028235e38, -3.4028235e38),
+            (f32::INFINITY, f64::INFINITY),
+        ] {
+            assert_eq!(super::f32_to_f64(input), output);
+        }
+        assert!(super::f32_to_f64(f32::NAN).is_nan());
+    }
+
+    #[test]
+    fn base64() {
+        for (input, output) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0xff, 0xfe, 0xfd], "//79"),
+        ] {
+            assert_eq!(super::base64(input), output);
+        }
+    }
+}
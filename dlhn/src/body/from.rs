@@ -0,0 +1,206 @@
+use super::Body;
+use crate::{BigDecimal, BigInt, BigUint, Date, DateTime};
+use serde_bytes::ByteBuf;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+// Builds a `Body` from JSON-like literals, e.g. `body!({ "a": 1u8, "b": [true, null] })`. Maps
+// become `Body::Map`, arrays `Body::Array`, `null` is `Body::Null`, and any other value goes
+// through `Body::from`, so integer literals need a suffix to pick their variant.
+#[macro_export]
+macro_rules! body {
+    (null) => {
+        $crate::Body::Null
+    };
+    ([ $($elements:tt)* ]) => {
+        $crate::Body::Array($crate::body!(@array [] $($elements)*))
+    };
+    ({ $($entries:tt)* }) => {
+        $crate::Body::Map($crate::body!(@map [] $($entries)*))
+    };
+    (@array [$($done:expr,)*]) => {
+        ::std::vec![$($done,)*]
+    };
+    (@array [$($done:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::body!(@array [$($done,)* $crate::Body::Null,] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::body!(@array [$($done,)* $crate::body!([$($inner)*]),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::body!(@array [$($done,)* $crate::body!({$($inner)*}),] $($($rest)*)?)
+    };
+    (@array [$($done:expr,)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::body!(@array [$($done,)* $crate::Body::from($value),] $($($rest)*)?)
+    };
+    (@map [$($done:expr,)*]) => {
+        <::std::collections::BTreeMap<::std::string::String, $crate::Body>>::from([$($done,)*])
+    };
+    (@map [$($done:expr,)*] $key:literal : null $(, $($rest:tt)*)?) => {
+        $crate::body!(@map [$($done,)* ($key.to_string(), $crate::Body::Null),] $($($rest)*)?)
+    };
+    (@map [$($done:expr,)*] $key:literal : [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::body!(@map [$($done,)* ($key.to_string(), $crate::body!([$($inner)*])),] $($($rest)*)?)
+    };
+    (@map [$($done:expr,)*] $key:literal : { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::body!(@map [$($done,)* ($key.to_string(), $crate::body!({$($inner)*})),] $($($rest)*)?)
+    };
+    (@map [$($done:expr,)*] $key:literal : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::body!(@map [$($done,)* ($key.to_string(), $crate::Body::from($value)),] $($($rest)*)?)
+    };
+    ($value:expr) => {
+        $crate::Body::from($value)
+    };
+}
+
+macro_rules! from_impls {
+    ($($ty:ty => $variant:ident)+) => {
+        $(
+            impl From<$ty> for Body {
+                fn from(v: $ty) -> Self {
+                    Body::$variant(v)
+                }
+            }
+        )+
+    }
+}
+
+from_impls! {
+    bool => Boolean
+    u8 => UInt8
+    u16 => UInt16
+    u32 => UInt32
+    u64 => UInt64
+    i8 => Int8
+    i16 => Int16
+    i32 => Int32
+    i64 => Int64
+    f32 => Float32
+    f64 => Float64
+    BigUint => BigUInt
+    BigInt => BigInt
+    BigDecimal => BigDecimal
+    String => String
+    ByteBuf => Binary
+    PathBuf => Path
+    Date => Date
+    DateTime => DateTime
+    Duration => Duration
+    SystemTime => SystemTime
+}
+
+impl From<()> for Body {
+    fn from(_: ()) -> Self {
+        Body::Unit
+    }
+}
+
+impl From<&str> for Body {
+    fn from(v: &str) -> Self {
+        Body::String(v.to_string())
+    }
+}
+
+impl<T: Into<Body>> From<Option<T>> for Body {
+    fn from(v: Option<T>) -> Self {
+        Body::Optional(v.map(|v| Box::new(v.into())))
+    }
+}
+
+impl<T: Into<Body>> From<Vec<T>> for Body {
+    fn from(v: Vec<T>) -> Self {
+        Body::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Body>> From<BTreeMap<String, T>> for Body {
+    fn from(v: BTreeMap<String, T>) -> Self {
+        Body::Map(v.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Body;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn body_from() {
+        assert_eq!(Body::from(5u8), Body::UInt8(5));
+        assert_eq!(Body::from(-5i64), Body::Int64(-5));
+        assert_eq!(Body::from(1.5f32), Body::Float32(1.5));
+        assert_eq!(Body::from(true), Body::Boolean(true));
+        assert_eq!(Body::from("x"), Body::String("x".to_string()));
+        assert_eq!(Body::from(()), Body::Unit);
+        assert_eq!(
+            Body::from(Some(1u16)),
+            Body::Optional(Some(Box::new(Body::UInt16(1))))
+        );
+        assert_eq!(Body::from(None::<u16>), Body::Optional(None));
+        assert_eq!(
+            Body::from(vec![1u32, 2]),
+            Body::Array(vec![Body::UInt32(1), Body::UInt32(2)])
+        );
+        assert_eq!(
+            Body::from(vec![Body::Unit, Body::Null]),
+            Body::Array(vec![Body::Unit, Body::Null])
+        );
+        assert_eq!(
+            Body::from(BTreeMap::from([("a".to_string(), "b")])),
+            Body::Map(BTreeMap::from([(
+                "a".to_string(),
+                Body::String("b".to_string())
+            )]))
+        );
+    }
+
+    #[test]
+    fn body_macro() {
+        assert_eq!(body!(1u8), Body::UInt8(1));
+        assert_eq!(body!(null), Body::Null);
+        assert_eq!(body!([]), Body::Array(vec![]));
+        assert_eq!(body!({}), Body::Map(BTreeMap::new()));
+        assert_eq!(
+            body!({ "a": 1u8, "b": true }),
+            Body::Map(BTreeMap::from([
+                ("a".to_string(), Body::UInt8(1)),
+                ("b".to_string(), Body::Boolean(true)),
+            ]))
+        );
+
+        let name = "x".to_string();
+        assert_eq!(
+            body!({
+                "name": name.as_str(),
+                "tags": ["a", null, [-1i8, 2i8]],
+                "nested": { "id": Some(7u64), "empty": {} },
+                "none": null,
+            }),
+            Body::Map(BTreeMap::from([
+                ("name".to_string(), Body::String("x".to_string())),
+                (
+                    "tags".to_string(),
+                    Body::Array(vec![
+                        Body::String("a".to_string()),
+                        Body::Null,
+                        Body::Array(vec![Body::Int8(-1), Body::Int8(2)]),
+                    ])
+                ),
+                (
+                    "nested".to_string(),
+                    Body::Map(BTreeMap::from([
+                        (
+                            "id".to_string(),
+                            Body::Optional(Some(Box::new(Body::UInt64(7))))
+                        ),
+                        ("empty".to_string(), Body::Map(BTreeMap::new())),
+                    ]))
+                ),
+                ("none".to_string(), Body::Null),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use super::{
+    convert::{base64, date_time, f32_to_f64, system_time},
+    Body,
+};
+use crate::decimal;
+use serde_json::{Map, Number, Value};
+use std::convert::TryInto;
+use time::OffsetDateTime;
+
+impl From<&Body> for Value {
+    fn from(body: &Body) -> Self {
+        match body {
+            Body::Unit | Body::Null | Body::Optional(None) => Value::Null,
+            Body::Optional(Some(v)) => Value::from(v.as_ref()),
+            Body::Boolean(v) => Value::Bool(*v),
+            Body::UInt8(v) => Value::from(*v),
+            Body::UInt16(v) => Value::from(*v),
+            Body::UInt32(v) => Value::from(*v),
+            Body::UInt64(v) => Value::from(*v),
+            Body::Int8(v) => Value::from(*v),
+            Body::Int16(v) => Value::from(*v),
+            Body::Int32(v) => Value::from(*v),
+            Body::Int64(v) => Value::from(*v),
+            // JSON has no representation for NaN or infinities.
+            Body::Float32(v) => float(f32_to_f64(*v)),
+            Body::Float64(v) | Body::FixedPoint(v, _) => float(*v),
+            Body::BigUInt(v) => {
+                Value::String(Into::<num_bigint::BigUint>::into(v.clone()).to_string())
+            }
+            Body::BigInt(v) => {
+                Value::String(Into::<num_bigint::BigInt>::into(v.clone()).to_string())
+            }
+            Body::BigDecimal(v) => {
+                Value::String(Into::<bigdecimal::BigDecimal>::into(v.clone()).to_string())
+            }
+            Body::String(v) => Value::String(v.clone()),
+            Body::Binary(v) => Value::String(base64(v)),
+            Body::TypedBinary(tag, v) => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), Value::from(*tag));
+                map.insert("data".to_string(), Value::String(base64(v)));
+                Value::Object(map)
+            }
+            Body::Path(v) => Value::String(v.to_string_lossy().into_owned()),
+            Body::Array(v)
+            | Body::Tuple(v)
+            | Body::BitmapStruct(v)
+            | Body::Columnar(v)
+            | Body::DeltaArray(v)
+            | Body::RleArray(v)
+            | Body::SegmentedArray(v) => Value::Array(v.iter().map(Value::from).collect()),
+            Body::Map(v) => Value::Object(
+                v.iter()
+                    .map(|(key, value)| (key.clone(), Value::from(value)))
+                    .collect(),
+            ),
+            Body::OrderedMap(v) => Value::Object(
+                v.iter()
+                    .map(|(key, value)| (key.clone(), Value::from(value)))
+                    .collect(),
+            ),
+            // Variant names are not part of the format, so the index stands in for the name.
+            Body::Enum(i, v) | Body::OpenEnum(i, v) => {
+                let mut map = Map::new();
+                map.insert(i.to_string(), Value::from(v.as_ref()));
+                Value::Object(map)
+            }
+            Body::UnknownVariant(i, v) => {
+                let mut map = Map::new();
+                map.insert(i.to_string(), Value::String(base64(v)));
+                Value::Object(map)
+            }
+            Body::Date(v) => TryInto::<time::Date>::try_into(*v)
+                .map_or(Value::Null, |v| Value::String(v.to_string())),
+            Body::DateTime(v) => TryInto::<OffsetDateTime>::try_into(*v)
+                .map_or(Value::Null, |v| Value::String(date_time(v))),
+            Body::Duration(v) => float(v.as_secs_f64()),
+            Body::DurationNanos128(v) => float(*v as f64 / 1e9),
+            Body::Decimal(v, scale) => Value::String(decimal::to_string(*v, *scale)),
+            Body::SystemTime(v) => {
+                system_time(*v).map_or_else(float, |v| Value::String(date_time(v)))
+            }
+        }
+    }
+}
+
+fn float(v: f64) -> Value {
+    Number::from_f64(v).map_or(Value::Null, Value::Number)
+}
+
+// A `null` becomes `Body::Null`, so it is not confused with `Unit` or a missing `Optional`, and
+// an absent key is simply not in the map.
+impl From<&Value> for Body {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Null => Body::Null,
+            Value::Bool(v) => Body::Boolean(*v),
+            Value::Number(v) => match (v.as_i64(), v.as_u64(), v.as_f64()) {
+                (Some(v), _, _) => Body::Int64(v),
+                (None, Some(v), _) => Body::UInt64(v),
+                (None, None, v) => Body::Float64(v.unwrap_or(f64::NAN)),
+            },
+            Value::String(v) => Body::String(v.clone()),
+            Value::Array(v) => Body::Array(v.iter().map(Body::from).collect()),
+            Value::Object(v) => Body::Map(
+                v.iter()
+                    .map(|(key, value)| (key.clone(), Body::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        BigDecimal, BigInt, BigUint, Body, Date, DateTime, Deserializer, Header, Serializer,
+    };
+    use serde::Serialize;
+    use serde_bytes::ByteBuf;
+    use serde_json::{json, Value};
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, SystemTime},
+    };
+    use time::{ext::NumericalDuration, Month, OffsetDateTime};
+
+    #[test]
+    fn body_to_json_value() {
+        let body = Body::Tuple(vec![
+            Body::Unit,
+            Body::Optional(None),
+            Body::Optional(Some(Box::new(Body::Int8(-1)))),
+            Body::UInt64(u64::MAX),
+            Body::Float32(0.1),
+            Body::Float64(f64::NAN),
+            Body::FixedPoint(12.34, 2),
+            Body::BigUInt(BigUint::from(num_bigint::BigUint::from(u128::MAX))),
+            Body::BigInt(BigInt::from(num_bigint::BigInt::from(i128::MIN))),
+            Body::BigDecimal(BigDecimal::from(bigdecimal::BigDecimal::new(
+                num_bigint::BigInt::from(-12345),
+                2,
+            ))),
+            Body::Binary(ByteBuf::from(b"dlhn!".to_vec())),
+            Body::Map(BTreeMap::from([
+                ("b".to_string(), Body::Boolean(true)),
+                (
+                    "a".to_string(),
+                    Body::Array(vec![Body::String("x".to_string())]),
+                ),
+            ])),
+            Body::Enum(1, Box::new(Body::UInt8(2))),
+            Body::Date(Date::from(
+                time::Date::from_calendar_date(2021, Month::March, 4).unwrap(),
+            )),
+            Body::DateTime(DateTime::from(
+                OffsetDateTime::from_unix_timestamp(1_614_834_367).unwrap() + 8.nanoseconds(),
+            )),
+            Body::Decimal(i128::MAX, 2),
+        ]);
+        assert_eq!(
+            Value::from(&body),
+            json!([
+                null,
+                null,
+                -1,
+                u64::MAX,
+                0.1,
+                null,
+                12.34,
+                u128::MAX.to_string(),
+                i128::MIN.to_string(),
+                "-123.45",
+                "ZGxobiE=",
+                {"a": ["x"], "b": true},
+                {"1": 2},
+                "2021-03-04",
+                "2021-03-04T05:06:07.000000008Z",
+                "1701411834604692317316873037158841057.27",
+            ])
+        );
+    }
+
+    #[test]
+    fn body_to_json_value_system_time_out_of_range() {
+        let secs = 1u64 << 40;
+        assert_eq!(
+            Value::from(&Body::SystemTime(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+            )),
+            json!(secs as f64)
+        );
+        assert_eq!(
+            Value::from(&Body::SystemTime(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_614_834_367)
+            )),
+            json!("2021-03-04T05:06:07.000000000Z")
+        );
+    }
+
+    #[test]
+    fn json_null_roundtrip() {
+        let value = json!({"id": 1, "parent": null, "tags": [null, "a"]});
+        let body = Body::from(&value);
+        assert_eq!(
+            body,
+            Body::Map(BTreeMap::from([
+                ("id".to_string(), Body::Int64(1)),
+                ("parent".to_string(), Body::Null),
+                (
+                    "tags".to_string(),
+                    Body::Array(vec![Body::Null, Body::String("a".to_string())]),
+                ),
+            ]))
+        );
+
+        // Through DLHN: `parent` is a null, `note` is absent, and `unit` is a unit.
+        let header = Header::FlattenedMap(
+            vec![
+                ("id".to_string(), Header::Int64),
+                ("note".to_string(), Header::String),
+                ("parent".to_string(), Header::Null),
+                ("unit".to_string(), Header::Unit),
+            ],
+            Box::new(Header::Array(Box::new(Header::Optional(Box::new(
+                Header::String,
+            ))))),
+        );
+        let body = Body::Map(BTreeMap::from([
+            ("id".to_string(), Body::Int64(1)),
+            ("parent".to_string(), Body::Null),
+            ("unit".to_string(), Body::Unit),
+            (
+                "tags".to_string(),
+                Body::Array(vec![
+                    Body::Optional(None),
+                    Body::Optional(Some(Box::new(Body::String("a".to_string())))),
+                ]),
+            ),
+        ]));
+        assert!(body.validate(&header));
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        let decoded =
+            Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())).unwrap();
+        assert_eq!(decoded, body);
+        match &decoded {
+            Body::Map(v) => {
+                assert_eq!(v["parent"], Body::Null);
+                assert_eq!(v["unit"], Body::Unit);
+                assert!(!v.contains_key("note"));
+            }
+            body => panic!("expected a map, got {:?}", body),
+        }
+        assert!(!Body::Null.validate(&Header::Unit));
+        assert!(!Body::Unit.validate(&Header::Null));
+        assert!(!Body::Null.validate(&Header::Optional(Box::new(Header::Unit))));
+
+        // Going back to JSON, every kind of nothing is a `null`.
+        assert_eq!(
+            Value::from(&decoded),
+            json!({"id": 1, "parent": null, "tags": [null, "a"], "unit": null})
+        );
+    }
+}
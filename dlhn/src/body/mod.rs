@@ -1,11 +1,42 @@
-use crate::{de::Error, BigDecimal, BigInt, BigUint, Date, DateTime, Deserializer, Header};
-use serde::{ser::SerializeTuple, Deserialize, Serialize};
-use serde_bytes::ByteBuf;
-use std::{collections::BTreeMap, io::Read};
+use crate::{
+    big_decimal::LimitedBigDecimal,
+    de::{Error, MAX_PREALLOCATION},
+    fixed_point, BigDecimal, BigInt, BigUint, Date, DateTime, Deserializer, Header,
+};
+use serde::{
+    ser::{self, SerializeMap, SerializeTuple},
+    Deserialize, Serialize,
+};
+use serde_bytes::{ByteBuf, Bytes};
+use std::{
+    cmp::min,
+    collections::BTreeMap,
+    convert::TryFrom,
+    io::Read,
+    iter,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+#[cfg(any(feature = "serde_json", feature = "toml", feature = "yaml"))]
+mod convert;
+mod from;
+#[cfg(feature = "serde_json")]
+mod json;
+#[cfg(feature = "toml")]
+mod toml;
+mod validate;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+#[cfg(feature = "toml")]
+pub use self::toml::TomlError;
+pub use validate::ValidationError;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Body {
     Unit,
+    Null,
     Optional(Option<Box<Body>>),
     Boolean(bool),
     UInt8(u8),
@@ -20,18 +51,34 @@ pub enum Body {
     // Int128(i128),
     Float32(f32),
     Float64(f64),
+    FixedPoint(f64, i8),
+    Decimal(i128, u8),
     BigUInt(BigUint),
     BigInt(BigInt),
     BigDecimal(BigDecimal),
     String(String),
     Binary(ByteBuf),
+    TypedBinary(u16, ByteBuf),
+    Path(PathBuf),
     Array(Vec<Body>),
     Tuple(Vec<Body>),
+    BitmapStruct(Vec<Body>),
+    Columnar(Vec<Body>),
+    DeltaArray(Vec<Body>),
+    RleArray(Vec<Body>),
+    SegmentedArray(Vec<Body>),
     // Struct(Vec<Body>),
     Map(BTreeMap<String, Body>),
+    OrderedMap(Vec<(String, Body)>),
     Enum(u32, Box<Body>),
+    // A variant of an `OpenEnum` header, and one whose index the header does not list.
+    OpenEnum(u32, Box<Body>),
+    UnknownVariant(u32, Vec<u8>),
     Date(Date),
     DateTime(DateTime),
+    Duration(Duration),
+    SystemTime(SystemTime),
+    DurationNanos128(i128),
 }
 
 impl Serialize for Body {
@@ -40,7 +87,7 @@ impl Serialize for Body {
         S: serde::Serializer,
     {
         match self {
-            Body::Unit => ().serialize(serializer),
+            Body::Unit | Body::Null => ().serialize(serializer),
             Body::Optional(v) => v.serialize(serializer),
             Body::Boolean(v) => v.serialize(serializer),
             Body::UInt8(v) => v.serialize(serializer),
@@ -55,11 +102,20 @@ impl Serialize for Body {
             // Body::Int128(v) => v.serialize(serializer),
             Body::Float32(v) => v.serialize(serializer),
             Body::Float64(v) => v.serialize(serializer),
+            Body::FixedPoint(v, scale) => fixed_point::serialize(*v, *scale, serializer),
+            Body::Decimal(v, _) => v.serialize(serializer),
             Body::BigUInt(v) => v.serialize(serializer),
             Body::BigInt(v) => v.serialize(serializer),
             Body::BigDecimal(v) => v.serialize(serializer),
             Body::String(v) => v.serialize(serializer),
             Body::Binary(v) => v.serialize(serializer),
+            Body::TypedBinary(tag, v) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(tag)?;
+                tuple.serialize_element(v)?;
+                tuple.end()
+            }
+            Body::Path(v) => crate::format::path::serialize(v, serializer),
             Body::Array(v) => v.serialize(serializer),
             Body::Tuple(v) /* | Body::Struct(v) */ => {
                 let mut tuple = serializer.serialize_tuple(v.len())?;
@@ -68,11 +124,153 @@ impl Serialize for Body {
                 }
                 tuple.end()
             }
+            Body::BitmapStruct(v) => {
+                let mut bits = vec![0u8; v.len().div_ceil(8)];
+                for (i, value) in v.iter().enumerate() {
+                    if *value != Body::Optional(None) {
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                let present = v.iter().filter(|v| **v != Body::Optional(None)).count();
+                let mut tuple = serializer.serialize_tuple(bits.len() + present)?;
+                for bits in bits.iter() {
+                    tuple.serialize_element(bits)?;
+                }
+                for value in v.iter() {
+                    match value {
+                        Body::Optional(None) => {}
+                        Body::Optional(Some(value)) => tuple.serialize_element(value)?,
+                        value => tuple.serialize_element(value)?,
+                    }
+                }
+                tuple.end()
+            }
+            // Rows of tuples are written column by column, each column prefixed with its byte
+            // length, anything else is written row by row.
+            Body::Columnar(v) => match v.first() {
+                Some(Body::Tuple(first)) => {
+                    let mut tuple = serializer.serialize_tuple(1 + first.len())?;
+                    tuple.serialize_element(&v.len())?;
+                    for index in 0..first.len() {
+                        tuple.serialize_element(&LengthPrefixed(Column {
+                            rows: v,
+                            width: first.len(),
+                            index,
+                        }))?;
+                    }
+                    tuple.end()
+                }
+                _ => v.serialize(serializer),
+            },
+            // Integers after the first are written as the zigzag difference from the previous
+            // one, anything else is written as a plain array.
+            Body::DeltaArray(v) => match v.iter().map(Body::integer_bits).collect::<Option<Vec<_>>>() {
+                Some(bits) if !v.is_empty() => {
+                    let mut tuple = serializer.serialize_tuple(1 + v.len())?;
+                    tuple.serialize_element(&v.len())?;
+                    tuple.serialize_element(&v[0])?;
+                    for pair in bits.windows(2) {
+                        tuple.serialize_element(&(pair[1].wrapping_sub(pair[0]) as i64))?;
+                    }
+                    tuple.end()
+                }
+                _ => v.serialize(serializer),
+            },
+            // Integers are written as (value, run length) pairs, anything else is written as a
+            // plain array.
+            Body::RleArray(v) => match v.iter().map(Body::integer_bits).collect::<Option<Vec<_>>>() {
+                Some(bits) if !v.is_empty() => {
+                    let mut runs = Vec::new();
+                    let mut start = 0;
+                    for i in 1..=bits.len() {
+                        if i == bits.len() || bits[i] != bits[start] {
+                            runs.push((start, (i - start) as u64));
+                            start = i;
+                        }
+                    }
+                    let mut tuple = serializer.serialize_tuple(1 + runs.len() * 2)?;
+                    tuple.serialize_element(&v.len())?;
+                    for (start, run) in runs {
+                        tuple.serialize_element(&v[start])?;
+                        tuple.serialize_element(&run)?;
+                    }
+                    tuple.end()
+                }
+                _ => v.serialize(serializer),
+            },
+            // Written as a single chunk, followed by the empty chunk that ends the array.
+            Body::SegmentedArray(v) => {
+                let mut tuple = serializer.serialize_tuple(v.len() + 2)?;
+                if !v.is_empty() {
+                    tuple.serialize_element(&v.len())?;
+                    for v in v.iter() {
+                        tuple.serialize_element(v)?;
+                    }
+                }
+                tuple.serialize_element(&0usize)?;
+                tuple.end()
+            }
             Body::Map(v) => v.serialize(serializer),
+            Body::OrderedMap(v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (key, value) in v.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
             Body::Enum(i, v) => serializer.serialize_newtype_variant("", *i, "", v),
+            Body::OpenEnum(i, v) => {
+                serializer.serialize_newtype_variant("", *i, "", &LengthPrefixed(v))
+            }
+            Body::UnknownVariant(i, v) => {
+                serializer.serialize_newtype_variant("", *i, "", Bytes::new(v))
+            }
             Body::Date(v) => v.serialize(serializer),
             Body::DateTime(v) => v.serialize(serializer),
+            Body::Duration(v) => v.serialize(serializer),
+            Body::SystemTime(v) => v.serialize(serializer),
+            Body::DurationNanos128(v) => v.serialize(serializer),
+        }
+    }
+}
+
+// Names a value that the dlhn serializer writes prefixed with its byte length, encoded with the
+// serializer's own configuration. Other serializers see a plain newtype struct.
+pub(crate) const LENGTH_PREFIXED_NAME: &str = "$dlhn::LengthPrefixed";
+
+struct LengthPrefixed<T>(T);
+
+impl<T: Serialize> Serialize for LengthPrefixed<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(LENGTH_PREFIXED_NAME, &self.0)
+    }
+}
+
+// The values of field `index` across the rows of a `Body::Columnar`.
+struct Column<'a> {
+    rows: &'a [Body],
+    width: usize,
+    index: usize,
+}
+
+impl Serialize for Column<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(self.rows.len())?;
+        for row in self.rows.iter() {
+            match row {
+                Body::Tuple(row) if row.len() == self.width => {
+                    tuple.serialize_element(&row[self.index])?
+                }
+                _ => return Err(ser::Error::custom("columnar rows differ in shape")),
+            }
         }
+        tuple.end()
     }
 }
 
@@ -80,14 +278,77 @@ impl Body {
     pub fn deserialize<R: Read>(
         header: &Header,
         deserializer: &mut Deserializer<R>,
+    ) -> Result<Self, crate::de::Error> {
+        deserializer.with_header(header, |de| Self::deserialize_inner(header, de, false))
+    }
+
+    pub fn deserialize_ordered<R: Read>(
+        header: &Header,
+        deserializer: &mut Deserializer<R>,
+    ) -> Result<Self, crate::de::Error> {
+        deserializer.with_header(header, |de| Self::deserialize_inner(header, de, true))
+    }
+
+    pub fn deserialize_into<R: Read>(
+        header: &Header,
+        deserializer: &mut Deserializer<R>,
+        buf: &mut Vec<Body>,
+    ) -> Result<(), crate::de::Error> {
+        deserializer.with_header(header, |de| Self::deserialize_into_inner(header, de, buf))
+    }
+
+    // `deserialize_into` below the top-level header, counting depth like `deserialize_inner`.
+    fn deserialize_into_inner<R: Read>(
+        header: &Header,
+        deserializer: &mut Deserializer<R>,
+        buf: &mut Vec<Body>,
+    ) -> Result<(), crate::de::Error> {
+        deserializer.enter()?;
+        let result = match header {
+            Header::Array(inner) => deserializer.deserialize_seq_len().and_then(|len| {
+                buf.clear();
+                buf.reserve(min(len, MAX_PREALLOCATION) as usize);
+                for _ in 0..len {
+                    buf.push(Self::deserialize_inner(inner, deserializer, false)?);
+                }
+                Ok(())
+            }),
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) => {
+                Self::deserialize_into_inner(inner, deserializer, buf)
+            }
+            _ => Err(Error::Message(
+                "deserialize_into requires an array header".to_string(),
+            )),
+        };
+        deserializer.leave();
+        result
+    }
+
+    fn deserialize_inner<R: Read>(
+        header: &Header,
+        deserializer: &mut Deserializer<R>,
+        ordered: bool,
+    ) -> Result<Self, crate::de::Error> {
+        deserializer.enter()?;
+        let result = Self::deserialize_value(header, deserializer, ordered);
+        deserializer.leave();
+        result
+    }
+
+    fn deserialize_value<R: Read>(
+        header: &Header,
+        deserializer: &mut Deserializer<R>,
+        ordered: bool,
     ) -> Result<Self, crate::de::Error> {
         match header {
             Header::Unit => Ok(Self::Unit),
+            Header::Null => Ok(Self::Null),
             Header::Optional(inner) => {
                 if bool::deserialize(&mut *deserializer)? {
-                    Ok(Self::Optional(Some(Box::new(Self::deserialize(
+                    Ok(Self::Optional(Some(Box::new(Self::deserialize_inner(
                         inner,
                         deserializer,
+                        ordered,
                     )?))))
                 } else {
                     Ok(Self::Optional(None))
@@ -106,26 +367,175 @@ impl Body {
             // Header::Int128 => i128::deserialize(deserializer).map(Self::Int128),
             Header::Float32 => f32::deserialize(deserializer).map(Self::Float32),
             Header::Float64 => f64::deserialize(deserializer).map(Self::Float64),
+            Header::FixedPoint { scale } => i64::deserialize(deserializer)
+                .map(|v| Self::FixedPoint(fixed_point::decode(v, *scale), *scale)),
+            Header::Decimal { scale } => {
+                i128::deserialize(deserializer).map(|v| Self::Decimal(v, *scale))
+            }
             Header::BigUInt => BigUint::deserialize(deserializer).map(Self::BigUInt),
             Header::BigInt => BigInt::deserialize(deserializer).map(Self::BigInt),
-            Header::BigDecimal => BigDecimal::deserialize(deserializer).map(Self::BigDecimal),
+            Header::BigDecimal => {
+                LimitedBigDecimal::deserialize(deserializer).map(|v| Self::BigDecimal(v.0))
+            }
             Header::String => String::deserialize(deserializer).map(Self::String),
+            Header::InternedString => deserializer.deserialize_interned_string().map(Self::String),
             Header::Binary => ByteBuf::deserialize(deserializer).map(|v| Self::Binary(v)),
+            Header::Array(inner) if **inner == Header::Boolean => Ok(Self::Array(
+                deserializer
+                    .deserialize_bool_vec()?
+                    .into_iter()
+                    .map(Self::Boolean)
+                    .collect(),
+            )),
+            // Fixed-width elements are read in bulk rather than one `Body` decode at a time.
+            Header::Array(inner) if **inner == Header::UInt8 => Ok(Self::Array(
+                deserializer.deserialize_fixed_width_vec(|v| Self::UInt8(u8::from_le_bytes(v)))?,
+            )),
+            Header::Array(inner) if **inner == Header::Int8 => Ok(Self::Array(
+                deserializer.deserialize_fixed_width_vec(|v| Self::Int8(i8::from_le_bytes(v)))?,
+            )),
+            Header::Array(inner) if **inner == Header::Float32 => Ok(Self::Array(
+                deserializer
+                    .deserialize_fixed_width_vec(|v| Self::Float32(f32::from_le_bytes(v)))?,
+            )),
+            Header::Array(inner) if **inner == Header::Float64 => Ok(Self::Array(
+                deserializer
+                    .deserialize_fixed_width_vec(|v| Self::Float64(f64::from_le_bytes(v)))?,
+            )),
             Header::Array(inner) => {
-                let len = u64::deserialize(&mut *deserializer)?;
-                let mut buf = Vec::with_capacity(len as usize);
+                let len = deserializer.deserialize_seq_len()?;
+                let mut buf = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
                 for _ in 0..len {
-                    buf.push(Self::deserialize(inner, deserializer)?);
+                    buf.push(Self::deserialize_inner(inner, deserializer, ordered)?);
                 }
                 Ok(Self::Array(buf))
             }
             Header::Tuple(inner) => {
                 let mut buf = Vec::with_capacity(inner.len());
                 for inner in inner.iter() {
-                    buf.push(Self::deserialize(inner, deserializer)?);
+                    buf.push(Self::deserialize_inner(inner, deserializer, ordered)?);
                 }
                 Ok(Self::Tuple(buf))
             }
+            Header::BitmapStruct(inner) => {
+                let presence = deserializer.deserialize_presence_bitmap(inner.len())?;
+                let mut buf = Vec::with_capacity(inner.len());
+                for (inner, present) in inner.iter().zip(presence) {
+                    buf.push(match inner.bitmap_optional() {
+                        Some(_) if !present => Self::Optional(None),
+                        Some(inner) => Self::Optional(Some(Box::new(Self::deserialize_inner(
+                            inner,
+                            deserializer,
+                            ordered,
+                        )?))),
+                        None => Self::deserialize_inner(inner, deserializer, ordered)?,
+                    });
+                }
+                Ok(Self::BitmapStruct(buf))
+            }
+            Header::Columnar(inner) => {
+                let len = deserializer.deserialize_seq_len()?;
+                let fields = match inner.columns() {
+                    Some(fields) if len > 0 => fields,
+                    _ => {
+                        let mut buf = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
+                        for _ in 0..len {
+                            buf.push(Self::deserialize_inner(inner, deserializer, ordered)?);
+                        }
+                        return Ok(Self::Columnar(buf));
+                    }
+                };
+                let mut columns = Vec::with_capacity(fields.len());
+                for field in fields.iter() {
+                    let size = u64::deserialize(&mut *deserializer)?;
+                    let start = deserializer.offset();
+                    let mut column = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
+                    for _ in 0..len {
+                        column.push(Self::deserialize_inner(field, deserializer, ordered)?);
+                    }
+                    if deserializer.offset() - start != size {
+                        return Err(Error::Read {
+                            offset: deserializer.offset(),
+                        });
+                    }
+                    columns.push(column.into_iter());
+                }
+                Ok(Self::Columnar(
+                    (0..len)
+                        .map(|_| Self::Tuple(columns.iter_mut().flat_map(|v| v.next()).collect()))
+                        .collect(),
+                ))
+            }
+            Header::DeltaArray(inner) => {
+                let len = deserializer.deserialize_seq_len()?;
+                let mut buf = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
+                if !inner.is_delta_integer() {
+                    for _ in 0..len {
+                        buf.push(Self::deserialize_inner(inner, deserializer, ordered)?);
+                    }
+                    return Ok(Self::DeltaArray(buf));
+                }
+                let mut prev: Option<u64> = None;
+                for _ in 0..len {
+                    let body = match prev {
+                        Some(bits) => {
+                            let delta = i64::deserialize(&mut *deserializer)?;
+                            Self::from_integer_bits(inner, bits.wrapping_add(delta as u64)).ok_or(
+                                Error::Read {
+                                    offset: deserializer.offset(),
+                                },
+                            )?
+                        }
+                        None => Self::deserialize_inner(inner, deserializer, ordered)?,
+                    };
+                    prev = body.integer_bits();
+                    buf.push(body);
+                }
+                Ok(Self::DeltaArray(buf))
+            }
+            Header::RleArray(inner) => {
+                let len = deserializer.deserialize_seq_len()?;
+                let mut buf = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
+                if !inner.is_delta_integer() {
+                    for _ in 0..len {
+                        buf.push(Self::deserialize_inner(inner, deserializer, ordered)?);
+                    }
+                    return Ok(Self::RleArray(buf));
+                }
+                let mut read = 0u64;
+                while read < len {
+                    let body = Self::deserialize_inner(inner, deserializer, ordered)?;
+                    let run = u64::deserialize(&mut *deserializer)?;
+                    read = match read.checked_add(run) {
+                        Some(read) if run > 0 && read <= len => read,
+                        _ => {
+                            return Err(Error::Read {
+                                offset: deserializer.offset(),
+                            })
+                        }
+                    };
+                    // A run is expanded without reading more input, so a forged one must fail
+                    // rather than abort on allocation.
+                    let run = usize::try_from(run).map_err(|_| Error::SeqLengthLimit)?;
+                    buf.try_reserve(run).map_err(|_| Error::SeqLengthLimit)?;
+                    buf.extend(iter::repeat_n(body, run));
+                }
+                Ok(Self::RleArray(buf))
+            }
+            Header::SegmentedArray(inner) => {
+                let mut buf = Vec::new();
+                let mut total = 0;
+                loop {
+                    let len = deserializer.deserialize_segment_len(&mut total)?;
+                    if len == 0 {
+                        return Ok(Self::SegmentedArray(buf));
+                    }
+                    buf.reserve(min(len, MAX_PREALLOCATION) as usize);
+                    for _ in 0..len {
+                        buf.push(Self::deserialize_inner(inner, deserializer, ordered)?);
+                    }
+                }
+            }
             // Header::Struct(inner) => {
             //     let mut buf = Vec::with_capacity(inner.len());
             //     for inner in inner.iter() {
@@ -133,33 +543,132 @@ impl Body {
             //     }
             //     Ok(Self::Struct(buf))
             // }
+            Header::Map(inner) if ordered => {
+                let len = deserializer.deserialize_map_len()?;
+                let mut buf = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
+                let mut prev = None;
+                for _ in 0..len {
+                    buf.push((
+                        deserializer.deserialize_map_key(&mut prev)?,
+                        Self::deserialize_inner(inner, deserializer, ordered)?,
+                    ));
+                }
+                Ok(Self::OrderedMap(buf))
+            }
+            Header::FlattenedMap(fields, extra) if ordered => {
+                let len = deserializer.deserialize_map_len()?;
+                let mut buf = Vec::with_capacity(min(len, MAX_PREALLOCATION) as usize);
+                let mut prev = None;
+                for _ in 0..len {
+                    let key = deserializer.deserialize_map_key(&mut prev)?;
+                    let inner = Header::flattened_value(fields, extra, &key);
+                    buf.push((key, Self::deserialize_inner(inner, deserializer, ordered)?));
+                }
+                Ok(Self::OrderedMap(buf))
+            }
+            Header::FlattenedMap(fields, extra) => {
+                let len = deserializer.deserialize_map_len()?;
+                let mut buf = BTreeMap::new();
+                let mut prev = None;
+                for _ in 0..len {
+                    let key = deserializer.deserialize_map_key(&mut prev)?;
+                    let inner = Header::flattened_value(fields, extra, &key);
+                    let value = Self::deserialize_inner(inner, deserializer, ordered)?;
+                    buf.insert(key, value);
+                }
+                Ok(Self::Map(buf))
+            }
             Header::Map(inner) => {
-                let len = u64::deserialize(&mut *deserializer)?;
+                let len = deserializer.deserialize_map_len()?;
                 let mut buf = BTreeMap::new();
+                let mut prev = None;
                 for _ in 0..len {
                     buf.insert(
-                        String::deserialize(&mut *deserializer)?,
-                        Self::deserialize(inner, deserializer)?,
+                        deserializer.deserialize_map_key(&mut prev)?,
+                        Self::deserialize_inner(inner, deserializer, ordered)?,
                     );
                 }
                 Ok(Self::Map(buf))
             }
             Header::Enum(inner) => {
-                let i = u32::deserialize(&mut *deserializer)?;
-                let inner = inner.get(i as usize).ok_or(Error::Read)?;
+                let i = deserializer.deserialize_variant_index()?;
+                let inner = inner.get(i as usize).ok_or(Error::Read {
+                    offset: deserializer.offset(),
+                })?;
                 Ok(Self::Enum(
                     i,
-                    Box::new(Self::deserialize(inner, deserializer)?),
+                    Box::new(Self::deserialize_inner(inner, deserializer, ordered)?),
                 ))
             }
+            Header::OpenEnum(inner) => {
+                let i = deserializer.deserialize_variant_index()?;
+                let size = deserializer.deserialize_binary_len()?;
+                let inner = match inner.get(i as usize) {
+                    Some(inner) => inner,
+                    None => return Ok(Self::UnknownVariant(i, deserializer.read_bytes(size)?)),
+                };
+                let start = deserializer.offset();
+                let body = Self::deserialize_inner(inner, deserializer, ordered)?;
+                if deserializer.offset() - start != size {
+                    return Err(Error::Read {
+                        offset: deserializer.offset(),
+                    });
+                }
+                Ok(Self::OpenEnum(i, Box::new(body)))
+            }
             Header::Date => Date::deserialize(deserializer).map(Self::Date),
             Header::DateTime => DateTime::deserialize(deserializer).map(Self::DateTime),
+            Header::Duration => Duration::deserialize(deserializer).map(Self::Duration),
+            Header::SystemTime => SystemTime::deserialize(deserializer).map(Self::SystemTime),
+            Header::DurationNanos128 => i128::deserialize(deserializer).map(Self::DurationNanos128),
+            Header::TypedBinary => Ok(Self::TypedBinary(
+                u16::deserialize(&mut *deserializer)?,
+                ByteBuf::deserialize(deserializer)?,
+            )),
+            // A reserved slot keeps its value here so the body still round-trips; typed decoding
+            // through `Deserializer::deserialize_guided` is what drops it.
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) | Header::Reserved(inner) => {
+                Self::deserialize_inner(inner, deserializer, ordered)
+            }
+        }
+    }
+
+    // Integers widened to 64 bits, so the differences in a delta array wrap instead of overflowing.
+    fn integer_bits(&self) -> Option<u64> {
+        match self {
+            Body::UInt8(v) => Some(*v as u64),
+            Body::UInt16(v) => Some(*v as u64),
+            Body::UInt32(v) => Some(*v as u64),
+            Body::UInt64(v) => Some(*v),
+            Body::Int8(v) => Some(*v as i64 as u64),
+            Body::Int16(v) => Some(*v as i64 as u64),
+            Body::Int32(v) => Some(*v as i64 as u64),
+            Body::Int64(v) => Some(*v as u64),
+            _ => None,
+        }
+    }
+
+    fn from_integer_bits(header: &Header, bits: u64) -> Option<Self> {
+        match header {
+            Header::UInt8 => u8::try_from(bits).ok().map(Self::UInt8),
+            Header::UInt16 => u16::try_from(bits).ok().map(Self::UInt16),
+            Header::UInt32 => u32::try_from(bits).ok().map(Self::UInt32),
+            Header::UInt64 => Some(Self::UInt64(bits)),
+            Header::Int8 => i8::try_from(bits as i64).ok().map(Self::Int8),
+            Header::Int16 => i16::try_from(bits as i64).ok().map(Self::Int16),
+            Header::Int32 => i32::try_from(bits as i64).ok().map(Self::Int32),
+            Header::Int64 => Some(Self::Int64(bits as i64)),
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) => {
+                Self::from_integer_bits(inner, bits)
+            }
+            _ => None,
         }
     }
 
     pub fn validate(&self, header: &Header) -> bool {
         match (header, self) {
             (Header::Unit, Body::Unit) => true,
+            (Header::Null, Body::Null) => true,
             (Header::Optional(inner_header), Body::Optional(inner_body)) => {
                 if let Some(v) = inner_body {
                     v.validate(inner_header)
@@ -178,15 +687,24 @@ impl Body {
             (Header::Int64, Body::Int64(_)) => true,
             (Header::Float32, Body::Float32(_)) => true,
             (Header::Float64, Body::Float64(_)) => true,
+            (Header::FixedPoint { scale }, Body::FixedPoint(_, v)) => scale == v,
+            (Header::Decimal { scale }, Body::Decimal(_, v)) => scale == v,
             (Header::BigUInt, Body::BigUInt(_)) => true,
             (Header::BigInt, Body::BigInt(_)) => true,
             (Header::BigDecimal, Body::BigDecimal(_)) => true,
             (Header::String, Body::String(_)) => true,
+            (Header::InternedString, Body::String(_)) => true,
             (Header::Binary, Body::Binary(_)) => true,
-            (Header::Array(inner_header), Body::Array(inner_body)) => {
+            (Header::Binary, Body::Path(_)) => true,
+            (Header::Array(inner_header), Body::Array(inner_body))
+            | (Header::Columnar(inner_header), Body::Columnar(inner_body))
+            | (Header::DeltaArray(inner_header), Body::DeltaArray(inner_body))
+            | (Header::RleArray(inner_header), Body::RleArray(inner_body))
+            | (Header::SegmentedArray(inner_header), Body::SegmentedArray(inner_body)) => {
                 inner_body.iter().all(|v| v.validate(inner_header))
             }
-            (Header::Tuple(inner_headers), Body::Tuple(inner_bodies)) => {
+            (Header::Tuple(inner_headers), Body::Tuple(inner_bodies))
+            | (Header::BitmapStruct(inner_headers), Body::BitmapStruct(inner_bodies)) => {
                 inner_headers.len() == inner_bodies.len()
                     && inner_headers
                         .iter()
@@ -203,6 +721,15 @@ impl Body {
             (Header::Map(inner_header), Body::Map(inner_body)) => inner_body
                 .values()
                 .all(|value| value.validate(inner_header)),
+            (Header::Map(inner_header), Body::OrderedMap(inner_body)) => inner_body
+                .iter()
+                .all(|(_, value)| value.validate(inner_header)),
+            (Header::FlattenedMap(fields, extra), Body::Map(inner_body)) => inner_body
+                .iter()
+                .all(|(key, value)| value.validate(Header::flattened_value(fields, extra, key))),
+            (Header::FlattenedMap(fields, extra), Body::OrderedMap(inner_body)) => inner_body
+                .iter()
+                .all(|(key, value)| value.validate(Header::flattened_value(fields, extra, key))),
             (Header::Enum(inner_header), Body::Enum(i, v)) => {
                 if let Some(header) = inner_header.get(*i as usize) {
                     v.validate(header)
@@ -210,11 +737,131 @@ impl Body {
                     false
                 }
             }
+            (Header::OpenEnum(inner_header), Body::OpenEnum(i, v)) => {
+                if let Some(header) = inner_header.get(*i as usize) {
+                    v.validate(header)
+                } else {
+                    false
+                }
+            }
+            (Header::OpenEnum(inner_header), Body::UnknownVariant(i, _)) => {
+                *i as usize >= inner_header.len()
+            }
             (Header::Date, Body::Date(_)) => true,
             (Header::DateTime, Body::DateTime(_)) => true,
+            (Header::Duration, Body::Duration(_)) => true,
+            (Header::SystemTime, Body::SystemTime(_)) => true,
+            (Header::TypedBinary, Body::TypedBinary(..)) => true,
+            (Header::DurationNanos128, Body::DurationNanos128(_)) => true,
+            (Header::Annotated(inner_header, _), body)
+            | (Header::Tagged(inner_header, _), body)
+            | (Header::Reserved(inner_header), body) => body.validate(inner_header),
             _ => false,
         }
     }
+
+    // Compares values while ignoring differences in representation: map key order (including a
+    // `Map` against an `OrderedMap`), decimal and fixed-point scale, `-0.0` against `0.0`, and NaN
+    // payloads.
+    pub fn semantically_eq(&self, other: &Body) -> bool {
+        fn all_eq(a: &[Body], b: &[Body]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.semantically_eq(b))
+        }
+
+        match (self, other) {
+            (Body::Optional(Some(a)), Body::Optional(Some(b))) => a.semantically_eq(b),
+            (Body::Float32(a), Body::Float32(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Body::Float64(a), Body::Float64(b))
+            | (Body::FixedPoint(a, _), Body::FixedPoint(b, _)) => {
+                a == b || (a.is_nan() && b.is_nan())
+            }
+            (Body::BigDecimal(a), Body::BigDecimal(b)) => a.value_eq(b),
+            (Body::Array(a), Body::Array(b))
+            | (Body::Tuple(a), Body::Tuple(b))
+            | (Body::BitmapStruct(a), Body::BitmapStruct(b))
+            | (Body::Columnar(a), Body::Columnar(b))
+            | (Body::DeltaArray(a), Body::DeltaArray(b))
+            | (Body::RleArray(a), Body::RleArray(b))
+            | (Body::SegmentedArray(a), Body::SegmentedArray(b)) => all_eq(a, b),
+            (Body::Enum(i, a), Body::Enum(j, b)) | (Body::OpenEnum(i, a), Body::OpenEnum(j, b)) => {
+                i == j && a.semantically_eq(b)
+            }
+            (a, b) => match (a.map_entries(), b.map_entries()) {
+                (Some(a), Some(b)) => {
+                    a.len() == b.len()
+                        && a.iter()
+                            .all(|(key, v)| b.get(key).is_some_and(|w| v.semantically_eq(w)))
+                }
+                _ => a == b,
+            },
+        }
+    }
+
+    // The entries of a map by key, or `None` for anything else. An `OrderedMap` with a duplicate
+    // key yields fewer entries than it holds, so it never equals a map without the duplicate.
+    fn map_entries(&self) -> Option<BTreeMap<&str, &Body>> {
+        match self {
+            Body::Map(v) => Some(v.iter().map(|(k, v)| (k.as_str(), v)).collect()),
+            Body::OrderedMap(v) => {
+                let entries = v
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v))
+                    .collect::<BTreeMap<_, _>>();
+                if entries.len() == v.len() {
+                    Some(entries)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Applies `patch` as a partial update. Maps merge key by key, recursing into keys present on
+    // both sides and appending new ones. Tuples of the same length (structs) merge field by field,
+    // where a patch field of `Optional(None)` means unset and keeps the base field. Optionals and
+    // enums of the same variant merge their inner values. Everything else, including scalars,
+    // arrays and values whose shapes differ, is replaced by the patch.
+    pub fn merge(&mut self, patch: &Body) {
+        match (self, patch) {
+            (Body::Map(base), Body::Map(patch)) => {
+                for (key, value) in patch.iter() {
+                    match base.get_mut(key) {
+                        Some(base) => base.merge(value),
+                        None => {
+                            base.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            (Body::OrderedMap(base), Body::OrderedMap(patch)) => {
+                for (key, value) in patch.iter() {
+                    match base.iter_mut().find(|(k, _)| k == key) {
+                        Some((_, base)) => base.merge(value),
+                        None => base.push((key.clone(), value.clone())),
+                    }
+                }
+            }
+            (Body::Tuple(base), Body::Tuple(patch))
+            | (Body::BitmapStruct(base), Body::BitmapStruct(patch))
+                if base.len() == patch.len() =>
+            {
+                for (base, value) in base.iter_mut().zip(patch) {
+                    if *value != Body::Optional(None) {
+                        base.merge(value);
+                    }
+                }
+            }
+            (Body::Optional(Some(base)), Body::Optional(Some(patch))) => base.merge(patch),
+            (Body::Enum(i, base), Body::Enum(j, patch))
+            | (Body::OpenEnum(i, base), Body::OpenEnum(j, patch))
+                if i == j =>
+            {
+                base.merge(patch)
+            }
+            (base, patch) => *base = patch.clone(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -233,12 +880,17 @@ mod tests {
         use super::*;
         #[cfg(feature = "bigdecimal")]
         use crate::big_decimal::BigDecimal;
+        use crate::fixed_point::FixedPoint;
         #[cfg(all(feature = "num-traits", feature = "num-bigint"))]
         use crate::{big_int::BigInt, big_uint::BigUint};
         #[cfg(feature = "time")]
         use crate::{date::Date, date_time::DateTime};
+        use crate::{decimal::Decimal, ser::SortMode, Config};
         use serde_bytes::ByteBuf;
-        use std::collections::BTreeMap;
+        use std::{
+            collections::BTreeMap,
+            sync::atomic::{AtomicUsize, Ordering},
+        };
         #[cfg(feature = "time")]
         use time::{Month, OffsetDateTime};
 
@@ -375,6 +1027,24 @@ mod tests {
             assert_eq!(serialize(Body::Float64(-f64::NAN)), serialize(-f64::NAN));
         }
 
+        #[test]
+        fn serialize_fixed_point() {
+            assert_eq!(
+                serialize(Body::FixedPoint(12.34, 2)),
+                serialize(FixedPoint::<2>(12.34))
+            );
+            assert_eq!(serialize(Body::FixedPoint(12.34, 2)), serialize(1234i64));
+        }
+
+        #[test]
+        fn serialize_decimal() {
+            assert_eq!(
+                serialize(Body::Decimal(123456, 2)),
+                serialize(Decimal::<2>(123456))
+            );
+            assert_eq!(serialize(Body::Decimal(-1999, 2)), serialize(-1999i128));
+        }
+
         #[cfg(all(feature = "num-traits", feature = "num-bigint"))]
         #[test]
         fn serialize_big_uint() {
@@ -482,6 +1152,14 @@ mod tests {
             );
         }
 
+        #[test]
+        fn serialize_path() {
+            assert_eq!(
+                serialize(Body::Path(std::path::PathBuf::from("a/b"))),
+                serialize(ByteBuf::from(b"a/b".to_vec()))
+            );
+        }
+
         #[test]
         fn serialize_array() {
             assert_eq!(
@@ -549,6 +1227,181 @@ mod tests {
             );
         }
 
+        #[test]
+        fn serialize_ref_without_clone() {
+            static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+            struct Counted(Body);
+
+            impl Clone for Counted {
+                fn clone(&self) -> Self {
+                    CLONES.fetch_add(1, Ordering::SeqCst);
+                    Counted(self.0.clone())
+                }
+            }
+
+            impl Serialize for Counted {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    self.0.serialize(serializer)
+                }
+            }
+
+            let untouched = Counted(Body::Array((0..10_000).map(Body::UInt32).collect()));
+            let edited = Body::String("edited".to_string());
+            let buf = serialize((&untouched, &edited));
+            assert_eq!(CLONES.load(Ordering::SeqCst), 0);
+
+            assert_eq!(buf, serialize(Body::Tuple(vec![untouched.0, edited])));
+        }
+
+        // A `Body`-shaped tree whose every node counts its clones, serialized the same way `Body`
+        // is, so a serializer path that clones a subtree instead of borrowing it shows up here.
+        #[test]
+        fn serialize_nested_without_clone() {
+            use serde::ser::SerializeTuple;
+
+            static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+            enum Tracked {
+                Leaf(Body),
+                Optional(Option<Box<Tracked>>),
+                Array(Vec<Tracked>),
+                Tuple(Vec<Tracked>),
+                Map(BTreeMap<String, Tracked>),
+                Enum(u32, Box<Tracked>),
+            }
+
+            impl Clone for Tracked {
+                fn clone(&self) -> Self {
+                    CLONES.fetch_add(1, Ordering::SeqCst);
+                    match self {
+                        Tracked::Leaf(v) => Tracked::Leaf(v.clone()),
+                        Tracked::Optional(v) => Tracked::Optional(v.clone()),
+                        Tracked::Array(v) => Tracked::Array(v.clone()),
+                        Tracked::Tuple(v) => Tracked::Tuple(v.clone()),
+                        Tracked::Map(v) => Tracked::Map(v.clone()),
+                        Tracked::Enum(i, v) => Tracked::Enum(*i, v.clone()),
+                    }
+                }
+            }
+
+            impl Serialize for Tracked {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    match self {
+                        Tracked::Leaf(v) => v.serialize(serializer),
+                        Tracked::Optional(v) => v.serialize(serializer),
+                        Tracked::Array(v) => v.serialize(serializer),
+                        Tracked::Tuple(v) => {
+                            let mut tuple = serializer.serialize_tuple(v.len())?;
+                            for v in v.iter() {
+                                tuple.serialize_element(v)?;
+                            }
+                            tuple.end()
+                        }
+                        Tracked::Map(v) => v.serialize(serializer),
+                        Tracked::Enum(i, v) => serializer.serialize_newtype_variant("", *i, "", v),
+                    }
+                }
+            }
+
+            impl Tracked {
+                fn to_body(&self) -> Body {
+                    match self {
+                        Tracked::Leaf(v) => v.clone(),
+                        Tracked::Optional(v) => {
+                            Body::Optional(v.as_ref().map(|v| Box::new(v.to_body())))
+                        }
+                        Tracked::Array(v) => Body::Array(v.iter().map(Tracked::to_body).collect()),
+                        Tracked::Tuple(v) => Body::Tuple(v.iter().map(Tracked::to_body).collect()),
+                        Tracked::Map(v) => Body::Map(
+                            v.iter()
+                                .map(|(key, value)| (key.clone(), value.to_body()))
+                                .collect(),
+                        ),
+                        Tracked::Enum(i, v) => Body::Enum(*i, Box::new(v.to_body())),
+                    }
+                }
+            }
+
+            let tracked = Tracked::Array(
+                (0..1_000u32)
+                    .map(|i| {
+                        Tracked::Tuple(vec![
+                            Tracked::Leaf(Body::UInt32(i)),
+                            Tracked::Map(BTreeMap::from([
+                                (
+                                    "name".to_string(),
+                                    Tracked::Optional(Some(Box::new(Tracked::Leaf(Body::String(
+                                        format!("row {}", i % 7),
+                                    ))))),
+                                ),
+                                (
+                                    "tags".to_string(),
+                                    Tracked::Enum(
+                                        i % 3,
+                                        Box::new(Tracked::Array(vec![
+                                            Tracked::Leaf(Body::Boolean(i % 2 == 0)),
+                                            Tracked::Optional(None),
+                                        ])),
+                                    ),
+                                ),
+                            ])),
+                        ])
+                    })
+                    .collect(),
+            );
+
+            for config in [
+                Config::default(),
+                Serializer::new(Vec::new())
+                    .map_sort(SortMode::Natural)
+                    .config(),
+                Serializer::new(Vec::new())
+                    .string_interning(true)
+                    .compact_enum_tags(true)
+                    .config(),
+            ] {
+                let mut buf = Vec::new();
+                tracked
+                    .serialize(&mut Serializer::with_config(&mut buf, config))
+                    .unwrap();
+                assert_eq!(CLONES.load(Ordering::SeqCst), 0);
+
+                let mut expected = Vec::new();
+                tracked
+                    .to_body()
+                    .serialize(&mut Serializer::with_config(&mut expected, config))
+                    .unwrap();
+                assert_eq!(buf, expected);
+            }
+        }
+
+        #[test]
+        fn serialize_columnar_with_config() {
+            use crate::{de::Deserializer, header::Header};
+
+            let body = Body::Columnar(vec![
+                Body::Tuple(vec![Body::String("a".to_string())]),
+                Body::Tuple(vec![Body::String("a".to_string())]),
+            ]);
+            let mut buf = Vec::new();
+            body.serialize(&mut Serializer::new(&mut buf).string_interning(true))
+                .unwrap();
+            // The column is written with the serializer's configuration, so the repeat is interned.
+            assert_eq!(buf, [2, 3, 2, b'a', 1]);
+
+            let header = Header::Columnar(Box::new(Header::Tuple(vec![Header::String])));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).string_interning(true);
+            assert_eq!(Body::deserialize(&header, &mut deserializer), Ok(body));
+        }
+
         #[cfg(feature = "time")]
         #[test]
         fn serialize_date() {
@@ -576,12 +1429,23 @@ mod tests {
         use crate::big_decimal::BigDecimal;
         #[cfg(all(feature = "num-traits", feature = "num-bigint"))]
         use crate::{big_int::BigInt, big_uint::BigUint};
-        use crate::{body::Body, de::Deserializer, header::Header, ser::Serializer};
+        use crate::{
+            body::Body,
+            de::{DecodeLimits, Deserializer, Error},
+            decimal::Decimal,
+            fixed_point::FixedPoint,
+            header::Header,
+            ser::Serializer,
+            DeserializeHeader, SerializeHeader,
+        };
         #[cfg(feature = "time")]
         use crate::{date::Date, date_time::DateTime};
         use serde::Serialize;
         use serde_bytes::ByteBuf;
-        use std::collections::BTreeMap;
+        use std::{
+            collections::BTreeMap,
+            time::{Duration, SystemTime},
+        };
         #[cfg(feature = "time")]
         use time::{Month, OffsetDateTime};
 
@@ -627,6 +1491,35 @@ mod tests {
             }
         }
 
+        #[test]
+        fn deserialize_option_none_consumes_only_tag() {
+            let buf = serialize((None::<u64>, u64::MAX, "after"));
+            assert_eq!(buf[0], 0);
+            let header = Header::Tuple(vec![
+                Header::Optional(Box::new(Header::UInt64)),
+                Header::UInt64,
+                Header::String,
+            ]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&header, &mut deserializer).unwrap(),
+                Body::Tuple(vec![
+                    Body::Optional(None),
+                    Body::UInt64(u64::MAX),
+                    Body::String("after".to_string()),
+                ])
+            );
+            assert!(reader.is_empty());
+
+            let mut reader = &buf[1..];
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&Header::UInt64, &mut deserializer).unwrap(),
+                Body::UInt64(u64::MAX)
+            );
+        }
+
         #[test]
         fn deserialize_bool() {
             {
@@ -1016,6 +1909,52 @@ mod tests {
                 });
         }
 
+        #[test]
+        fn deserialize_fixed_point() {
+            IntoIterator::into_iter([-273.15, 0f64, 21.5, 1e12]).for_each(|v| {
+                let buf = serialize(FixedPoint::<2>(v));
+                assert_eq!(
+                    Body::deserialize(
+                        &Header::FixedPoint { scale: 2 },
+                        &mut Deserializer::new(&mut buf.as_slice())
+                    )
+                    .unwrap(),
+                    Body::FixedPoint(v, 2)
+                );
+            });
+            assert!(Body::FixedPoint(1.0, 2).validate(&Header::FixedPoint { scale: 2 }));
+            assert!(!Body::FixedPoint(1.0, 3).validate(&Header::FixedPoint { scale: 2 }));
+        }
+
+        #[test]
+        fn deserialize_decimal() {
+            // Amounts in cents: 1234.56 and -19.99 are exact, unlike with `FixedPoint`.
+            let prices = (
+                Decimal::<2>(123456),
+                Decimal::<2>(-1999),
+                Decimal::<2>(i128::MAX),
+            );
+            let mut header = Vec::new();
+            <(Decimal<2>, Decimal<2>, Decimal<2>)>::serialize_header(&mut header).unwrap();
+            let header = header.as_slice().deserialize_header().unwrap();
+            let buf = serialize(prices);
+            let body =
+                Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())).unwrap();
+            assert_eq!(
+                body,
+                Body::Tuple(vec![
+                    Body::Decimal(123456, 2),
+                    Body::Decimal(-1999, 2),
+                    Body::Decimal(i128::MAX, 2),
+                ])
+            );
+            assert!(body.validate(&header));
+            assert_eq!(serialize(&body), buf);
+            assert_eq!(prices.0.to_string(), "1234.56");
+            assert_eq!(prices.1.to_string(), "-19.99");
+            assert!(!Body::Decimal(1, 3).validate(&Header::Decimal { scale: 2 }));
+        }
+
         #[cfg(all(feature = "num-traits", feature = "num-bigint"))]
         #[test]
         fn deserialize_big_uint() {
@@ -1184,6 +2123,105 @@ mod tests {
             );
         }
 
+        #[test]
+        fn deserialize_fixed_width_array() {
+            let values = (0..10_000)
+                .map(|i| i as f64 * 0.5 - 1000.0)
+                .collect::<Vec<_>>();
+            let buf = serialize(&values);
+            let header = Header::Array(Box::new(Header::Float64));
+
+            let mut reader = buf.as_slice();
+            let bulk = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+            assert!(reader.is_empty());
+
+            // `deserialize_into` decodes one element at a time.
+            let mut elements = Vec::new();
+            Body::deserialize_into(
+                &header,
+                &mut Deserializer::new(&mut buf.as_slice()),
+                &mut elements,
+            )
+            .unwrap();
+            assert_eq!(bulk, Body::Array(elements));
+            assert_eq!(
+                bulk,
+                Body::Array(values.into_iter().map(Body::Float64).collect())
+            );
+
+            for (header, body) in [
+                (Header::UInt8, Body::UInt8(u8::MAX)),
+                (Header::Int8, Body::Int8(i8::MIN)),
+                (Header::Float32, Body::Float32(-1.5)),
+            ] {
+                let body = Body::Array(vec![body; 3]);
+                let buf = serialize(body.clone());
+                assert_eq!(
+                    Body::deserialize(
+                        &Header::Array(Box::new(header)),
+                        &mut Deserializer::new(&mut buf.as_slice())
+                    ),
+                    Ok(body)
+                );
+            }
+
+            let buf = [serialize(3u64), 1.0f64.to_le_bytes().to_vec()].concat();
+            assert_eq!(
+                Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())),
+                Err(Error::Read { offset: 9 })
+            );
+        }
+
+        #[test]
+        fn deserialize_into() {
+            let header = Header::Array(Box::new(Header::UInt8));
+            let mut buf = Vec::new();
+
+            let bytes = serialize(vec![1u8, 2, 3]);
+            Body::deserialize_into(
+                &header,
+                &mut Deserializer::new(&mut bytes.as_slice()),
+                &mut buf,
+            )
+            .unwrap();
+            assert_eq!(buf, [Body::UInt8(1), Body::UInt8(2), Body::UInt8(3)]);
+
+            let bytes = serialize(vec![4u8, 5]);
+            Body::deserialize_into(
+                &header,
+                &mut Deserializer::new(&mut bytes.as_slice()),
+                &mut buf,
+            )
+            .unwrap();
+            assert_eq!(buf, [Body::UInt8(4), Body::UInt8(5)]);
+
+            assert!(Body::deserialize_into(
+                &Header::UInt8,
+                &mut Deserializer::new(&mut bytes.as_slice()),
+                &mut buf,
+            )
+            .is_err());
+
+            // The array and its elements count against `max_depth` as in `deserialize`.
+            let header = Header::Array(Box::new(Header::Array(Box::new(Header::UInt8))));
+            let bytes = serialize(vec![vec![1u8]]);
+            assert_eq!(
+                Body::deserialize_into(
+                    &header,
+                    &mut Deserializer::new(&mut bytes.as_slice()).max_depth(1),
+                    &mut buf,
+                ),
+                Err(Error::DepthLimit)
+            );
+            Body::deserialize_into(
+                &header,
+                &mut Deserializer::new(&mut bytes.as_slice()).max_depth(2),
+                &mut buf,
+            )
+            .unwrap();
+            assert_eq!(buf, [Body::Array(vec![Body::UInt8(1)])]);
+        }
+
         #[test]
         fn deserialize_tuple() {
             let body = Body::Tuple(vec![
@@ -1202,6 +2240,98 @@ mod tests {
             );
         }
 
+        #[test]
+        fn deserialize_tuple_16() {
+            type Test = (
+                bool,
+                u8,
+                i8,
+                u16,
+                i16,
+                u32,
+                i32,
+                u64,
+                i64,
+                f32,
+                f64,
+                String,
+                Option<u8>,
+                Vec<u32>,
+                (),
+                bool,
+            );
+            let header = {
+                let mut buf = Vec::new();
+                Test::serialize_header(&mut buf).unwrap();
+                buf.as_slice().deserialize_header().unwrap()
+            };
+            assert_eq!(
+                header,
+                Header::Tuple(vec![
+                    Header::Boolean,
+                    Header::UInt8,
+                    Header::Int8,
+                    Header::UInt16,
+                    Header::Int16,
+                    Header::UInt32,
+                    Header::Int32,
+                    Header::UInt64,
+                    Header::Int64,
+                    Header::Float32,
+                    Header::Float64,
+                    Header::String,
+                    Header::Optional(Box::new(Header::UInt8)),
+                    Header::Array(Box::new(Header::UInt32)),
+                    Header::Unit,
+                    Header::Boolean,
+                ])
+            );
+
+            let buf = serialize((
+                true,
+                123u8,
+                -1i8,
+                u16::MAX,
+                i16::MIN,
+                u32::MAX,
+                i32::MIN,
+                u64::MAX,
+                i64::MIN,
+                1.5f32,
+                -2.5f64,
+                "test".to_string(),
+                Some(1u8),
+                vec![1u32, 2],
+                (),
+                false,
+            ));
+            let mut reader = buf.as_slice();
+            let body = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+            assert!(reader.is_empty());
+            assert!(body.validate(&header));
+            assert_eq!(
+                body,
+                Body::Tuple(vec![
+                    Body::Boolean(true),
+                    Body::UInt8(123),
+                    Body::Int8(-1),
+                    Body::UInt16(u16::MAX),
+                    Body::Int16(i16::MIN),
+                    Body::UInt32(u32::MAX),
+                    Body::Int32(i32::MIN),
+                    Body::UInt64(u64::MAX),
+                    Body::Int64(i64::MIN),
+                    Body::Float32(1.5),
+                    Body::Float64(-2.5),
+                    Body::String("test".to_string()),
+                    Body::Optional(Some(Box::new(Body::UInt8(1)))),
+                    Body::Array(vec![Body::UInt32(1), Body::UInt32(2)]),
+                    Body::Unit,
+                    Body::Boolean(false),
+                ])
+            );
+        }
+
         // #[test]
         // fn deserialize_struct() {
         //     let body = Body::Struct(vec![
@@ -1240,6 +2370,168 @@ mod tests {
             );
         }
 
+        #[test]
+        fn deserialize_ordered_map() {
+            let body = Body::OrderedMap(vec![
+                ("c".to_string(), Body::Boolean(true)),
+                ("a".to_string(), Body::Boolean(false)),
+                ("b".to_string(), Body::Boolean(true)),
+            ]);
+            let buf = serialize(body.clone());
+            assert_eq!(
+                Body::deserialize_ordered(
+                    &Header::Map(Box::new(Header::Boolean)),
+                    &mut Deserializer::new(&mut buf.as_slice())
+                )
+                .unwrap(),
+                body
+            );
+            assert_eq!(
+                Body::deserialize(
+                    &Header::Map(Box::new(Header::Boolean)),
+                    &mut Deserializer::new(&mut buf.as_slice())
+                )
+                .unwrap(),
+                Body::Map({
+                    let mut buf = BTreeMap::new();
+                    buf.insert("a".to_string(), Body::Boolean(false));
+                    buf.insert("b".to_string(), Body::Boolean(true));
+                    buf.insert("c".to_string(), Body::Boolean(true));
+                    buf
+                })
+            );
+        }
+
+        #[test]
+        fn deserialize_annotated() {
+            let plain = Header::Tuple(vec![Header::Boolean, Header::UInt8]);
+            let annotated = Header::Annotated(
+                Box::new(Header::Tuple(vec![
+                    Header::Annotated(Box::new(Header::Boolean), "enabled".to_string()),
+                    Header::Annotated(Box::new(Header::UInt8), "retry count".to_string()),
+                ])),
+                "settings".to_string(),
+            );
+            let buf = serialize((true, 3u8));
+            let body =
+                Body::deserialize(&annotated, &mut Deserializer::new(&mut buf.as_slice())).unwrap();
+            assert_eq!(
+                body,
+                Body::deserialize(&plain, &mut Deserializer::new(&mut buf.as_slice())).unwrap()
+            );
+            assert!(body.validate(&annotated));
+            assert_eq!(serialize(body), buf);
+        }
+
+        #[test]
+        fn deserialize_tagged() {
+            let plain = Header::Tuple(vec![Header::Float64, Header::UInt32]);
+            let tagged = Header::Tuple(vec![
+                Header::Tagged(Box::new(Header::Float64), 1),
+                Header::Tagged(Box::new(Header::UInt32), 2),
+            ]);
+            let buf = serialize((1.5f64, 300u32));
+            let body =
+                Body::deserialize(&tagged, &mut Deserializer::new(&mut buf.as_slice())).unwrap();
+            assert_eq!(
+                body,
+                Body::deserialize(&plain, &mut Deserializer::new(&mut buf.as_slice())).unwrap()
+            );
+            assert!(body.validate(&tagged));
+            assert_eq!(serialize(body), buf);
+        }
+
+        #[test]
+        fn deserialize_array_count_mismatch() {
+            let header = Header::Array(Box::new(Header::UInt8));
+
+            let buf = [serialize(5u64), vec![1, 2, 3]].concat();
+            let mut reader = buf.as_slice();
+            assert_eq!(
+                Body::deserialize(&header, &mut Deserializer::new(&mut reader)),
+                Err(Error::Read { offset: 4 })
+            );
+
+            let buf = [serialize(2u64), vec![1, 2, 3]].concat();
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&header, &mut deserializer),
+                Ok(Body::Array(vec![Body::UInt8(1), Body::UInt8(2)]))
+            );
+            assert_eq!(deserializer.end(), Err(Error::TrailingBytes));
+
+            let buf = [serialize(3u64), vec![1, 2, 3]].concat();
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert!(Body::deserialize(&header, &mut deserializer).is_ok());
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+
+        #[test]
+        fn deserialize_depth_limit() {
+            let header = (0..10_000).fold(Header::Boolean, |header, _| {
+                Header::Optional(Box::new(header))
+            });
+            let buf = vec![1u8; 10_001];
+
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).max_depth(64);
+            assert_eq!(
+                Body::deserialize(&header, &mut deserializer),
+                Err(Error::DepthLimit)
+            );
+            assert_eq!(reader.len(), buf.len() - 64);
+
+            let header = (0..63).fold(Header::Boolean, |header, _| {
+                Header::Optional(Box::new(header))
+            });
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).max_depth(64);
+            assert!(Body::deserialize(&header, &mut deserializer).is_ok());
+        }
+
+        #[test]
+        fn deserialize_forged_length() {
+            // Even with the limits off, a length is only believed as far as the input goes.
+            let buf = [serialize(u64::MAX), serialize("a"), serialize(true)].concat();
+            assert_eq!(
+                Body::deserialize(
+                    &Header::Map(Box::new(Header::Boolean)),
+                    &mut Deserializer::new(&mut buf.as_slice())
+                        .decode_limits(DecodeLimits::UNLIMITED)
+                ),
+                Err(Error::Read { offset: 12 })
+            );
+            assert_eq!(
+                Body::deserialize_ordered(
+                    &Header::Map(Box::new(Header::Boolean)),
+                    &mut Deserializer::new(&mut buf.as_slice())
+                        .decode_limits(DecodeLimits::UNLIMITED)
+                ),
+                Err(Error::Read { offset: 12 })
+            );
+
+            let buf = [serialize(u64::MAX), serialize(true)].concat();
+            assert_eq!(
+                Body::deserialize(
+                    &Header::Array(Box::new(Header::Boolean)),
+                    &mut Deserializer::new(&mut buf.as_slice())
+                        .decode_limits(DecodeLimits::UNLIMITED)
+                ),
+                Err(Error::Read { offset: 10 })
+            );
+            assert_eq!(
+                Body::deserialize_into(
+                    &Header::Array(Box::new(Header::Boolean)),
+                    &mut Deserializer::new(&mut buf.as_slice())
+                        .decode_limits(DecodeLimits::UNLIMITED),
+                    &mut Vec::new()
+                ),
+                Err(Error::Read { offset: 10 })
+            );
+        }
+
         #[test]
         fn deserialize_enum() {
             let body = Body::Enum(1, Box::new(Body::UInt8(123)));
@@ -1254,6 +2546,52 @@ mod tests {
             );
         }
 
+        #[test]
+        fn deserialize_enum_without_variants() {
+            let buf = serialize(0u32);
+            assert!(Body::deserialize(
+                &Header::Enum(Vec::new()),
+                &mut Deserializer::new(&mut buf.as_slice())
+            )
+            .is_err());
+        }
+
+        #[test]
+        fn deserialize_open_enum() {
+            // Written by a peer whose header has a third variant this reader does not know yet.
+            let known = Body::OpenEnum(0, Box::new(Body::String("test".to_string())));
+            let unknown = Body::OpenEnum(
+                2,
+                Box::new(Body::Tuple(vec![Body::UInt16(300), Body::Boolean(true)])),
+            );
+            let buf = [serialize(&known), serialize(&unknown)].concat();
+            assert_eq!(buf[..7], [0, 5, 4, b't', b'e', b's', b't']);
+
+            let header = Header::OpenEnum(vec![Header::String, Header::Unit]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&header, &mut deserializer).unwrap(),
+                known
+            );
+            let body = Body::deserialize(&header, &mut deserializer).unwrap();
+            assert_eq!(body, Body::UnknownVariant(2, vec![172, 4, 1]));
+            assert!(reader.is_empty());
+            assert!(known.validate(&header));
+            assert!(body.validate(&header));
+            assert!(!Body::UnknownVariant(1, Vec::new()).validate(&header));
+
+            // The raw bytes are written back unchanged.
+            assert_eq!(serialize(&body), serialize(&unknown));
+
+            // A known variant must fill exactly the length it was written with.
+            let buf = [0, 3, 1, b'a'];
+            assert_eq!(
+                Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_ref())),
+                Err(Error::Read { offset: 4 })
+            );
+        }
+
         #[cfg(feature = "time")]
         #[test]
         fn deserialize_date() {
@@ -1285,6 +2623,69 @@ mod tests {
                 body
             );
         }
+
+        #[test]
+        fn deserialize_duration() {
+            let duration = Duration::new(90, 5);
+            let buf = serialize(duration);
+            assert_eq!(buf, serialize((90u64, 5u32)));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&Header::Duration, &mut deserializer),
+                Ok(Body::Duration(duration))
+            );
+            assert!(reader.is_empty());
+
+            let time = SystemTime::UNIX_EPOCH + duration;
+            let buf = serialize(Body::SystemTime(time));
+            assert_eq!(buf, serialize(time));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&Header::SystemTime, &mut deserializer),
+                Ok(Body::SystemTime(time))
+            );
+        }
+
+        #[test]
+        fn deserialize_duration_nanos_128() {
+            let beyond_u64 = Duration::new(u64::MAX, 999_999_999).as_nanos() as i128;
+            assert!(beyond_u64 > u64::MAX as i128);
+            for v in [0, -1, i64::MIN as i128, beyond_u64, -beyond_u64, i128::MAX] {
+                let buf = serialize(Body::DurationNanos128(v));
+                assert_eq!(buf, serialize(v));
+                let mut reader = buf.as_slice();
+                let mut deserializer = Deserializer::new(&mut reader);
+                assert_eq!(
+                    Body::deserialize(&Header::DurationNanos128, &mut deserializer),
+                    Ok(Body::DurationNanos128(v))
+                );
+                assert!(reader.is_empty());
+            }
+            assert_eq!(serialize(Body::DurationNanos128(-1)), [1]);
+        }
+
+        #[test]
+        fn deserialize_typed_binary() {
+            let png = Body::TypedBinary(1, ByteBuf::from(vec![0x89, b'P']));
+            let jpeg = Body::TypedBinary(300, ByteBuf::from(vec![0x89, b'P']));
+            let buf = serialize((&png, &jpeg));
+            assert_eq!(buf, [1, 2, 0x89, b'P', 0xac, 0x04, 2, 0x89, b'P']);
+
+            let header = Header::Tuple(vec![Header::TypedBinary, Header::TypedBinary]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let body = Body::deserialize(&header, &mut deserializer).unwrap();
+            assert!(reader.is_empty());
+            assert!(body.validate(&header));
+            assert_eq!(body, Body::Tuple(vec![png, jpeg]));
+            assert!(!Body::Binary(ByteBuf::new()).validate(&Header::TypedBinary));
+
+            let mut reader = buf.as_slice();
+            assert_eq!(Deserializer::new(&mut reader).skip_value(&header), Ok(()));
+            assert!(reader.is_empty());
+        }
     }
 
     mod validate {
@@ -1297,7 +2698,10 @@ mod tests {
         #[cfg(feature = "time")]
         use crate::{date::Date, date_time::DateTime};
         use serde_bytes::ByteBuf;
-        use std::collections::BTreeMap;
+        use std::{
+            collections::BTreeMap,
+            time::{Duration, SystemTime},
+        };
         #[cfg(feature = "time")]
         use time::{Month, OffsetDateTime};
 
@@ -1433,6 +2837,7 @@ mod tests {
         fn validate_binary() {
             let header = Header::Binary;
             assert!(Body::Binary(ByteBuf::from(vec![0, 1, 2, 3])).validate(&header));
+            assert!(Body::Path(std::path::PathBuf::from("a/b")).validate(&header));
             assert!(!Body::Unit.validate(&header));
         }
 
@@ -1512,6 +2917,21 @@ mod tests {
             assert!(!Body::Unit.validate(&header));
         }
 
+        #[test]
+        fn validate_ordered_map() {
+            let header = Header::Map(Box::new(Header::Boolean));
+            assert!(Body::OrderedMap(vec![
+                ("b".to_string(), Body::Boolean(true)),
+                ("a".to_string(), Body::Boolean(false)),
+            ])
+            .validate(&header));
+            assert!(!Body::OrderedMap(vec![
+                ("b".to_string(), Body::Boolean(true)),
+                ("a".to_string(), Body::Unit),
+            ])
+            .validate(&header));
+        }
+
         #[test]
         fn validate_enum() {
             let header = Header::Enum(vec![Header::Unit, Header::Boolean]);
@@ -1520,6 +2940,7 @@ mod tests {
             assert!(!Body::Enum(0, Box::new(Body::Boolean(true))).validate(&header));
             assert!(!Body::Enum(1, Box::new(Body::Unit)).validate(&header));
             assert!(!Body::Unit.validate(&header));
+            assert!(!Body::Enum(0, Box::new(Body::Unit)).validate(&Header::Enum(Vec::new())));
         }
 
         #[cfg(feature = "time")]
@@ -1540,5 +2961,185 @@ mod tests {
             assert!(Body::DateTime(DateTime::from(OffsetDateTime::UNIX_EPOCH)).validate(&header));
             assert!(!Body::Unit.validate(&header));
         }
+
+        #[test]
+        fn validate_duration() {
+            let header = Header::Tuple(vec![Header::String, Header::Duration]);
+            let body = Body::Tuple(vec![
+                Body::String("timeout".to_string()),
+                Body::Duration(Duration::from_millis(1500)),
+            ]);
+            assert!(body.validate(&header));
+            assert!(!Body::Tuple(vec![
+                Body::String("timeout".to_string()),
+                Body::UInt64(1500),
+            ])
+            .validate(&header));
+            assert!(!Body::Duration(Duration::ZERO).validate(&Header::SystemTime));
+            assert!(Body::SystemTime(SystemTime::UNIX_EPOCH).validate(&Header::SystemTime));
+            assert!(!Body::SystemTime(SystemTime::UNIX_EPOCH).validate(&Header::DateTime));
+        }
+    }
+
+    mod semantically_eq {
+        use super::*;
+        use std::collections::BTreeMap;
+
+        #[test]
+        fn semantically_eq_map_order() {
+            let map = Body::Map(BTreeMap::from([
+                ("a".to_string(), Body::UInt8(1)),
+                ("b".to_string(), Body::Float64(0.0)),
+            ]));
+            let ordered = Body::OrderedMap(vec![
+                ("b".to_string(), Body::Float64(-0.0)),
+                ("a".to_string(), Body::UInt8(1)),
+            ]);
+            assert_ne!(map, ordered);
+            assert!(map.semantically_eq(&ordered));
+            assert!(ordered.semantically_eq(&map));
+            assert!(Body::OrderedMap(vec![
+                ("a".to_string(), Body::UInt8(1)),
+                ("b".to_string(), Body::Float64(0.0)),
+            ])
+            .semantically_eq(&ordered));
+
+            let duplicate = Body::OrderedMap(vec![
+                ("a".to_string(), Body::UInt8(1)),
+                ("a".to_string(), Body::UInt8(1)),
+            ]);
+            assert!(!duplicate.semantically_eq(&Body::Map(BTreeMap::from([(
+                "a".to_string(),
+                Body::UInt8(1)
+            )]))));
+            assert!(
+                !map.semantically_eq(&Body::OrderedMap(vec![("a".to_string(), Body::UInt8(1))]))
+            );
+        }
+
+        #[test]
+        fn semantically_eq_numbers() {
+            let a = Body::Tuple(vec![
+                Body::Float32(-0.0),
+                Body::Optional(Some(Box::new(Body::Float64(f64::NAN)))),
+                Body::FixedPoint(1.5, 1),
+                Body::Array(vec![Body::Enum(1, Box::new(Body::Float64(0.0)))]),
+            ]);
+            let b = Body::Tuple(vec![
+                Body::Float32(0.0),
+                Body::Optional(Some(Box::new(Body::Float64(-f64::NAN)))),
+                Body::FixedPoint(1.5, 2),
+                Body::Array(vec![Body::Enum(1, Box::new(Body::Float64(-0.0)))]),
+            ]);
+            assert_ne!(a, b);
+            assert!(a.semantically_eq(&b));
+
+            assert!(!Body::Float64(1.0).semantically_eq(&Body::Float32(1.0)));
+            assert!(!Body::UInt8(1).semantically_eq(&Body::UInt16(1)));
+            assert!(!Body::Enum(0, Box::new(Body::Unit))
+                .semantically_eq(&Body::Enum(1, Box::new(Body::Unit))));
+            assert!(!Body::Array(vec![Body::Unit]).semantically_eq(&Body::Array(vec![])));
+        }
+    }
+
+    mod merge {
+        use super::*;
+        use std::collections::BTreeMap;
+
+        fn map(entries: Vec<(&str, Body)>) -> Body {
+            Body::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        }
+
+        #[test]
+        fn merge_map() {
+            let mut base = map(vec![
+                ("name", Body::String("base".to_string())),
+                (
+                    "server",
+                    map(vec![
+                        ("host", Body::String("localhost".to_string())),
+                        ("port", Body::UInt16(80)),
+                    ]),
+                ),
+                ("tags", Body::Array(vec![Body::String("a".to_string())])),
+            ]);
+            let patch = map(vec![
+                ("server", map(vec![("port", Body::UInt16(8080))])),
+                ("tags", Body::Array(vec![Body::String("b".to_string())])),
+                ("debug", Body::Boolean(true)),
+            ]);
+            base.merge(&patch);
+            assert_eq!(
+                base,
+                map(vec![
+                    ("name", Body::String("base".to_string())),
+                    (
+                        "server",
+                        map(vec![
+                            ("host", Body::String("localhost".to_string())),
+                            ("port", Body::UInt16(8080)),
+                        ]),
+                    ),
+                    ("tags", Body::Array(vec![Body::String("b".to_string())])),
+                    ("debug", Body::Boolean(true)),
+                ])
+            );
+
+            let mut base = Body::OrderedMap(vec![
+                ("b".to_string(), Body::UInt8(1)),
+                ("a".to_string(), Body::UInt8(2)),
+            ]);
+            base.merge(&Body::OrderedMap(vec![
+                ("c".to_string(), Body::UInt8(3)),
+                ("b".to_string(), Body::UInt8(4)),
+            ]));
+            assert_eq!(
+                base,
+                Body::OrderedMap(vec![
+                    ("b".to_string(), Body::UInt8(4)),
+                    ("a".to_string(), Body::UInt8(2)),
+                    ("c".to_string(), Body::UInt8(3)),
+                ])
+            );
+        }
+
+        #[test]
+        fn merge_struct() {
+            let mut base = Body::Tuple(vec![
+                Body::String("base".to_string()),
+                Body::Optional(Some(Box::new(Body::UInt32(1)))),
+                Body::Tuple(vec![Body::Boolean(false), Body::UInt8(1)]),
+                Body::Enum(0, Box::new(Body::UInt8(1))),
+            ]);
+            let patch = Body::Tuple(vec![
+                Body::Optional(None),
+                Body::Optional(Some(Box::new(Body::UInt32(2)))),
+                Body::Tuple(vec![Body::Optional(None), Body::UInt8(2)]),
+                Body::Enum(1, Box::new(Body::Unit)),
+            ]);
+            base.merge(&patch);
+            assert_eq!(
+                base,
+                Body::Tuple(vec![
+                    Body::String("base".to_string()),
+                    Body::Optional(Some(Box::new(Body::UInt32(2)))),
+                    Body::Tuple(vec![Body::Boolean(false), Body::UInt8(2)]),
+                    Body::Enum(1, Box::new(Body::Unit)),
+                ])
+            );
+
+            // Values whose shapes differ are replaced rather than merged.
+            let mut base = Body::Tuple(vec![Body::UInt8(1)]);
+            base.merge(&Body::Tuple(vec![Body::UInt8(2), Body::UInt8(3)]));
+            assert_eq!(base, Body::Tuple(vec![Body::UInt8(2), Body::UInt8(3)]));
+            let mut base = Body::UInt8(1);
+            base.merge(&map(vec![]));
+            assert_eq!(base, map(vec![]));
+        }
     }
 }
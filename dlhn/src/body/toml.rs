@@ -0,0 +1,337 @@
+use super::{
+    convert::{base64, date_time, f32_to_f64, system_time},
+    Body,
+};
+use crate::{decimal, Date, DateTime};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::{self, Display},
+};
+use time::{Month, OffsetDateTime, UtcOffset};
+use toml::{
+    value::{Datetime, Offset},
+    Table, Value,
+};
+
+// A value TOML cannot represent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TomlError {
+    // TOML has no null, so a unit or absent value can only be left out of a table.
+    Null,
+    // TOML dates are limited to the years 0 to 9999.
+    Date,
+}
+
+impl Display for TomlError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TomlError::Null => formatter.write_str("TOML has no null outside a table"),
+            TomlError::Date => formatter.write_str("date is outside the years TOML supports"),
+        }
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+impl Body {
+    // Unit and absent values are left out of the tables that hold them; anywhere else they are
+    // an error, as dropping an array element would shift the ones after it. This is a method
+    // rather than `TryFrom`, which `toml::Value::try_from` would shadow.
+    pub fn to_toml(&self) -> Result<Value, TomlError> {
+        to_value(self)?.ok_or(TomlError::Null)
+    }
+}
+
+fn to_value(body: &Body) -> Result<Option<Value>, TomlError> {
+    Ok(Some(match body {
+        Body::Unit | Body::Null | Body::Optional(None) => return Ok(None),
+        Body::Optional(Some(v)) => return to_value(v),
+        Body::Boolean(v) => Value::Boolean(*v),
+        Body::UInt8(v) => Value::Integer(*v as i64),
+        Body::UInt16(v) => Value::Integer(*v as i64),
+        Body::UInt32(v) => Value::Integer(*v as i64),
+        // TOML integers are signed 64-bit, so larger values are written as strings.
+        Body::UInt64(v) => i64::try_from(*v).map_or(Value::String(v.to_string()), Value::Integer),
+        Body::Int8(v) => Value::Integer(*v as i64),
+        Body::Int16(v) => Value::Integer(*v as i64),
+        Body::Int32(v) => Value::Integer(*v as i64),
+        Body::Int64(v) => Value::Integer(*v),
+        Body::Float32(v) => Value::Float(f32_to_f64(*v)),
+        Body::Float64(v) | Body::FixedPoint(v, _) => Value::Float(*v),
+        Body::BigUInt(v) => Value::String(Into::<num_bigint::BigUint>::into(v.clone()).to_string()),
+        Body::BigInt(v) => Value::String(Into::<num_bigint::BigInt>::into(v.clone()).to_string()),
+        Body::BigDecimal(v) => {
+            Value::String(Into::<bigdecimal::BigDecimal>::into(v.clone()).to_string())
+        }
+        Body::String(v) => Value::String(v.clone()),
+        Body::Binary(v) | Body::TypedBinary(_, v) => Value::String(base64(v)),
+        Body::UnknownVariant(_, v) => Value::String(base64(v)),
+        Body::Path(v) => Value::String(v.to_string_lossy().into_owned()),
+        Body::Array(v)
+        | Body::Tuple(v)
+        | Body::BitmapStruct(v)
+        | Body::Columnar(v)
+        | Body::DeltaArray(v)
+        | Body::RleArray(v)
+        | Body::SegmentedArray(v) => {
+            Value::Array(v.iter().map(Body::to_toml).collect::<Result<_, _>>()?)
+        }
+        Body::Map(v) => Value::Table(to_table(v)?),
+        Body::OrderedMap(v) => Value::Table(to_table(v.iter().map(|(key, value)| (key, value)))?),
+        // Variant names are not part of the format, so the index stands in for the name.
+        Body::Enum(i, v) | Body::OpenEnum(i, v) => {
+            let mut table = Table::new();
+            table.insert(i.to_string(), v.to_toml()?);
+            Value::Table(table)
+        }
+        Body::Date(v) => {
+            let v = TryInto::<time::Date>::try_into(*v).map_err(|_| TomlError::Date)?;
+            Value::Datetime(Datetime {
+                date: Some(toml_date(v)?),
+                time: None,
+                offset: None,
+            })
+        }
+        Body::DateTime(v) => {
+            toml_date_time(TryInto::<OffsetDateTime>::try_into(*v).map_err(|_| TomlError::Date)?)?
+        }
+        Body::SystemTime(v) => match system_time(*v) {
+            Ok(v) => toml_date_time(v).unwrap_or_else(|_| Value::String(date_time(v))),
+            Err(secs) => Value::Float(secs),
+        },
+        Body::Duration(v) => Value::Float(v.as_secs_f64()),
+        Body::DurationNanos128(v) => Value::Float(*v as f64 / 1e9),
+        Body::Decimal(v, scale) => Value::String(decimal::to_string(*v, *scale)),
+    }))
+}
+
+fn to_table<'a>(
+    entries: impl IntoIterator<Item = (&'a String, &'a Body)>,
+) -> Result<Table, TomlError> {
+    let mut table = Table::new();
+    for (key, value) in entries {
+        if let Some(value) = to_value(value)? {
+            table.insert(key.clone(), value);
+        }
+    }
+    Ok(table)
+}
+
+fn toml_date(v: time::Date) -> Result<toml::value::Date, TomlError> {
+    Ok(toml::value::Date {
+        year: u16::try_from(v.year())
+            .ok()
+            .filter(|year| *year <= 9999)
+            .ok_or(TomlError::Date)?,
+        month: v.month() as u8,
+        day: v.day(),
+    })
+}
+
+fn toml_date_time(v: OffsetDateTime) -> Result<Value, TomlError> {
+    Ok(Value::Datetime(Datetime {
+        date: Some(toml_date(v.date())?),
+        time: Some(toml::value::Time {
+            hour: v.hour(),
+            minute: v.minute(),
+            second: v.second(),
+            nanosecond: v.nanosecond(),
+        }),
+        offset: Some(match v.offset().whole_minutes() {
+            0 => Offset::Z,
+            minutes => Offset::Custom { minutes },
+        }),
+    }))
+}
+
+// Local date-times and times, which carry no offset, are kept as their TOML text.
+impl From<&Value> for Body {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::String(v) => Body::String(v.clone()),
+            Value::Integer(v) => Body::Int64(*v),
+            Value::Float(v) => Body::Float64(*v),
+            Value::Boolean(v) => Body::Boolean(*v),
+            Value::Datetime(v) => from_datetime(v).unwrap_or_else(|| Body::String(v.to_string())),
+            Value::Array(v) => Body::Array(v.iter().map(Body::from).collect()),
+            Value::Table(v) => Body::Map(
+                v.iter()
+                    .map(|(key, value)| (key.clone(), Body::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn from_datetime(v: &Datetime) -> Option<Body> {
+    let date = v.date?;
+    let date = time::Date::from_calendar_date(
+        date.year as i32,
+        Month::try_from(date.month).ok()?,
+        date.day,
+    )
+    .ok()?;
+    match (v.time, v.offset) {
+        (None, None) => Some(Body::Date(Date::from(date))),
+        (Some(time), Some(offset)) => {
+            let time =
+                time::Time::from_hms_nano(time.hour, time.minute, time.second, time.nanosecond)
+                    .ok()?;
+            let offset = match offset {
+                Offset::Z => UtcOffset::UTC,
+                Offset::Custom { minutes } => {
+                    UtcOffset::from_whole_seconds(minutes as i32 * 60).ok()?
+                }
+            };
+            Some(Body::DateTime(DateTime::from(
+                date.with_time(time).assume_offset(offset),
+            )))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Body, Date, DateTime, TomlError};
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, SystemTime},
+    };
+    use time::{ext::NumericalDuration, Month, OffsetDateTime};
+    use toml::{toml, Value};
+
+    #[test]
+    fn toml_value_roundtrip() {
+        let value = Value::Table(toml! {
+            name = "dlhn"
+            version = 2
+            ratio = 0.5
+            enabled = true
+            released = 2021-03-04
+            updated = 2021-03-04T05:06:07.000000008Z
+            local = 2021-03-04T05:06:07
+
+            [limits]
+            depth = 64
+            tags = ["a", "b"]
+
+            [[limits.rules]]
+            path = "$.id"
+            required = true
+        });
+
+        let body = Body::from(&value);
+        match &body {
+            Body::Map(v) => {
+                assert_eq!(v["name"], Body::String("dlhn".to_string()));
+                assert_eq!(v["version"], Body::Int64(2));
+                assert_eq!(
+                    v["released"],
+                    Body::Date(Date::from(
+                        time::Date::from_calendar_date(2021, Month::March, 4).unwrap()
+                    ))
+                );
+                assert_eq!(
+                    v["updated"],
+                    Body::DateTime(DateTime::from(
+                        OffsetDateTime::from_unix_timestamp(1_614_834_367).unwrap()
+                            + 8.nanoseconds()
+                    ))
+                );
+                assert_eq!(v["local"], Body::String("2021-03-04T05:06:07".to_string()));
+                assert_eq!(
+                    v["limits"],
+                    Body::Map(BTreeMap::from([
+                        ("depth".to_string(), Body::Int64(64)),
+                        (
+                            "rules".to_string(),
+                            Body::Array(vec![Body::Map(BTreeMap::from([
+                                ("path".to_string(), Body::String("$.id".to_string())),
+                                ("required".to_string(), Body::Boolean(true)),
+                            ]))]),
+                        ),
+                        (
+                            "tags".to_string(),
+                            Body::Array(vec![
+                                Body::String("a".to_string()),
+                                Body::String("b".to_string()),
+                            ]),
+                        ),
+                    ]))
+                );
+            }
+            body => panic!("expected a map, got {:?}", body),
+        }
+
+        // The local date-time comes back as a string, everything else as it was.
+        let mut expected = value.clone();
+        expected["local"] = Value::String("2021-03-04T05:06:07".to_string());
+        assert_eq!(body.to_toml(), Ok(expected));
+    }
+
+    #[test]
+    fn body_to_toml_value_without_null() {
+        let body = Body::Tuple(vec![
+            Body::UInt64(u64::MAX),
+            Body::Optional(Some(Box::new(Body::Int8(-1)))),
+            Body::Map(BTreeMap::from([
+                ("a".to_string(), Body::Unit),
+                ("b".to_string(), Body::Float32(1.5)),
+                ("c".to_string(), Body::Optional(None)),
+            ])),
+        ]);
+        assert_eq!(
+            body.to_toml(),
+            Ok(Value::Array(vec![
+                Value::String("18446744073709551615".to_string()),
+                Value::Integer(-1),
+                Value::Table(toml! { b = 1.5 }),
+            ]))
+        );
+
+        // Outside a table there is nowhere to leave a null out.
+        for body in [
+            Body::Unit,
+            Body::Array(vec![Body::Optional(None), Body::UInt8(1)]),
+            Body::Enum(1, Box::new(Body::Unit)),
+        ] {
+            assert_eq!(body.to_toml(), Err(TomlError::Null));
+        }
+    }
+
+    #[test]
+    fn body_to_toml_value_date_out_of_range() {
+        let date = time::Date::from_calendar_date(-1, Month::January, 1).unwrap();
+        assert_eq!(
+            Body::Array(vec![Body::Date(Date::from(date))]).to_toml(),
+            Err(TomlError::Date)
+        );
+        assert_eq!(
+            Body::DateTime(DateTime::from(date.midnight().assume_utc())).to_toml(),
+            Err(TomlError::Date)
+        );
+    }
+
+    #[test]
+    fn body_to_toml_value_float32() {
+        assert_eq!(Body::Float32(0.1).to_toml(), Ok(Value::Float(0.1)));
+    }
+
+    #[test]
+    fn body_to_toml_value_decimal() {
+        assert_eq!(
+            Body::Decimal(-5, 2).to_toml(),
+            Ok(Value::String("-0.05".to_string()))
+        );
+    }
+
+    #[test]
+    fn body_to_toml_value_system_time_out_of_range() {
+        let secs = 1u64 << 40;
+        assert_eq!(
+            Body::SystemTime(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).to_toml(),
+            Ok(Value::Float(secs as f64))
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use super::Body;
+use crate::Header;
+use std::fmt::{self, Display};
+
+// A value that does not match its header. `path` uses the notation of `Header::describe`, with
+// array elements addressed by index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub path: String,
+    pub expected: Header,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: expected {:?}", self.path, self.expected)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Body {
+    // Like `validate`, but keeps going after a mismatch and reports every one of them.
+    pub fn validate_all(&self, header: &Header) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        self.validate_into(header, "$".to_string(), &mut errors);
+        errors
+    }
+
+    fn validate_into(&self, header: &Header, path: String, errors: &mut Vec<ValidationError>) {
+        match (header, self) {
+            (Header::Optional(inner), Body::Optional(Some(v))) => {
+                v.validate_into(inner, format!("{}?", path), errors)
+            }
+            (Header::Array(inner), Body::Array(v))
+            | (Header::Columnar(inner), Body::Columnar(v))
+            | (Header::DeltaArray(inner), Body::DeltaArray(v))
+            | (Header::RleArray(inner), Body::RleArray(v))
+            | (Header::SegmentedArray(inner), Body::SegmentedArray(v)) => {
+                for (i, v) in v.iter().enumerate() {
+                    v.validate_into(inner, format!("{}[{}]", path, i), errors);
+                }
+            }
+            (Header::Tuple(inner), Body::Tuple(v))
+            | (Header::BitmapStruct(inner), Body::BitmapStruct(v)) => {
+                if inner.len() != v.len() {
+                    errors.push(ValidationError {
+                        path: path.clone(),
+                        expected: header.clone(),
+                    });
+                }
+                for (i, (inner, v)) in inner.iter().zip(v).enumerate() {
+                    v.validate_into(inner, format!("{}.{}", path, i), errors);
+                }
+            }
+            (Header::Map(inner), Body::Map(_) | Body::OrderedMap(_)) => {
+                for (key, v) in self.entries() {
+                    v.validate_into(inner, format!("{}.{}", path, key), errors);
+                }
+            }
+            (Header::FlattenedMap(fields, extra), Body::Map(_) | Body::OrderedMap(_)) => {
+                for (key, v) in self.entries() {
+                    let inner = Header::flattened_value(fields, extra, key);
+                    v.validate_into(inner, format!("{}.{}", path, key), errors);
+                }
+            }
+            (Header::Enum(inner), Body::Enum(i, v))
+            | (Header::OpenEnum(inner), Body::OpenEnum(i, v)) => match inner.get(*i as usize) {
+                Some(inner) => v.validate_into(inner, format!("{}::{}", path, i), errors),
+                None => errors.push(ValidationError {
+                    path,
+                    expected: header.clone(),
+                }),
+            },
+            (Header::Annotated(inner, _), _)
+            | (Header::Tagged(inner, _), _)
+            | (Header::Reserved(inner), _) => self.validate_into(inner, path, errors),
+            _ => {
+                if !self.validate(header) {
+                    errors.push(ValidationError {
+                        path,
+                        expected: header.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    // The entries of a `Map` or `OrderedMap` in their stored order.
+    fn entries(&self) -> Box<dyn Iterator<Item = (&String, &Body)> + '_> {
+        match self {
+            Body::Map(v) => Box::new(v.iter()),
+            Body::OrderedMap(v) => Box::new(v.iter().map(|(key, v)| (key, v))),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationError;
+    use crate::{Body, Header};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn validate_all() {
+        let header = Header::Tuple(vec![
+            Header::UInt8,
+            Header::Array(Box::new(Header::String)),
+            Header::Map(Box::new(Header::Optional(Box::new(Header::Int32)))),
+            Header::Enum(vec![Header::Unit, Header::Boolean]),
+        ]);
+        let body = Body::Tuple(vec![
+            Body::UInt16(1),
+            Body::Array(vec![
+                Body::String("a".to_string()),
+                Body::String("b".to_string()),
+                Body::Boolean(true),
+            ]),
+            Body::Map(BTreeMap::from([
+                ("x".to_string(), Body::Optional(None)),
+                (
+                    "y".to_string(),
+                    Body::Optional(Some(Box::new(Body::Int64(2)))),
+                ),
+            ])),
+            Body::Enum(1, Box::new(Body::Boolean(false))),
+        ]);
+        assert!(!body.validate(&header));
+        assert_eq!(
+            body.validate_all(&header),
+            [
+                ValidationError {
+                    path: "$.0".to_string(),
+                    expected: Header::UInt8,
+                },
+                ValidationError {
+                    path: "$.1[2]".to_string(),
+                    expected: Header::String,
+                },
+                ValidationError {
+                    path: "$.2.y?".to_string(),
+                    expected: Header::Int32,
+                },
+            ]
+        );
+        assert_eq!(
+            body.validate_all(&header)[1].to_string(),
+            "$.1[2]: expected String"
+        );
+    }
+
+    #[test]
+    fn validate_all_valid() {
+        let header = Header::Annotated(
+            Box::new(Header::Enum(vec![Header::Unit, Header::UInt8])),
+            "status".to_string(),
+        );
+        assert!(Body::Enum(1, Box::new(Body::UInt8(3)))
+            .validate_all(&header)
+            .is_empty());
+        assert_eq!(
+            Body::Enum(2, Box::new(Body::Unit)).validate_all(&header),
+            [ValidationError {
+                path: "$".to_string(),
+                expected: Header::Enum(vec![Header::Unit, Header::UInt8]),
+            }]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use super::{
+    convert::{base64, date_time, f32_to_f64, system_time},
+    Body,
+};
+use crate::decimal;
+use serde_yaml::{Mapping, Number, Value};
+use std::convert::TryInto;
+use time::OffsetDateTime;
+
+impl From<&Body> for Value {
+    fn from(body: &Body) -> Self {
+        match body {
+            Body::Unit | Body::Null | Body::Optional(None) => Value::Null,
+            Body::Optional(Some(v)) => Value::from(v.as_ref()),
+            Body::Boolean(v) => Value::Bool(*v),
+            Body::UInt8(v) => Value::Number(Number::from(*v)),
+            Body::UInt16(v) => Value::Number(Number::from(*v)),
+            Body::UInt32(v) => Value::Number(Number::from(*v)),
+            Body::UInt64(v) => Value::Number(Number::from(*v)),
+            Body::Int8(v) => Value::Number(Number::from(*v)),
+            Body::Int16(v) => Value::Number(Number::from(*v)),
+            Body::Int32(v) => Value::Number(Number::from(*v)),
+            Body::Int64(v) => Value::Number(Number::from(*v)),
+            // Unlike JSON, YAML can represent NaN and infinities.
+            Body::Float32(v) => Value::Number(Number::from(f32_to_f64(*v))),
+            Body::Float64(v) | Body::FixedPoint(v, _) => Value::Number(Number::from(*v)),
+            Body::BigUInt(v) => {
+                Value::String(Into::<num_bigint::BigUint>::into(v.clone()).to_string())
+            }
+            Body::BigInt(v) => {
+                Value::String(Into::<num_bigint::BigInt>::into(v.clone()).to_string())
+            }
+            Body::BigDecimal(v) => {
+                Value::String(Into::<bigdecimal::BigDecimal>::into(v.clone()).to_string())
+            }
+            Body::String(v) => Value::String(v.clone()),
+            Body::Binary(v) => Value::String(base64(v)),
+            Body::TypedBinary(tag, v) => {
+                let mut map = Mapping::new();
+                map.insert(Value::from("type"), Value::from(*tag));
+                map.insert(Value::from("data"), Value::String(base64(v)));
+                Value::Mapping(map)
+            }
+            Body::Path(v) => Value::String(v.to_string_lossy().into_owned()),
+            Body::Array(v)
+            | Body::Tuple(v)
+            | Body::BitmapStruct(v)
+            | Body::Columnar(v)
+            | Body::DeltaArray(v)
+            | Body::RleArray(v)
+            | Body::SegmentedArray(v) => Value::Sequence(v.iter().map(Value::from).collect()),
+            Body::Map(v) => Value::Mapping(
+                v.iter()
+                    .map(|(key, value)| (Value::from(key.as_str()), Value::from(value)))
+                    .collect(),
+            ),
+            Body::OrderedMap(v) => Value::Mapping(
+                v.iter()
+                    .map(|(key, value)| (Value::from(key.as_str()), Value::from(value)))
+                    .collect(),
+            ),
+            // Variant names are not part of the format, so the index stands in for the name.
+            Body::Enum(i, v) | Body::OpenEnum(i, v) => {
+                let mut map = Mapping::new();
+                map.insert(Value::from(*i), Value::from(v.as_ref()));
+                Value::Mapping(map)
+            }
+            Body::UnknownVariant(i, v) => {
+                let mut map = Mapping::new();
+                map.insert(Value::from(*i), Value::String(base64(v)));
+                Value::Mapping(map)
+            }
+            Body::Date(v) => TryInto::<time::Date>::try_into(*v)
+                .map_or(Value::Null, |v| Value::String(v.to_string())),
+            Body::DateTime(v) => TryInto::<OffsetDateTime>::try_into(*v)
+                .map_or(Value::Null, |v| Value::String(date_time(v))),
+            Body::Duration(v) => Value::Number(Number::from(v.as_secs_f64())),
+            Body::DurationNanos128(v) => Value::Number(Number::from(*v as f64 / 1e9)),
+            Body::Decimal(v, scale) => Value::String(decimal::to_string(*v, *scale)),
+            Body::SystemTime(v) => system_time(*v).map_or_else(
+                |secs| Value::Number(Number::from(secs)),
+                |v| Value::String(date_time(v)),
+            ),
+        }
+    }
+}
+
+// Mapping keys other than strings are keyed by their YAML text; tags are dropped.
+impl From<&Value> for Body {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Null => Body::Optional(None),
+            Value::Bool(v) => Body::Boolean(*v),
+            Value::Number(v) => match (v.as_i64(), v.as_u64(), v.as_f64()) {
+                (Some(v), _, _) => Body::Int64(v),
+                (None, Some(v), _) => Body::UInt64(v),
+                (None, None, v) => Body::Float64(v.unwrap_or(f64::NAN)),
+            },
+            Value::String(v) => Body::String(v.clone()),
+            Value::Sequence(v) => Body::Array(v.iter().map(Body::from).collect()),
+            Value::Mapping(v) => Body::Map(
+                v.iter()
+                    .map(|(key, value)| (key_string(key), Body::from(value)))
+                    .collect(),
+            ),
+            Value::Tagged(v) => Body::from(&v.value),
+        }
+    }
+}
+
+fn key_string(key: &Value) -> String {
+    match key {
+        Value::String(v) => v.clone(),
+        Value::Tagged(v) => key_string(&v.value),
+        key => serde_yaml::to_string(key)
+            .map(|v| v.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Body;
+    use serde_yaml::Value;
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, SystemTime},
+    };
+
+    #[test]
+    fn yaml_value_roundtrip() {
+        let value = serde_yaml::from_str::<Value>(
+            "
+name: dlhn
+version: 2
+ratio: 0.5
+big: 18446744073709551615
+missing: null
+limits:
+  depth: 64
+  tags: [a, b]
+  rules:
+    - path: $.id
+      required: true
+",
+        )
+        .unwrap();
+
+        let body = Body::from(&value);
+        match &body {
+            Body::Map(v) => {
+                assert_eq!(v["version"], Body::Int64(2));
+                assert_eq!(v["big"], Body::UInt64(u64::MAX));
+                assert_eq!(v["missing"], Body::Optional(None));
+                assert_eq!(
+                    v["limits"],
+                    Body::Map(BTreeMap::from([
+                        ("depth".to_string(), Body::Int64(64)),
+                        (
+                            "rules".to_string(),
+                            Body::Array(vec![Body::Map(BTreeMap::from([
+                                ("path".to_string(), Body::String("$.id".to_string())),
+                                ("required".to_string(), Body::Boolean(true)),
+                            ]))]),
+                        ),
+                        (
+                            "tags".to_string(),
+                            Body::Array(vec![
+                                Body::String("a".to_string()),
+                                Body::String("b".to_string()),
+                            ]),
+                        ),
+                    ]))
+                );
+            }
+            body => panic!("expected a map, got {:?}", body),
+        }
+        assert_eq!(Value::from(&body), value);
+    }
+
+    #[test]
+    fn yaml_mapping_keys() {
+        let value = serde_yaml::from_str::<Value>("{1: a, true: b, !tag c: d}").unwrap();
+        assert_eq!(
+            Body::from(&value),
+            Body::Map(BTreeMap::from([
+                ("1".to_string(), Body::String("a".to_string())),
+                ("true".to_string(), Body::String("b".to_string())),
+                ("c".to_string(), Body::String("d".to_string())),
+            ]))
+        );
+        assert_eq!(
+            Value::from(&Body::Enum(1, Box::new(Body::Float32(f32::INFINITY)))),
+            serde_yaml::from_str::<Value>("{1: .inf}").unwrap()
+        );
+    }
+
+    #[test]
+    fn yaml_float32() {
+        assert_eq!(
+            Value::from(&Body::Float32(0.1)),
+            Value::Number(0.1f64.into())
+        );
+    }
+
+    #[test]
+    fn yaml_decimal() {
+        assert_eq!(
+            Value::from(&Body::Decimal(i128::MIN, 3)),
+            Value::String("-170141183460469231731687303715884105.728".to_string())
+        );
+    }
+
+    #[test]
+    fn yaml_system_time_out_of_range() {
+        let secs = 1u64 << 40;
+        assert_eq!(
+            Value::from(&Body::SystemTime(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+            )),
+            Value::Number((secs as f64).into())
+        );
+    }
+}
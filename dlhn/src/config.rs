@@ -0,0 +1,23 @@
+use crate::{
+    de::{BigDecimalLimits, DecodeLimits},
+    ser::SortMode,
+};
+
+// Set through the `Serializer` and `Deserializer` builder methods and read back with their
+// `config()`, so options can be added without breaking callers.
+#[derive(Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct Config {
+    pub map_sort: Option<SortMode>,
+    pub compact_enum_tags: bool,
+    pub reject_non_finite_floats: bool,
+    pub string_interning: bool,
+    pub bitmap_structs: bool,
+    pub columnar_arrays: bool,
+    pub delta_encode_arrays: bool,
+    pub rle_arrays: bool,
+    pub canonical: bool,
+    pub require_sorted_map_keys: bool,
+    pub big_decimal_limits: Option<BigDecimalLimits>,
+    pub decode_limits: DecodeLimits,
+}
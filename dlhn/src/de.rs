@@ -1,18 +1,56 @@
-use crate::{PrefixVarint, ZigZag};
-use serde::{de, Deserialize};
+use crate::{
+    big_decimal::BIG_DECIMAL_NAME,
+    big_int::BIG_INT_NAME,
+    big_uint::BIG_UINT_NAME,
+    fixed_point,
+    format::{byte_array::BYTE_ARRAY_NAME, fixed_width},
+    Body, Config, DeserializeHeader, Header, Leb128, PrefixVarint, TruncatedVarint, ZigZag,
+};
+use serde::{
+    de::{self, IntoDeserializer},
+    Deserialize,
+};
 use std::{
     cmp::min,
+    convert::TryFrom,
     fmt::{self, Display},
-    io::Read,
-    slice::Iter,
+    io::{self, Cursor, ErrorKind, Read},
+    iter::{self, Peekable},
+    mem,
+    slice::{self, Iter},
+    time::Instant,
     vec,
 };
 
+// Lengths come from the input, so never trust them for up-front allocation.
+pub(crate) const MAX_PREALLOCATION: u64 = 4096;
+
+// Reading the clock costs more than decoding most values, so a deadline is only checked once
+// every this many values.
+const DEADLINE_INTERVAL: u64 = 1024;
+
+// Matches need a wildcard arm, since decoding keeps gaining limits and checks of its own.
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
-    Read,
+    Read { offset: u64 },
     CharSize,
     UnsupportedKeyType,
+    BigDecimalOutOfBounds,
+    TrailingBytes,
+    DepthLimit,
+    SeqLengthLimit,
+    MapLengthLimit,
+    BigNumLimit,
+    StringLengthLimit,
+    BinaryLengthLimit,
+    NotSelfDescribing,
+    UnsortedMapKey { offset: u64 },
+    Cancelled,
+    // The input ended in the middle of a varint.
+    TruncatedVarint { offset: u64 },
+    // The header ahead of a self-describing value could not be read.
+    Header { offset: u64, message: String },
     Message(String),
 }
 
@@ -25,9 +63,26 @@ impl de::Error for Error {
 impl de::Expected for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Read => formatter.write_str("Read error"),
+            Error::Read { .. } => formatter.write_str("Read error"),
             Error::CharSize => formatter.write_str("The size of the char is more than 32bit"),
             Error::UnsupportedKeyType => formatter.write_str("Unsupported Key Type"),
+            Error::BigDecimalOutOfBounds => formatter.write_str("BigDecimal out of bounds"),
+            Error::TrailingBytes => formatter.write_str("Trailing bytes"),
+            Error::DepthLimit => formatter.write_str("Depth limit exceeded"),
+            Error::SeqLengthLimit => formatter.write_str("Sequence length limit exceeded"),
+            Error::MapLengthLimit => formatter.write_str("Map length limit exceeded"),
+            Error::BigNumLimit => formatter.write_str("Big number length limit exceeded"),
+            Error::StringLengthLimit => formatter.write_str("String length limit exceeded"),
+            Error::BinaryLengthLimit => formatter.write_str("Binary length limit exceeded"),
+            Error::NotSelfDescribing => formatter.write_str(
+                "deserialize_any is not supported because the data is not self-describing; \
+                 untagged enums need a Header (see Body::deserialize), and adjacently tagged \
+                 enums can only have newtype and tuple variants",
+            ),
+            Error::UnsortedMapKey { .. } => formatter.write_str("Map keys not in ascending order"),
+            Error::Cancelled => formatter.write_str("Decoding deadline passed"),
+            Error::TruncatedVarint { .. } => formatter.write_str("Truncated varint"),
+            Error::Header { .. } => formatter.write_str("Invalid header"),
             Error::Message(msg) => formatter.write_str(msg),
         }
     }
@@ -36,9 +91,40 @@ impl de::Expected for Error {
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Read => formatter.write_str("Read error"),
+            Error::Read { offset } => write!(formatter, "Read error at offset {}", offset),
             Error::CharSize => formatter.write_str("The size of the char is more than 32bit"),
             Error::UnsupportedKeyType => formatter.write_str("Unsupported Key Type"),
+            Error::BigDecimalOutOfBounds => formatter.write_str("BigDecimal out of bounds"),
+            Error::TrailingBytes => formatter.write_str("Trailing bytes"),
+            Error::DepthLimit => formatter.write_str("Depth limit exceeded"),
+            Error::SeqLengthLimit => formatter.write_str("Sequence length limit exceeded"),
+            Error::MapLengthLimit => formatter.write_str("Map length limit exceeded"),
+            Error::BigNumLimit => formatter.write_str("Big number length limit exceeded"),
+            Error::StringLengthLimit => formatter.write_str("String length limit exceeded"),
+            Error::BinaryLengthLimit => formatter.write_str("Binary length limit exceeded"),
+            Error::NotSelfDescribing => formatter.write_str(
+                "deserialize_any is not supported because the data is not self-describing; \
+                 untagged enums need a Header (see Body::deserialize), and adjacently tagged \
+                 enums can only have newtype and tuple variants",
+            ),
+            Error::UnsortedMapKey { offset } => {
+                write!(
+                    formatter,
+                    "Map key at offset {} not in ascending order",
+                    offset
+                )
+            }
+            Error::Cancelled => formatter.write_str("Decoding deadline passed"),
+            Error::TruncatedVarint { offset } => {
+                write!(formatter, "Truncated varint at offset {}", offset)
+            }
+            Error::Header { offset, message } => {
+                write!(
+                    formatter,
+                    "Invalid header at offset {}: {}",
+                    offset, message
+                )
+            }
             Error::Message(msg) => formatter.write_str(msg),
         }
     }
@@ -46,24 +132,826 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
-pub struct Deserializer<'de, R: Read> {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BigDecimalLimits {
+    pub max_scale: u64,
+    pub max_coefficient_len: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub max_depth: usize,
+    pub max_seq_len: u64,
+    pub max_map_len: u64,
+    pub max_bignum_len: u64,
+    pub max_string_bytes: u64,
+    pub max_binary_bytes: u64,
+}
+
+impl DecodeLimits {
+    pub const UNLIMITED: Self = Self {
+        max_depth: usize::MAX,
+        max_seq_len: u64::MAX,
+        max_map_len: u64::MAX,
+        max_bignum_len: u64::MAX,
+        max_string_bytes: u64::MAX,
+        max_binary_bytes: u64::MAX,
+    };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_seq_len: 1 << 20,
+            max_map_len: 1 << 20,
+            max_bignum_len: 1 << 10,
+            max_string_bytes: 16 << 20,
+            max_binary_bytes: 16 << 20,
+        }
+    }
+}
+
+struct OffsetReader<'de, R: Read> {
     reader: &'de mut R,
+    offset: u64,
+    columns: Vec<ColumnSet>,
+}
+
+// The columns of a columnar array being decoded. While a field is being read, `active` points
+// at its column and reads are served from it instead of the underlying reader.
+struct ColumnSet {
+    columns: Vec<Cursor<Vec<u8>>>,
+    active: Option<usize>,
+}
+
+impl<'de, R: Read> Read for OffsetReader<'de, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(set) = self.columns.iter_mut().rev().find(|v| v.active.is_some()) {
+            return set.columns[set.active.unwrap_or_default()].read(buf);
+        }
+        let size = self.reader.read(buf)?;
+        self.offset += size as u64;
+        Ok(size)
+    }
+}
+
+pub struct Deserializer<'de, R: Read> {
+    reader: OffsetReader<'de, R>,
+    config: Config,
+    depth: usize,
+    strings: Vec<String>,
+    deadline: Option<Instant>,
+    ticks: u64,
+    lend: Option<Lend<'de, R>>,
+    // Whether enum tags are single bytes, decided by the outermost header being decoded.
+    compact_tags: Option<bool>,
+}
+
+// Set when the input is a slice, to hand out strings and bytes borrowed for `'de`.
+type Lend<'de, R> = fn(&mut R, usize) -> Option<&'de [u8]>;
+
+impl<'de, 'a: 'de> Deserializer<'de, &'a [u8]> {
+    // Reads from a slice so `&str` and `&[u8]` fields (and `#[serde(borrow)]` ones) point into
+    // the input instead of being copied. They stay valid while `reader` is borrowed.
+    pub fn from_slice(reader: &'de mut &'a [u8]) -> Self {
+        Deserializer {
+            lend: Some(|reader, len| {
+                if reader.len() < len {
+                    return None;
+                }
+                let (head, tail) = reader.split_at(len);
+                *reader = tail;
+                Some(head)
+            }),
+            ..Self::new(reader)
+        }
+    }
 }
 
 impl<'de, R: Read> Deserializer<'de, R> {
     pub fn new(reader: &'de mut R) -> Self {
-        Deserializer { reader }
+        Self::with_config(reader, Config::default())
+    }
+
+    pub fn with_config(reader: &'de mut R, config: Config) -> Self {
+        Deserializer {
+            reader: OffsetReader {
+                reader,
+                offset: 0,
+                columns: Vec::new(),
+            },
+            config,
+            depth: 0,
+            strings: Vec::new(),
+            deadline: None,
+            ticks: 0,
+            lend: None,
+            compact_tags: None,
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    // Decoding fails with `Error::Cancelled` once `deadline` has passed.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn compact_enum_tags(mut self, compact_enum_tags: bool) -> Self {
+        self.config.compact_enum_tags = compact_enum_tags;
+        self
+    }
+
+    pub fn big_decimal_limits(mut self, limits: BigDecimalLimits) -> Self {
+        self.config.big_decimal_limits = Some(limits);
+        self
+    }
+
+    pub fn string_interning(mut self, string_interning: bool) -> Self {
+        self.config.string_interning = string_interning;
+        self
+    }
+
+    pub fn columnar_arrays(mut self, columnar_arrays: bool) -> Self {
+        self.config.columnar_arrays = columnar_arrays;
+        self
+    }
+
+    pub fn bitmap_structs(mut self, bitmap_structs: bool) -> Self {
+        self.config.bitmap_structs = bitmap_structs;
+        self
+    }
+
+    pub fn delta_encode_arrays(mut self, delta_encode_arrays: bool) -> Self {
+        self.config.delta_encode_arrays = delta_encode_arrays;
+        self
+    }
+
+    pub fn rle_arrays(mut self, rle_arrays: bool) -> Self {
+        self.config.rle_arrays = rle_arrays;
+        self
+    }
+
+    pub fn require_sorted_map_keys(mut self, require_sorted_map_keys: bool) -> Self {
+        self.config.require_sorted_map_keys = require_sorted_map_keys;
+        self
+    }
+
+    // `DecodeLimits::default()` applies unless replaced here; `DecodeLimits::UNLIMITED` turns
+    // every cap off, for input that is trusted.
+    pub fn decode_limits(mut self, limits: DecodeLimits) -> Self {
+        self.config.decode_limits = limits;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.config.decode_limits.max_depth = max_depth;
+        self
+    }
+
+    pub fn deserialize_with_header(reader: &'de mut R, header: &Header) -> Result<Body, Error> {
+        Body::deserialize(header, &mut Deserializer::new(reader))
+    }
+
+    // Like `T::deserialize`, but guided by `header`, so values the schema stores differently from
+    // the Rust type (such as fixed-point floats) are converted on the way in.
+    pub fn deserialize_guided<T: Deserialize<'de>>(&mut self, header: &Header) -> Result<T, Error> {
+        self.with_header(header, |de| {
+            T::deserialize(HeaderDeserializer::new(de, header))
+        })
+    }
+
+    // Reads a header and then the value it describes, as written by
+    // `Serializer::serialize_self_describing`.
+    pub fn deserialize_self_describing(&mut self) -> Result<(Header, Body), Error> {
+        let header = match self.reader.deserialize_header() {
+            Ok(header) => header,
+            Err(e) => {
+                return Err(Error::Header {
+                    offset: self.reader.offset,
+                    message: e.to_string(),
+                })
+            }
+        };
+        let body = Body::deserialize(&header, self)?;
+        Ok((header, body))
+    }
+
+    pub fn end(&mut self) -> Result<(), Error> {
+        let mut buf = [0u8; 1];
+        loop {
+            match self.reader.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(_) => return Err(Error::TrailingBytes),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return Err(self.read_error()),
+            }
+        }
+    }
+
+    pub fn try_decode<T: de::DeserializeOwned>(&mut self) -> Result<Option<T>, Error> {
+        let mut first = [0u8; 1];
+        loop {
+            match self.reader.read(&mut first) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return Err(self.read_error()),
+            }
+        }
+        let offset = self.reader.offset - 1;
+        let strings = mem::take(&mut self.strings);
+        let mut reader = first.as_ref().chain(&mut self.reader);
+        let mut deserializer = Deserializer::with_config(&mut reader, self.config);
+        deserializer.reader.offset = offset;
+        deserializer.strings = strings;
+        deserializer.deadline = self.deadline;
+        let result = T::deserialize(&mut deserializer).map(Some);
+        let strings = mem::take(&mut deserializer.strings);
+        self.strings = strings;
+        result
+    }
+
+    pub fn skip_value(&mut self, header: &Header) -> Result<(), Error> {
+        self.with_header(header, |de| {
+            de.enter()?;
+            let result = de.skip_inner(header);
+            de.leave();
+            result
+        })
+    }
+
+    fn skip_inner(&mut self, header: &Header) -> Result<(), Error> {
+        match header {
+            Header::Unit | Header::Null => Ok(()),
+            Header::Optional(inner) => {
+                if bool::deserialize(&mut *self)? {
+                    self.skip_value(inner)?;
+                }
+                Ok(())
+            }
+            Header::Boolean | Header::UInt8 | Header::Int8 => self.skip_bytes(1),
+            Header::UInt16 => u16::deserialize(self).map(drop),
+            Header::UInt32 => u32::deserialize(self).map(drop),
+            Header::UInt64 => u64::deserialize(self).map(drop),
+            Header::Int16 => i16::deserialize(self).map(drop),
+            Header::Int32 => i32::deserialize(self).map(drop),
+            Header::Int64 | Header::FixedPoint { .. } => i64::deserialize(self).map(drop),
+            Header::Float32 => self.skip_bytes(4),
+            Header::Float64 => self.skip_bytes(8),
+            Header::String if self.config.string_interning => {
+                self.deserialize_interned_string().map(drop)
+            }
+            Header::InternedString => self.deserialize_interned_string().map(drop),
+            Header::BigUInt | Header::BigInt => {
+                let len = self.deserialize_bignum_len()?;
+                self.skip_bytes(len)
+            }
+            Header::String => {
+                let len = self.deserialize_string_len()?;
+                self.skip_bytes(len)
+            }
+            Header::Binary => {
+                let len = self.deserialize_binary_len()?;
+                self.skip_bytes(len)
+            }
+            Header::BigDecimal => {
+                let len = self.deserialize_bignum_len()?;
+                self.skip_bytes(len)?;
+                if len > 0 {
+                    i64::deserialize(self)?;
+                }
+                Ok(())
+            }
+            Header::Array(inner) => {
+                let len = self.deserialize_seq_len()?;
+                for _ in 0..len {
+                    self.skip_value(inner)?;
+                }
+                Ok(())
+            }
+            Header::Tuple(inner) => {
+                for inner in inner.iter() {
+                    self.skip_value(inner)?;
+                }
+                Ok(())
+            }
+            Header::BitmapStruct(inner) => {
+                let presence = self.deserialize_presence_bitmap(inner.len())?;
+                for (inner, present) in inner.iter().zip(presence) {
+                    self.skip_bitmap_field(inner, present)?;
+                }
+                Ok(())
+            }
+            Header::Columnar(inner) => {
+                let len = self.deserialize_seq_len()?;
+                match inner.columns() {
+                    Some(fields) if len > 0 => {
+                        for _ in fields.iter() {
+                            let size = u64::deserialize(&mut *self)?;
+                            self.skip_bytes(size)?;
+                        }
+                    }
+                    _ => {
+                        for _ in 0..len {
+                            self.skip_value(inner)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Header::DeltaArray(inner) if inner.is_delta_integer() => {
+                let len = self.deserialize_seq_len()?;
+                if len > 0 {
+                    self.skip_value(inner)?;
+                    for _ in 1..len {
+                        i64::deserialize(&mut *self)?;
+                    }
+                }
+                Ok(())
+            }
+            Header::RleArray(inner) if inner.is_delta_integer() => {
+                let len = self.deserialize_seq_len()?;
+                let mut read = 0u64;
+                while read < len {
+                    self.skip_value(inner)?;
+                    let run = u64::deserialize(&mut *self)?;
+                    read = match read.checked_add(run) {
+                        Some(read) if run > 0 && read <= len => read,
+                        _ => return Err(self.read_error()),
+                    };
+                }
+                Ok(())
+            }
+            Header::DeltaArray(inner) | Header::RleArray(inner) => {
+                let len = self.deserialize_seq_len()?;
+                for _ in 0..len {
+                    self.skip_value(inner)?;
+                }
+                Ok(())
+            }
+            Header::SegmentedArray(inner) => {
+                let mut total = 0;
+                loop {
+                    let len = self.deserialize_segment_len(&mut total)?;
+                    if len == 0 {
+                        return Ok(());
+                    }
+                    for _ in 0..len {
+                        self.skip_value(inner)?;
+                    }
+                }
+            }
+            Header::Map(inner) => {
+                let len = self.deserialize_map_len()?;
+                for _ in 0..len {
+                    self.skip_value(&Header::String)?;
+                    self.skip_value(inner)?;
+                }
+                Ok(())
+            }
+            Header::FlattenedMap(fields, extra) => {
+                let len = self.deserialize_map_len()?;
+                for _ in 0..len {
+                    let key = String::deserialize(&mut *self)?;
+                    self.skip_value(Header::flattened_value(fields, extra, &key))?;
+                }
+                Ok(())
+            }
+            Header::Enum(inner) => {
+                let i = self.deserialize_variant_index()?;
+                let inner = inner.get(i as usize).ok_or_else(|| self.read_error())?;
+                self.skip_value(inner)
+            }
+            Header::OpenEnum(_) => {
+                self.deserialize_variant_index()?;
+                let len = self.deserialize_binary_len()?;
+                self.skip_bytes(len)
+            }
+            Header::Date => {
+                i32::deserialize(&mut *self)?;
+                u16::deserialize(self).map(drop)
+            }
+            Header::DateTime => {
+                i64::deserialize(&mut *self)?;
+                u32::deserialize(self).map(drop)
+            }
+            Header::Duration | Header::SystemTime => {
+                u64::deserialize(&mut *self)?;
+                u32::deserialize(self).map(drop)
+            }
+            Header::DurationNanos128 | Header::Decimal { .. } => i128::deserialize(self).map(drop),
+            Header::TypedBinary => {
+                u16::deserialize(&mut *self)?;
+                let len = u64::deserialize(&mut *self)?;
+                self.skip_bytes(len)
+            }
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) | Header::Reserved(inner) => {
+                self.skip_value(inner)
+            }
+        }
+    }
+
+    fn skip_bytes(&mut self, len: u64) -> Result<(), Error> {
+        let skipped = io::copy(&mut (&mut self.reader).take(len), &mut io::sink())
+            .map_err(|_| self.read_error())?;
+        if skipped != len {
+            return Err(self.read_error());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn deserialize_interned_string(&mut self) -> Result<String, Error> {
+        let tag = u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?;
+        if tag & 1 == 1 {
+            return self
+                .strings
+                .get((tag >> 1) as usize)
+                .cloned()
+                .ok_or_else(|| self.read_error());
+        }
+        let len = self.check_string_len(tag >> 1)?;
+        let s = self.read_string(len)?;
+        self.strings.push(s.clone());
+        Ok(s)
+    }
+
+    fn read_string(&mut self, len: u64) -> Result<String, Error> {
+        const MAX_SIZE: u64 = 128;
+        if len < MAX_SIZE {
+            let mut body_buf = [0; MAX_SIZE as usize];
+            self.reader
+                .read_exact(&mut body_buf[..(len as usize)])
+                .map_err(|_| self.read_error())?;
+            String::from_utf8(body_buf[..(len as usize)].to_vec()).map_err(|_| self.read_error())
+        } else {
+            let mut s = String::new();
+            if (&mut self.reader)
+                .take(len as u64)
+                .read_to_string(&mut s)
+                .map_err(|_| self.read_error())?
+                != len as usize
+            {
+                return Err(self.read_error());
+            };
+            Ok(s)
+        }
+    }
+
+    // Takes `len` bytes straight from a slice input. Gives `None` for other readers and while a
+    // column is being read, where the bytes have to be copied.
+    fn borrow_bytes(&mut self, len: u64) -> Result<Option<&'de [u8]>, Error> {
+        let lend = match self.lend {
+            Some(lend) if self.reader.columns.iter().all(|v| v.active.is_none()) => lend,
+            _ => return Ok(None),
+        };
+        let v = usize::try_from(len)
+            .ok()
+            .and_then(|len| lend(self.reader.reader, len))
+            .ok_or_else(|| self.read_error())?;
+        self.reader.offset += len;
+        Ok(Some(v))
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.deserialize_binary_len()?;
+        self.read_bytes(len)
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: u64) -> Result<Vec<u8>, Error> {
+        const MAX_SIZE: u64 = 4096;
+        if len > MAX_SIZE {
+            let mut result = Vec::new();
+            let mut buf = vec![0; MAX_SIZE as usize];
+            let mut pos = 0;
+            while result.len() < len as usize {
+                self.reader
+                    .read_exact(&mut buf[..(min(MAX_SIZE, len - pos)) as usize])
+                    .map_err(|_| self.read_error())?;
+                result.extend_from_slice(&buf[..(min(MAX_SIZE, len - pos)) as usize]);
+                pos += min(MAX_SIZE, len - pos);
+            }
+            Ok(result)
+        } else {
+            let mut buf = vec![0; len as usize];
+            self.reader
+                .read_exact(&mut buf)
+                .map_err(|_| self.read_error())?;
+            Ok(buf)
+        }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.reader.offset
+    }
+
+    // Same result as deserializing a `Vec<bool>`, with the elements read in one go.
+    pub fn deserialize_bool_vec(&mut self) -> Result<Vec<bool>, Error> {
+        let len = self.deserialize_seq_len()?;
+        let start = self.reader.offset;
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut buf)
+            .map_err(|_| self.read_error())?;
+        if buf.len() as u64 != len {
+            return Err(self.read_error());
+        }
+        buf.iter()
+            .enumerate()
+            .map(|(i, v)| match v {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(Error::Read {
+                    offset: start + i as u64,
+                }),
+            })
+            .collect()
+    }
+
+    // Reads a sequence of fixed-width little-endian elements in one go, converting each with `f`.
+    pub(crate) fn deserialize_fixed_width_vec<const N: usize, T>(
+        &mut self,
+        f: impl Fn([u8; N]) -> T,
+    ) -> Result<Vec<T>, Error> {
+        let len = self.deserialize_seq_len()?;
+        let size = len.checked_mul(N as u64).ok_or_else(|| self.read_error())?;
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(size)
+            .read_to_end(&mut buf)
+            .map_err(|_| self.read_error())?;
+        if buf.len() as u64 != size {
+            return Err(self.read_error());
+        }
+        Ok(buf
+            .chunks_exact(N)
+            .map(|v| f(<[u8; N]>::try_from(v).unwrap()))
+            .collect())
+    }
+
+    fn read_error(&self) -> Error {
+        Error::Read {
+            offset: self.reader.offset,
+        }
+    }
+
+    fn varint_error(&self, e: io::Error) -> Error {
+        if TruncatedVarint::is(&e) {
+            Error::TruncatedVarint {
+                offset: self.reader.offset,
+            }
+        } else {
+            self.read_error()
+        }
+    }
+
+    pub(crate) fn check_deadline(&mut self) -> Result<(), Error> {
+        if let Some(deadline) = self.deadline {
+            let ticks = self.ticks;
+            self.ticks = ticks.wrapping_add(1);
+            if ticks.is_multiple_of(DEADLINE_INTERVAL) && Instant::now() >= deadline {
+                return Err(Error::Cancelled);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn enter(&mut self) -> Result<(), Error> {
+        self.check_deadline()?;
+        if self.depth >= self.config.decode_limits.max_depth {
+            return Err(Error::DepthLimit);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    // Skips a `BitmapStruct` field. An optional one that is absent takes no bytes, and a present
+    // one is written without its tag.
+    fn skip_bitmap_field(&mut self, header: &Header, present: bool) -> Result<(), Error> {
+        match header.bitmap_optional() {
+            Some(_) if !present => Ok(()),
+            Some(inner) => self.skip_value(inner),
+            None => self.skip_value(header),
+        }
+    }
+
+    pub(crate) fn deserialize_presence_bitmap(&mut self, len: usize) -> Result<Vec<bool>, Error> {
+        let mut bits = vec![0u8; len.div_ceil(8)];
+        self.reader
+            .read_exact(&mut bits)
+            .map_err(|_| self.read_error())?;
+        Ok((0..len).map(|i| bits[i / 8] >> (i % 8) & 1 == 1).collect())
+    }
+
+    // Reads the columns of a columnar array, each prefixed with its byte length. Reads are served
+    // from the column picked with `activate_column` until `pop_columns`.
+    fn push_columns(&mut self, count: usize) -> Result<(), Error> {
+        let mut columns = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = u64::deserialize(&mut *self)?;
+            let mut buf = Vec::new();
+            let result = (&mut self.reader).take(len).read_to_end(&mut buf);
+            if result.is_err() || buf.len() as u64 != len {
+                return Err(self.read_error());
+            }
+            columns.push(Cursor::new(buf));
+        }
+        self.reader.columns.push(ColumnSet {
+            columns,
+            active: None,
+        });
+        Ok(())
+    }
+
+    // Every column must have been read to its end, or its length did not match its values.
+    fn pop_columns(&mut self) -> Result<(), Error> {
+        let set = self.reader.columns.pop();
+        let mut columns = set.iter().flat_map(|v| v.columns.iter());
+        if columns.any(|v| v.position() != v.get_ref().len() as u64) {
+            return Err(self.read_error());
+        }
+        Ok(())
+    }
+
+    fn activate_column(&mut self, index: Option<usize>) {
+        if let Some(set) = self.reader.columns.last_mut() {
+            set.active = index;
+        }
+    }
+
+    // Reads an integer of schema `header` as the bits delta and run-length encoding work on.
+    fn deserialize_integer_bits(&mut self, header: &Header) -> Result<u64, Error> {
+        Ok(match peel(header) {
+            Header::UInt8 => u8::deserialize(self)? as u64,
+            Header::UInt16 => u16::deserialize(self)? as u64,
+            Header::UInt32 => u32::deserialize(self)? as u64,
+            Header::UInt64 => u64::deserialize(self)?,
+            Header::Int8 => i8::deserialize(self)? as i64 as u64,
+            Header::Int16 => i16::deserialize(self)? as i64 as u64,
+            Header::Int32 => i32::deserialize(self)? as i64 as u64,
+            Header::Int64 => i64::deserialize(self)? as u64,
+            _ => return Err(self.read_error()),
+        })
+    }
+
+    pub(crate) fn deserialize_seq_len(&mut self) -> Result<u64, Error> {
+        let len = u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?;
+        if len > self.config.decode_limits.max_seq_len {
+            return Err(Error::SeqLengthLimit);
+        }
+        Ok(len)
+    }
+
+    // Reads the length of the next chunk of a segmented array; zero ends the array. The limit on
+    // sequence length applies to the chunks together.
+    pub(crate) fn deserialize_segment_len(&mut self, total: &mut u64) -> Result<u64, Error> {
+        let len = self.deserialize_seq_len()?;
+        *total = total.checked_add(len).ok_or_else(|| self.read_error())?;
+        if *total > self.config.decode_limits.max_seq_len {
+            return Err(Error::SeqLengthLimit);
+        }
+        Ok(len)
+    }
+
+    pub(crate) fn deserialize_map_len(&mut self) -> Result<u64, Error> {
+        let len = u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?;
+        if len > self.config.decode_limits.max_map_len {
+            return Err(Error::MapLengthLimit);
+        }
+        Ok(len)
+    }
+
+    // Reads a map key, checking it against the previous one when sorted keys are required.
+    // Ascending is byte order, as written by `SortMode::Bytes`; duplicates are rejected too.
+    pub(crate) fn deserialize_map_key(
+        &mut self,
+        prev: &mut Option<String>,
+    ) -> Result<String, Error> {
+        let offset = self.offset();
+        let key = String::deserialize(&mut *self)?;
+        if self.config.require_sorted_map_keys {
+            if prev.as_ref().is_some_and(|prev| *prev >= key) {
+                return Err(Error::UnsortedMapKey { offset });
+            }
+            *prev = Some(key.clone());
+        }
+        Ok(key)
+    }
+
+    fn deserialize_string_len(&mut self) -> Result<u64, Error> {
+        let len = u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?;
+        self.check_string_len(len)
+    }
+
+    fn check_string_len(&self, len: u64) -> Result<u64, Error> {
+        if len > self.config.decode_limits.max_string_bytes {
+            return Err(Error::StringLengthLimit);
+        }
+        Ok(len)
+    }
+
+    pub(crate) fn deserialize_binary_len(&mut self) -> Result<u64, Error> {
+        let len = u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?;
+        if len > self.config.decode_limits.max_binary_bytes {
+            return Err(Error::BinaryLengthLimit);
+        }
+        Ok(len)
+    }
+
+    fn deserialize_bignum_len(&mut self) -> Result<u64, Error> {
+        let len = u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?;
+        if len > self.config.decode_limits.max_bignum_len {
+            return Err(Error::BigNumLimit);
+        }
+        Ok(len)
+    }
+
+    fn read_bignum_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.deserialize_bignum_len()?;
+        self.read_bytes(len)
+    }
+
+    // Decodes a value that contains others, counting it against `max_depth`.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        self.enter()?;
+        let result = f(self);
+        self.leave();
+        result
+    }
+
+    // Runs `f` on a value of schema `header`. The outermost call decides from its header whether
+    // enum tags are single bytes; nested calls on parts of that value keep the decision.
+    pub(crate) fn with_header<T>(
+        &mut self,
+        header: &Header,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.compact_tags.is_some() {
+            return f(self);
+        }
+        self.compact_tags = Some(self.config.compact_enum_tags && header.fits_compact_enum_tags());
+        let result = f(self);
+        self.compact_tags = None;
+        result
+    }
+
+    pub(crate) fn deserialize_variant_index(&mut self) -> Result<u32, Error> {
+        if self.compact_tags == Some(true) {
+            return u8::deserialize(self).map(u32::from);
+        }
+        u32::deserialize(self)
+    }
+
+    fn deserialize_big_decimal<V>(&mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let limits = self.config.big_decimal_limits.unwrap_or(BigDecimalLimits {
+            max_scale: u64::MAX,
+            max_coefficient_len: usize::MAX,
+        });
+        let len = self.deserialize_bignum_len()?;
+        if len > limits.max_coefficient_len as u64 {
+            return Err(Error::BigDecimalOutOfBounds);
+        }
+        let signed_bytes = self.read_bytes(len)?;
+        let scale = if signed_bytes.is_empty() {
+            None
+        } else {
+            let scale = i64::deserialize(&mut *self)?;
+            if scale.unsigned_abs() > limits.max_scale {
+                return Err(Error::BigDecimalOutOfBounds);
+            }
+            Some(scale)
+        };
+        visitor.visit_seq(BigNumDeserializer {
+            signed_bytes: Some(signed_bytes),
+            scale,
+        })
     }
 }
 
 impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
     type Error = Error;
 
+    // Reached by `#[serde(untagged)]` enums and other types that inspect the input to decide
+    // what it is. The wire carries no type information, so only a Header can decode them. The
+    // content of an adjacently tagged enum is decoded like an untagged variant, which reaches here
+    // for unit and struct variants.
     fn deserialize_any<V>(self, _: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        todo!()
+        Err(Error::NotSelfDescribing)
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -71,11 +959,15 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         V: de::Visitor<'de>,
     {
         let mut buf = [0u8; 1];
-        self.reader.read_exact(&mut buf).or(Err(Error::Read))?;
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| self.read_error())?;
         match buf[0] {
             0 => visitor.visit_bool(false),
             1 => visitor.visit_bool(true),
-            _ => Err(Error::Read),
+            _ => Err(Error::Read {
+                offset: self.reader.offset - 1,
+            }),
         }
     }
 
@@ -84,7 +976,9 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         V: de::Visitor<'de>,
     {
         let mut buf = [0u8; 1];
-        self.reader.read_exact(&mut buf).or(Err(Error::Read))?;
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| self.read_error())?;
         visitor.visit_i8(i8::from_le_bytes(buf))
     }
 
@@ -93,9 +987,9 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         V: de::Visitor<'de>,
     {
         visitor.visit_i16(
-            u16::decode_prefix_varint(self.reader)
+            u16::decode_prefix_varint(&mut self.reader)
                 .map(i16::decode_zigzag)
-                .or(Err(Error::Read))?,
+                .map_err(|e| self.varint_error(e))?,
         )
     }
 
@@ -104,9 +998,9 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         V: de::Visitor<'de>,
     {
         visitor.visit_i32(
-            u32::decode_prefix_varint(self.reader)
+            u32::decode_prefix_varint(&mut self.reader)
                 .map(i32::decode_zigzag)
-                .or(Err(Error::Read))?,
+                .map_err(|e| self.varint_error(e))?,
         )
     }
 
@@ -115,29 +1009,31 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         V: de::Visitor<'de>,
     {
         visitor.visit_i64(
-            u64::decode_prefix_varint(self.reader)
+            u64::decode_prefix_varint(&mut self.reader)
                 .map(i64::decode_zigzag)
-                .or(Err(Error::Read))?,
+                .map_err(|e| self.varint_error(e))?,
         )
     }
 
-    // fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    // where
-    //     V: de::Visitor<'de>,
-    // {
-    //     visitor.visit_i128(
-    //         u128::decode_leb128(self.reader)
-    //             .map(i128::decode_zigzag)
-    //             .or(Err(Error::Read))?,
-    //     )
-    // }
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_i128(
+            u128::decode_leb128(&mut self.reader)
+                .map(i128::decode_zigzag)
+                .map_err(|e| self.varint_error(e))?,
+        )
+    }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
         let mut buf = [0u8; 1];
-        self.reader.read_exact(&mut buf).or(Err(Error::Read))?;
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| self.read_error())?;
         visitor.visit_u8(u8::from_le_bytes(buf))
     }
 
@@ -145,36 +1041,44 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_u16(u16::decode_prefix_varint(self.reader).or(Err(Error::Read))?)
+        visitor.visit_u16(
+            u16::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?,
+        )
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_u32(u32::decode_prefix_varint(self.reader).or(Err(Error::Read))?)
+        visitor.visit_u32(
+            u32::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?,
+        )
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_u64(u64::decode_prefix_varint(self.reader).or(Err(Error::Read))?)
+        visitor.visit_u64(
+            u64::decode_prefix_varint(&mut self.reader).map_err(|e| self.varint_error(e))?,
+        )
     }
 
-    // fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    // where
-    //     V: de::Visitor<'de>,
-    // {
-    //     visitor.visit_u128(u128::decode_leb128(self.reader).or(Err(Error::Read))?)
-    // }
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_u128(u128::decode_leb128(&mut self.reader).map_err(|e| self.varint_error(e))?)
+    }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
         let mut buf = [0u8; 4];
-        self.reader.read_exact(&mut buf).or(Err(Error::Read))?;
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| self.read_error())?;
         visitor.visit_f32(f32::from_le_bytes(buf))
     }
 
@@ -183,7 +1087,9 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         V: de::Visitor<'de>,
     {
         let mut buf = [0u8; 8];
-        self.reader.read_exact(&mut buf).or(Err(Error::Read))?;
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| self.read_error())?;
         visitor.visit_f64(f64::from_le_bytes(buf))
     }
 
@@ -200,83 +1106,66 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         )
     }
 
-    fn deserialize_str<V>(self, _: V) -> Result<V::Value, Self::Error>
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        todo!()
+        // Only a slice input from `from_slice` can lend the string for `'de`; otherwise it is
+        // transient and `&'de str` targets fail.
+        let s = if self.config.string_interning {
+            self.deserialize_interned_string()?
+        } else {
+            let len = self.deserialize_string_len()?;
+            if let Some(v) = self.borrow_bytes(len)? {
+                let v = std::str::from_utf8(v).map_err(|_| self.read_error())?;
+                return visitor.visit_borrowed_str(v);
+            }
+            self.read_string(len)?
+        };
+        visitor.visit_str(&s)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        let len = u64::decode_prefix_varint(self.reader).or(Err(Error::Read))?;
-        const MAX_SIZE: u64 = 128;
-        if len < MAX_SIZE {
-            let mut body_buf = [0; MAX_SIZE as usize];
-            self.reader
-                .read_exact(&mut body_buf[..(len as usize)])
-                .or(Err(Error::Read))?;
-            visitor.visit_string(
-                String::from_utf8(body_buf[..(len as usize)].to_vec()).or(Err(Error::Read))?,
-            )
-        } else {
-            let mut s = String::new();
-            if self
-                .reader
-                .take(len as u64)
-                .read_to_string(&mut s)
-                .or(Err(Error::Read))?
-                != len as usize
-            {
-                return Err(Error::Read);
-            };
-            visitor.visit_string(s)
+        if self.config.string_interning {
+            return visitor.visit_string(self.deserialize_interned_string()?);
         }
+        let len = self.deserialize_string_len()?;
+        visitor.visit_string(self.read_string(len)?)
     }
 
-    fn deserialize_bytes<V>(self, _: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        todo!()
+        // As with strings, the bytes are only borrowed from a slice input.
+        let len = self.deserialize_binary_len()?;
+        match self.borrow_bytes(len)? {
+            Some(v) => visitor.visit_borrowed_bytes(v),
+            None => visitor.visit_bytes(&self.read_bytes(len)?),
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        let len = u64::decode_prefix_varint(self.reader).or(Err(Error::Read))?;
-        const MAX_SIZE: u64 = 4096;
-        if len > MAX_SIZE {
-            let mut result = Vec::new();
-            let mut buf = vec![0; MAX_SIZE as usize];
-            let mut pos = 0;
-            while result.len() < len as usize {
-                self.reader
-                    .read_exact(&mut buf[..(min(MAX_SIZE, len - pos)) as usize])
-                    .or(Err(Error::Read))?;
-                result.extend_from_slice(&buf[..(min(MAX_SIZE, len - pos)) as usize]);
-                pos += min(MAX_SIZE, len - pos);
-            }
-            visitor.visit_byte_buf(result)
-        } else {
-            let mut buf = vec![0; len as usize];
-            self.reader.read_exact(&mut buf).or(Err(Error::Read))?;
-            visitor.visit_byte_buf(buf)
-        }
+        visitor.visit_byte_buf(self.read_byte_buf()?)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        if bool::deserialize(&mut *self)? {
-            visitor.visit_some(self)
-        } else {
-            visitor.visit_none()
-        }
+        self.nested(|de| {
+            if bool::deserialize(&mut *de)? {
+                visitor.visit_some(de)
+            } else {
+                visitor.visit_none()
+            }
+        })
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -299,48 +1188,93 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
+        // A `format::fixed_width` sequence is read in one go unless its elements were transformed.
+        if let Some(size) = fixed_width::element_size(name) {
+            if self.config.columnar_arrays
+                || self.config.delta_encode_arrays
+                || self.config.rle_arrays
+            {
+                return self.deserialize_seq(visitor);
+            }
+            let len = self.deserialize_seq_len()?;
+            let size = len
+                .checked_mul(size as u64)
+                .ok_or_else(|| self.read_error())?;
+            return visitor.visit_byte_buf(self.read_bytes(size)?);
+        }
         visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        let count = u64::decode_prefix_varint(self.reader).or(Err(Error::Read))?;
-        visitor.visit_seq(SeqDeserializer::new(&mut self, count as usize))
+        self.nested(|de| {
+            let count = de.deserialize_seq_len()?;
+            if de.config.columnar_arrays || de.config.delta_encode_arrays || de.config.rle_arrays {
+                return visitor.visit_seq(ElementSeqDeserializer {
+                    deserializer: de,
+                    count: count as usize,
+                    started: false,
+                    prev: None,
+                    repeat: 0,
+                });
+            }
+            visitor.visit_seq(SeqDeserializer::new(de, count as usize))
+        })
     }
 
-    fn deserialize_tuple<V>(mut self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_seq(SeqDeserializer::new(&mut self, len))
+        self.nested(|de| visitor.visit_seq(SeqDeserializer::new(de, len)))
     }
 
     fn deserialize_tuple_struct<V>(
-        mut self,
-        _name: &'static str,
+        self,
+        name: &'static str,
         len: usize,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_seq(SeqDeserializer::new(&mut self, len))
+        self.nested(|de| {
+            if name == BYTE_ARRAY_NAME {
+                let mut buf = vec![0u8; len];
+                de.reader
+                    .read_exact(&mut buf)
+                    .map_err(|_| de.read_error())?;
+                return visitor.visit_byte_buf(buf);
+            }
+            if name == BIG_DECIMAL_NAME {
+                return de.deserialize_big_decimal(visitor);
+            }
+            if name == BIG_UINT_NAME || name == BIG_INT_NAME {
+                return visitor.visit_seq(BigNumDeserializer {
+                    signed_bytes: Some(de.read_bignum_bytes()?),
+                    scale: None,
+                });
+            }
+            visitor.visit_seq(SeqDeserializer::new(de, len))
+        })
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        let count = u64::decode_prefix_varint(self.reader).or(Err(Error::Read))?;
-        visitor.visit_map(MapDeserializer::new(&mut self, count as usize))
+        self.nested(|de| {
+            let count = de.deserialize_map_len()?;
+            visitor.visit_map(MapDeserializer::new(de, count as usize))
+        })
     }
 
     fn deserialize_struct<V>(
@@ -352,11 +1286,21 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_map(StructDeserializer::new(self, fields))
+        self.nested(|de| {
+            if de.config.bitmap_structs {
+                let presence = de.deserialize_presence_bitmap(fields.len())?;
+                return visitor.visit_map(StructDeserializer {
+                    deserializer: de,
+                    keys: fields.iter(),
+                    presence: Some(presence.into_iter()),
+                });
+            }
+            visitor.visit_map(StructDeserializer::new(de, fields))
+        })
     }
 
     fn deserialize_enum<V>(
-        mut self,
+        self,
         _name: &'static str,
         _variants: &'static [&'static str],
         visitor: V,
@@ -364,7 +1308,7 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_enum(VariantDeserializer::new(&mut self))
+        self.nested(|mut de| visitor.visit_enum(VariantDeserializer::new(&mut de)))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -374,11 +1318,12 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
         self.deserialize_u16(visitor)
     }
 
+    // Skipping a value needs its length, which only a Header knows.
     fn deserialize_ignored_any<V>(self, _: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        todo!()
+        Err(Error::NotSelfDescribing)
     }
 
     #[inline]
@@ -410,16 +1355,45 @@ impl<'a, 'de: 'a, R: Read> de::SeqAccess<'de> for SeqDeserializer<'a, 'de, R> {
     {
         if self.count > 0 {
             self.count -= 1;
+            self.deserializer.check_deadline()?;
             seed.deserialize(&mut *self.deserializer).map(Some)
         } else {
             Ok(None)
         }
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(min(self.count as u64, MAX_PREALLOCATION) as usize)
+    }
+}
+
+struct BigNumDeserializer {
+    signed_bytes: Option<Vec<u8>>,
+    scale: Option<i64>,
+}
+
+impl<'de> de::SeqAccess<'de> for BigNumDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if let Some(signed_bytes) = self.signed_bytes.take() {
+            seed.deserialize(de::value::SeqDeserializer::new(signed_bytes.into_iter()))
+                .map(Some)
+        } else if let Some(scale) = self.scale.take() {
+            seed.deserialize(scale.into_deserializer()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 struct MapDeserializer<'a, 'de: 'a, R: Read> {
     deserializer: &'a mut Deserializer<'de, R>,
     count: usize,
+    prev_key: Option<String>,
 }
 
 impl<'a, 'de: 'a, R: Read> MapDeserializer<'a, 'de, R> {
@@ -427,6 +1401,7 @@ impl<'a, 'de: 'a, R: Read> MapDeserializer<'a, 'de, R> {
         Self {
             deserializer,
             count,
+            prev_key: None,
         }
     }
 }
@@ -440,6 +1415,11 @@ impl<'a, 'de: 'a, R: Read> de::MapAccess<'de> for MapDeserializer<'a, 'de, R> {
     {
         if self.count > 0 {
             self.count -= 1;
+            self.deserializer.check_deadline()?;
+            if self.deserializer.config.require_sorted_map_keys {
+                let key = self.deserializer.deserialize_map_key(&mut self.prev_key)?;
+                return seed.deserialize(key.into_deserializer()).map(Some);
+            }
             seed.deserialize(&mut *self.deserializer).map(Some)
         } else {
             Ok(None)
@@ -457,6 +1437,7 @@ impl<'a, 'de: 'a, R: Read> de::MapAccess<'de> for MapDeserializer<'a, 'de, R> {
 struct StructDeserializer<'a, 'de: 'a, R: Read> {
     deserializer: &'a mut Deserializer<'de, R>,
     keys: Iter<'a, &'static str>,
+    presence: Option<vec::IntoIter<bool>>,
 }
 
 impl<'a, 'de: 'a, R: Read> StructDeserializer<'a, 'de, R> {
@@ -464,6 +1445,7 @@ impl<'a, 'de: 'a, R: Read> StructDeserializer<'a, 'de, R> {
         Self {
             deserializer,
             keys: keys.iter(),
+            presence: None,
         }
     }
 }
@@ -487,7 +1469,333 @@ impl<'a, 'de: 'a, R: Read> de::MapAccess<'de> for StructDeserializer<'a, 'de, R>
     where
         V: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.deserializer)
+        match self.presence.as_mut().and_then(|v| v.next()) {
+            Some(present) => seed.deserialize(PresenceDeserializer {
+                de: &mut *self.deserializer,
+                present,
+            }),
+            None => seed.deserialize(&mut *self.deserializer),
+        }
+    }
+}
+
+// Reads a struct field in bitmap mode: a top-level `Option` takes its presence from the
+// bitmap instead of a tag, everything else is forwarded unchanged.
+struct PresenceDeserializer<'a, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    present: bool,
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                self.de.$method(visitor)
+            }
+        )*
+    };
+}
+
+// Everything except `Option`, plain structs and integers up to 64 bits is forwarded unchanged by
+// the wrappers below.
+macro_rules! forward_deserialize_compound {
+    () => {
+        forward_deserialize! {
+            deserialize_any,
+            deserialize_bool,
+            deserialize_i128,
+            deserialize_u128,
+            deserialize_f32,
+            deserialize_f64,
+            deserialize_char,
+            deserialize_str,
+            deserialize_string,
+            deserialize_bytes,
+            deserialize_byte_buf,
+            deserialize_unit,
+            deserialize_seq,
+            deserialize_map,
+            deserialize_identifier,
+            deserialize_ignored_any,
+        }
+
+        fn deserialize_unit_struct<V>(
+            self,
+            name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.de.deserialize_unit_struct(name, visitor)
+        }
+
+        fn deserialize_newtype_struct<V>(
+            self,
+            name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.de.deserialize_newtype_struct(name, visitor)
+        }
+
+        fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.de.deserialize_tuple(len, visitor)
+        }
+
+        fn deserialize_tuple_struct<V>(
+            self,
+            name: &'static str,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.de.deserialize_tuple_struct(name, len, visitor)
+        }
+
+        fn deserialize_enum<V>(
+            self,
+            name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.de.deserialize_enum(name, variants, visitor)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+    };
+}
+
+impl<'a, 'de: 'a, R: Read> de::Deserializer<'de> for PresenceDeserializer<'a, 'de, R> {
+    type Error = Error;
+
+    forward_deserialize_compound!();
+
+    forward_deserialize! {
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.present {
+            visitor.visit_some(self.de)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.de.deserialize_struct(name, fields, visitor)
+    }
+}
+
+struct ElementSeqDeserializer<'a, 'de: 'a, R: Read> {
+    deserializer: &'a mut Deserializer<'de, R>,
+    count: usize,
+    started: bool,
+    prev: Option<u64>,
+    // How many more times `prev` repeats in the current run of a run-length encoded array.
+    repeat: u64,
+}
+
+impl<'a, 'de: 'a, R: Read> de::SeqAccess<'de> for ElementSeqDeserializer<'a, 'de, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.count > 0 {
+            self.count -= 1;
+            self.deserializer.check_deadline()?;
+            seed.deserialize(ElementDeserializer {
+                de: &mut *self.deserializer,
+                started: &mut self.started,
+                prev: &mut self.prev,
+                repeat: &mut self.repeat,
+            })
+            .map(Some)
+        } else {
+            // A run may not reach past the end of the array.
+            if self.repeat > 0 {
+                return Err(self.deserializer.read_error());
+            }
+            if self.started {
+                self.started = false;
+                self.deserializer.pop_columns()?;
+            }
+            Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(min(self.count as u64, MAX_PREALLOCATION) as usize)
+    }
+}
+
+macro_rules! delta_deserialize {
+    ($($method:ident($ty:ty, $wide:ty, $visit:ident)),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                if self.de.config.rle_arrays {
+                    let v = match *self.prev {
+                        Some(bits) if *self.repeat > 0 => {
+                            *self.repeat -= 1;
+                            <$ty>::try_from(bits as $wide).map_err(|_| self.de.read_error())?
+                        }
+                        _ => {
+                            let v = <$ty>::deserialize(&mut *self.de)?;
+                            let len = u64::deserialize(&mut *self.de)?;
+                            if len == 0 {
+                                return Err(self.de.read_error());
+                            }
+                            *self.prev = Some(v as $wide as u64);
+                            *self.repeat = len - 1;
+                            v
+                        }
+                    };
+                    return visitor.$visit(v);
+                }
+                if !self.de.config.delta_encode_arrays {
+                    return self.de.$method(visitor);
+                }
+                let v = match *self.prev {
+                    Some(prev) => {
+                        let bits = prev.wrapping_add(i64::deserialize(&mut *self.de)? as u64);
+                        <$ty>::try_from(bits as $wide).map_err(|_| self.de.read_error())?
+                    }
+                    None => <$ty>::deserialize(&mut *self.de)?,
+                };
+                *self.prev = Some(v as $wide as u64);
+                visitor.$visit(v)
+            }
+        )*
+    };
+}
+
+// Reads an element of a columnar, delta-encoded or run-length encoded array: the columns are read
+// in full at the first struct element and each field is then taken from its own column, and
+// integers after the first are read as the difference from the previous one or taken from the
+// current run.
+struct ElementDeserializer<'a, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    started: &'a mut bool,
+    prev: &'a mut Option<u64>,
+    repeat: &'a mut u64,
+}
+
+impl<'a, 'de: 'a, R: Read> de::Deserializer<'de> for ElementDeserializer<'a, 'de, R> {
+    type Error = Error;
+
+    forward_deserialize_compound!();
+
+    delta_deserialize! {
+        deserialize_i8(i8, i64, visit_i8),
+        deserialize_i16(i16, i64, visit_i16),
+        deserialize_i32(i32, i64, visit_i32),
+        deserialize_i64(i64, i64, visit_i64),
+        deserialize_u8(u8, u64, visit_u8),
+        deserialize_u16(u16, u64, visit_u16),
+        deserialize_u32(u32, u64, visit_u32),
+        deserialize_u64(u64, u64, visit_u64),
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.de.deserialize_option(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if !self.de.config.columnar_arrays {
+            return self.de.deserialize_struct(name, fields, visitor);
+        }
+        if !*self.started {
+            self.de.push_columns(fields.len())?;
+            *self.started = true;
+        }
+        visitor.visit_map(ElementStructDeserializer {
+            deserializer: self.de,
+            keys: fields.iter(),
+            index: 0,
+        })
+    }
+}
+
+struct ElementStructDeserializer<'a, 'de: 'a, R: Read> {
+    deserializer: &'a mut Deserializer<'de, R>,
+    keys: Iter<'a, &'static str>,
+    index: usize,
+}
+
+impl<'a, 'de: 'a, R: Read> de::MapAccess<'de> for ElementStructDeserializer<'a, 'de, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.keys.next() {
+            Some(&key) => seed
+                .deserialize(serde::de::value::BorrowedStrDeserializer::new(key))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        self.deserializer.activate_column(Some(self.index));
+        self.index += 1;
+        let result = seed.deserialize(&mut *self.deserializer);
+        self.deserializer.activate_column(None);
+        result
     }
 }
 
@@ -509,7 +1817,12 @@ impl<'de, 'a, R: Read> de::EnumAccess<'de> for VariantDeserializer<'de, 'a, R> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        Ok((seed.deserialize(&mut *self.de)?, self))
+        if self.de.compact_tags == Some(true) {
+            let index = self.de.deserialize_variant_index()?;
+            Ok((seed.deserialize(index.into_deserializer())?, self))
+        } else {
+            Ok((seed.deserialize(&mut *self.de)?, self))
+        }
     }
 }
 
@@ -542,373 +1855,2828 @@ impl<'de, 'a, R: Read> de::VariantAccess<'de> for VariantDeserializer<'de, 'a, R
     where
         V: de::Visitor<'de>,
     {
-        de::Deserializer::deserialize_struct(self.de, "", fields, visitor)
+        // Struct variants are written without a presence bitmap, even with `bitmap_structs` set.
+        visitor.visit_map(StructDeserializer::new(self.de, fields))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{de::Deserializer, ser::Serializer};
-    use serde::{Deserialize, Serialize};
-    use serde_bytes::ByteBuf;
-    use std::collections::{BTreeMap, HashMap};
+fn peel(mut header: &Header) -> &Header {
+    while let Header::Annotated(inner, _) | Header::Tagged(inner, _) = header {
+        header = inner;
+    }
+    header
+}
 
-    #[test]
-    fn deserialize_bool() {
-        IntoIterator::into_iter([true, false]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-        });
+struct HeaderDeserializer<'a, 'h, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    header: &'h Header,
+    // Set for an `Optional` field of a `BitmapStruct`, whose presence is in the bitmap.
+    present: Option<bool>,
+    // Set for a row of a `Columnar` array, whose fields are read from their columns.
+    row: bool,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> HeaderDeserializer<'a, 'h, 'de, R> {
+    fn new(de: &'a mut Deserializer<'de, R>, header: &'h Header) -> Self {
+        Self {
+            de,
+            header: peel(header),
+            present: None,
+            row: false,
+        }
     }
 
-    #[test]
-    fn deserialize_i8() {
-        IntoIterator::into_iter([i8::MIN, 0, i8::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-        });
+    fn visit_tuple<V>(self, headers: &'h [Header], visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let column = if self.row { Some(0) } else { None };
+        self.de.nested(|de| {
+            visit_fields(
+                HeaderSeqDeserializer {
+                    de,
+                    headers: headers.iter().peekable(),
+                    presence: None,
+                    column,
+                },
+                visitor,
+            )
+        })
     }
+}
 
-    #[test]
-    fn deserialize_i16() {
-        IntoIterator::into_iter([i16::MIN, 0, i16::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, i16::deserialize(&mut deserializer).unwrap());
-        });
+// Anything the header does not describe the way serde asks for it falls back to the plain
+// deserializer.
+impl<'a, 'h, 'de: 'a, R: Read> de::Deserializer<'de> for HeaderDeserializer<'a, 'h, 'de, R> {
+    type Error = Error;
+
+    // The header makes the data self-describing, which is what buffered values such as the fields
+    // around a `#[serde(flatten)]` need.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::Unit | Header::Null => self.deserialize_unit(visitor),
+            Header::Optional(_) => self.deserialize_option(visitor),
+            Header::Boolean => self.deserialize_bool(visitor),
+            Header::UInt8 => self.deserialize_u8(visitor),
+            Header::UInt16 => self.deserialize_u16(visitor),
+            Header::UInt32 => self.deserialize_u32(visitor),
+            Header::UInt64 => self.deserialize_u64(visitor),
+            Header::Int8 => self.deserialize_i8(visitor),
+            Header::Int16 => self.deserialize_i16(visitor),
+            Header::Int32 => self.deserialize_i32(visitor),
+            Header::Int64 => self.deserialize_i64(visitor),
+            Header::Float32 => self.deserialize_f32(visitor),
+            Header::Float64 | Header::FixedPoint { .. } => self.deserialize_f64(visitor),
+            Header::String | Header::InternedString => self.deserialize_string(visitor),
+            Header::Binary => self.deserialize_byte_buf(visitor),
+            Header::Array(_)
+            | Header::Columnar(_)
+            | Header::DeltaArray(_)
+            | Header::RleArray(_)
+            | Header::SegmentedArray(_) => self.deserialize_seq(visitor),
+            Header::Tuple(inner) | Header::BitmapStruct(inner) => {
+                let len = inner.len();
+                self.deserialize_tuple(len, visitor)
+            }
+            Header::Map(_) | Header::FlattenedMap(..) => self.deserialize_map(visitor),
+            _ => self.de.deserialize_any(visitor),
+        }
     }
 
-    #[test]
-    fn deserialize_i32() {
-        IntoIterator::into_iter([i32::MIN, 0, i32::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, i32::deserialize(&mut deserializer).unwrap());
-        });
+    forward_deserialize! {
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_char,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_unit,
+        deserialize_identifier,
     }
 
-    #[test]
-    fn deserialize_i64() {
-        IntoIterator::into_iter([i64::MIN, 0, i64::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::FixedPoint { scale } => {
+                let v = i64::deserialize(&mut *self.de)?;
+                visitor.visit_f32(fixed_point::decode(v, *scale) as f32)
+            }
+            _ => self.de.deserialize_f32(visitor),
+        }
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::FixedPoint { scale } => {
+                let v = i64::deserialize(&mut *self.de)?;
+                visitor.visit_f64(fixed_point::decode(v, *scale))
+            }
+            _ => self.de.deserialize_f64(visitor),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::InternedString => visitor.visit_str(&self.de.deserialize_interned_string()?),
+            _ => self.de.deserialize_str(visitor),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::InternedString => visitor.visit_string(self.de.deserialize_interned_string()?),
+            _ => self.de.deserialize_string(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::Optional(inner) => {
+                let present = self.present;
+                self.de.nested(|de| {
+                    let present = match present {
+                        Some(present) => present,
+                        None => bool::deserialize(&mut *de)?,
+                    };
+                    if present {
+                        visitor.visit_some(HeaderDeserializer::new(de, inner))
+                    } else {
+                        visitor.visit_none()
+                    }
+                })
+            }
+            _ => self.de.deserialize_option(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.de.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // A `format::fixed_width` sequence is read in one go unless its elements were transformed.
+        if fixed_width::element_size(name).is_some() {
+            return match self.header {
+                Header::Columnar(_)
+                | Header::DeltaArray(_)
+                | Header::RleArray(_)
+                | Header::SegmentedArray(_) => self.deserialize_seq(visitor),
+                _ => self.de.deserialize_newtype_struct(name, visitor),
+            };
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::DeltaArray(inner) | Header::RleArray(inner) if inner.is_delta_integer() => {
+                let rle = matches!(self.header, Header::RleArray(_));
+                self.de.nested(|de| {
+                    let count = de.deserialize_seq_len()?;
+                    visit_fields(
+                        HeaderIntegerSeqDeserializer {
+                            de,
+                            header: inner,
+                            count,
+                            rle,
+                            prev: None,
+                            repeat: 0,
+                        },
+                        visitor,
+                    )
+                })
+            }
+            Header::Array(inner) | Header::DeltaArray(inner) | Header::RleArray(inner) => {
+                self.de.nested(|de| {
+                    let len = de.deserialize_seq_len()?;
+                    visit_header_seq(de, iter::repeat_n(inner.as_ref(), len as usize), visitor)
+                })
+            }
+            Header::SegmentedArray(inner) => self.de.nested(|de| {
+                visit_fields(
+                    HeaderSegmentedDeserializer {
+                        de,
+                        header: inner,
+                        count: 0,
+                        total: 0,
+                        done: false,
+                    },
+                    visitor,
+                )
+            }),
+            Header::Columnar(inner) => self.de.nested(|de| {
+                let count = de.deserialize_seq_len()?;
+                match inner.columns() {
+                    Some(fields) if count > 0 => {
+                        de.push_columns(fields.len())?;
+                        let result = visit_fields(
+                            HeaderRowsDeserializer {
+                                de: &mut *de,
+                                header: inner,
+                                count,
+                            },
+                            visitor,
+                        );
+                        let popped = de.pop_columns();
+                        result.and_then(|value| popped.map(|_| value))
+                    }
+                    _ => visit_header_seq(
+                        de,
+                        iter::repeat_n(inner.as_ref(), count as usize),
+                        visitor,
+                    ),
+                }
+            }),
+            _ => self.de.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::Tuple(inner) => self.visit_tuple(inner, visitor),
+            Header::BitmapStruct(inner) => {
+                self.de.nested(|de| visit_bitmap_struct(de, inner, visitor))
+            }
+            // `BigDecimal` decodes as a 2-tuple; the header is what says to apply the limits.
+            Header::BigDecimal => self.de.nested(|de| de.deserialize_big_decimal(visitor)),
+            _ => self.de.deserialize_tuple(len, visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::Tuple(inner) => self.visit_tuple(inner, visitor),
+            Header::BitmapStruct(inner) => {
+                self.de.nested(|de| visit_bitmap_struct(de, inner, visitor))
+            }
+            _ => self.de.deserialize_tuple_struct(name, len, visitor),
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let (fields, extra): (&[(String, Header)], &Header) = match self.header {
+            Header::Map(inner) => (&[], inner),
+            Header::FlattenedMap(fields, extra) => (fields, extra),
+            _ => return self.de.deserialize_map(visitor),
+        };
+        self.de.nested(|de| {
+            let count = de.deserialize_map_len()?;
+            visitor.visit_map(HeaderMapDeserializer {
+                de,
+                fields,
+                extra,
+                header: extra,
+                count,
+                prev_key: None,
+            })
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.header {
+            Header::Tuple(inner) => self.visit_tuple(inner, visitor),
+            Header::BitmapStruct(inner) => {
+                self.de.nested(|de| visit_bitmap_struct(de, inner, visitor))
+            }
+            _ => self.de.deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // A typed enum has nowhere to keep a variant an `OpenEnum` does not list, so that stays an
+        // error.
+        let (inner, open) = match self.header {
+            Header::Enum(inner) => (inner, false),
+            Header::OpenEnum(inner) => (inner, true),
+            _ => return self.de.deserialize_enum(name, variants, visitor),
+        };
+        self.de.nested(|de| {
+            visitor.visit_enum(HeaderVariantDeserializer {
+                de,
+                variants: inner,
+                header: &Header::Unit,
+                open,
+            })
+        })
+    }
+
+    // The header gives the length of whatever the visitor leaves unread.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.row {
+            return self.deserialize_tuple(0, visitor);
+        }
+        match self.present {
+            Some(present) => self.de.skip_bitmap_field(self.header, present)?,
+            None => self.de.skip_value(self.header)?,
+        }
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// Reserved slots are skipped between the fields the visitor asks for.
+fn visit_header_seq<'h, 'de, R: Read, I: Iterator<Item = &'h Header>, V: de::Visitor<'de>>(
+    de: &mut Deserializer<'de, R>,
+    headers: I,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    visit_fields(
+        HeaderSeqDeserializer {
+            de,
+            headers: headers.peekable(),
+            presence: None,
+            column: None,
+        },
+        visitor,
+    )
+}
+
+// Like `visit_header_seq`, after reading the bitmap that holds the presence of the `Optional`
+// fields. Every field has a bit, but only those of `Optional` fields are used.
+fn visit_bitmap_struct<'de, R: Read, V: de::Visitor<'de>>(
+    de: &mut Deserializer<'de, R>,
+    headers: &[Header],
+    visitor: V,
+) -> Result<V::Value, Error> {
+    let presence = de.deserialize_presence_bitmap(headers.len())?;
+    visit_fields(
+        HeaderSeqDeserializer {
+            de,
+            headers: headers.iter().peekable(),
+            presence: Some(presence.into_iter()),
+            column: None,
+        },
+        visitor,
+    )
+}
+
+// Whatever the visitor leaves unread, such as fields a newer schema appended or reserved slots
+// after the last field, is skipped by its header so the input after it stays aligned.
+fn visit_fields<'de, S: de::SeqAccess<'de, Error = Error>, V: de::Visitor<'de>>(
+    mut seq: S,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    let value = visitor.visit_seq(&mut seq)?;
+    while seq.next_element::<de::IgnoredAny>()?.is_some() {}
+    Ok(value)
+}
+
+struct HeaderSeqDeserializer<'a, 'de: 'a, R: Read, I: Iterator> {
+    de: &'a mut Deserializer<'de, R>,
+    headers: Peekable<I>,
+    presence: Option<vec::IntoIter<bool>>,
+    // The column of the next field, when the fields are the columns of a columnar array.
+    column: Option<usize>,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read, I: Iterator<Item = &'h Header>>
+    HeaderSeqDeserializer<'a, 'de, R, I>
+{
+    fn skip_reserved(&mut self) -> Result<(), Error> {
+        while let Some(header @ Header::Reserved(inner)) = self.headers.peek().copied().map(peel) {
+            let present = self.presence.as_mut().and_then(|v| v.next());
+            self.next_field(|de| match present {
+                Some(present) => de.skip_bitmap_field(header, present),
+                None => de.skip_value(inner),
+            })?;
+            self.headers.next();
+        }
+        Ok(())
+    }
+
+    fn next_field<T>(
+        &mut self,
+        f: impl FnOnce(&mut Deserializer<'de, R>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let column = self.column;
+        if let Some(index) = column {
+            self.column = Some(index + 1);
+            self.de.activate_column(Some(index));
+        }
+        let result = f(self.de);
+        if column.is_some() {
+            self.de.activate_column(None);
+        }
+        result
+    }
+}
+
+impl<'a, 'h, 'de: 'a, R: Read, I: Iterator<Item = &'h Header>> de::SeqAccess<'de>
+    for HeaderSeqDeserializer<'a, 'de, R, I>
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        self.skip_reserved()?;
+        self.de.check_deadline()?;
+        match self.headers.next() {
+            Some(header) => {
+                let present = self.presence.as_mut().and_then(|v| v.next());
+                self.next_field(|de| {
+                    seed.deserialize(HeaderDeserializer {
+                        present: present.filter(|_| header.bitmap_optional().is_some()),
+                        ..HeaderDeserializer::new(de, header)
+                    })
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.headers
+            .size_hint()
+            .1
+            .map(|len| min(len as u64, MAX_PREALLOCATION) as usize)
+    }
+}
+
+// The elements of a delta or run-length encoded integer array. Every element after the first is
+// the difference from the previous one, or each is a (value, run length) pair expanded here.
+struct HeaderIntegerSeqDeserializer<'a, 'h, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    header: &'h Header,
+    count: u64,
+    rle: bool,
+    prev: Option<u64>,
+    // How many more times `prev` repeats in the current run.
+    repeat: u64,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> de::SeqAccess<'de> for HeaderIntegerSeqDeserializer<'a, 'h, 'de, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.count == 0 {
+            return Ok(None);
+        }
+        self.count -= 1;
+        self.de.check_deadline()?;
+        let bits = match self.prev {
+            Some(bits) if self.rle && self.repeat > 0 => {
+                self.repeat -= 1;
+                bits
+            }
+            Some(bits) if !self.rle => bits.wrapping_add(i64::deserialize(&mut *self.de)? as u64),
+            _ => {
+                let bits = self.de.deserialize_integer_bits(self.header)?;
+                if self.rle {
+                    // A run may not reach past the end of the array.
+                    let run = u64::deserialize(&mut *self.de)?;
+                    if run == 0 || run - 1 > self.count {
+                        return Err(self.de.read_error());
+                    }
+                    self.repeat = run - 1;
+                }
+                bits
+            }
+        };
+        self.prev = Some(bits);
+        visit_integer_bits(self.de, self.header, bits, seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(min(self.count, MAX_PREALLOCATION) as usize)
+    }
+}
+
+// Hands `bits` to `seed` as an integer of schema `header`, failing when it is out of range.
+fn visit_integer_bits<'de, R: Read, T: de::DeserializeSeed<'de>>(
+    de: &Deserializer<'de, R>,
+    header: &Header,
+    bits: u64,
+    seed: T,
+) -> Result<T::Value, Error> {
+    let value = match peel(header) {
+        Header::UInt8 => u8::try_from(bits)
+            .ok()
+            .map(|v| seed.deserialize(v.into_deserializer())),
+        Header::UInt16 => u16::try_from(bits)
+            .ok()
+            .map(|v| seed.deserialize(v.into_deserializer())),
+        Header::UInt32 => u32::try_from(bits)
+            .ok()
+            .map(|v| seed.deserialize(v.into_deserializer())),
+        Header::UInt64 => Some(seed.deserialize(bits.into_deserializer())),
+        Header::Int8 => i8::try_from(bits as i64)
+            .ok()
+            .map(|v| seed.deserialize(v.into_deserializer())),
+        Header::Int16 => i16::try_from(bits as i64)
+            .ok()
+            .map(|v| seed.deserialize(v.into_deserializer())),
+        Header::Int32 => i32::try_from(bits as i64)
+            .ok()
+            .map(|v| seed.deserialize(v.into_deserializer())),
+        Header::Int64 => Some(seed.deserialize((bits as i64).into_deserializer())),
+        _ => None,
+    };
+    value.unwrap_or_else(|| Err(de.read_error()))
+}
+
+// The elements of a segmented array, read a length-prefixed chunk at a time until an empty one.
+struct HeaderSegmentedDeserializer<'a, 'h, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    header: &'h Header,
+    // Elements left in the current chunk.
+    count: u64,
+    total: u64,
+    done: bool,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> de::SeqAccess<'de> for HeaderSegmentedDeserializer<'a, 'h, 'de, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.count == 0 {
+            if self.done {
+                return Ok(None);
+            }
+            self.count = self.de.deserialize_segment_len(&mut self.total)?;
+            if self.count == 0 {
+                self.done = true;
+                return Ok(None);
+            }
+        }
+        self.count -= 1;
+        self.de.check_deadline()?;
+        seed.deserialize(HeaderDeserializer::new(&mut *self.de, self.header))
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(min(self.count, MAX_PREALLOCATION) as usize)
+    }
+}
+
+// The rows of a columnar array, whose fields are read from the columns pushed beforehand.
+struct HeaderRowsDeserializer<'a, 'h, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    header: &'h Header,
+    count: u64,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> de::SeqAccess<'de> for HeaderRowsDeserializer<'a, 'h, 'de, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.count == 0 {
+            return Ok(None);
+        }
+        self.count -= 1;
+        self.de.check_deadline()?;
+        seed.deserialize(HeaderDeserializer {
+            row: true,
+            ..HeaderDeserializer::new(&mut *self.de, self.header)
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(min(self.count, MAX_PREALLOCATION) as usize)
+    }
+}
+
+struct HeaderMapDeserializer<'a, 'h, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    fields: &'h [(String, Header)],
+    extra: &'h Header,
+    header: &'h Header,
+    count: u64,
+    prev_key: Option<String>,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> de::MapAccess<'de> for HeaderMapDeserializer<'a, 'h, 'de, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if self.count > 0 {
+            self.count -= 1;
+            self.de.check_deadline()?;
+            // The key picks the header of the value that follows it in a flattened map.
+            if !self.fields.is_empty() || self.de.config.require_sorted_map_keys {
+                let key = self.de.deserialize_map_key(&mut self.prev_key)?;
+                self.header = Header::flattened_value(self.fields, self.extra, &key);
+                return seed.deserialize(key.into_deserializer()).map(Some);
+            }
+            seed.deserialize(&mut *self.de).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(HeaderDeserializer::new(&mut *self.de, self.header))
+    }
+}
+
+struct HeaderVariantDeserializer<'a, 'h, 'de: 'a, R: Read> {
+    de: &'a mut Deserializer<'de, R>,
+    variants: &'h [Header],
+    header: &'h Header,
+    open: bool,
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> de::EnumAccess<'de> for HeaderVariantDeserializer<'a, 'h, 'de, R> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let index = self.de.deserialize_variant_index()?;
+        self.header = self
+            .variants
+            .get(index as usize)
+            .map(peel)
+            .ok_or_else(|| self.de.read_error())?;
+        if self.open {
+            self.de.deserialize_binary_len()?;
+        }
+        Ok((seed.deserialize(index.into_deserializer())?, self))
+    }
+}
+
+impl<'a, 'h, 'de: 'a, R: Read> de::VariantAccess<'de>
+    for HeaderVariantDeserializer<'a, 'h, 'de, R>
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(HeaderDeserializer::new(self.de, self.header))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visit_header_seq(self.de, variant_fields(self.header).iter(), visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visit_header_seq(self.de, variant_fields(self.header).iter(), visitor)
+    }
+}
+
+// A variant with a single field is described by that field's header alone.
+fn variant_fields(header: &Header) -> &[Header] {
+    match header {
+        Header::Tuple(inner) => inner,
+        _ => slice::from_ref(header),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        de::{BigDecimalLimits, DecodeLimits, Deserializer, Error},
+        ser::{Serializer, SortMode},
+        BigDecimal, BigUint, Body, Header,
+    };
+    use serde::{Deserialize, Serialize};
+    use serde_bytes::{ByteBuf, Bytes};
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, HashMap},
+        num::NonZeroU32,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn deserialize_bool() {
+        IntoIterator::into_iter([true, false]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, bool::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_i8() {
+        IntoIterator::into_iter([i8::MIN, 0, i8::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, i8::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_i16() {
+        IntoIterator::into_iter([i16::MIN, 0, i16::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, i16::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_i32() {
+        IntoIterator::into_iter([i32::MIN, 0, i32::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, i32::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_i64() {
+        IntoIterator::into_iter([i64::MIN, 0, i64::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
             assert_eq!(v, i64::deserialize(&mut deserializer).unwrap());
         });
     }
 
-    // #[test]
-    // fn deserialize_i128() {
-    //     IntoIterator::into_iter([i128::MIN, 0, i128::MAX]).for_each(|v| {
-    //         let buf = serialize(v);
-    //         let mut reader = buf.as_slice();
-    //         let mut deserializer = Deserializer::new(&mut reader);
-    //         assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-    //     });
-    // }
+    #[test]
+    fn deserialize_i128() {
+        IntoIterator::into_iter([i128::MIN, -1, 0, 1, i128::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_u8() {
+        IntoIterator::into_iter([u8::MIN, u8::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, u8::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_u16() {
+        IntoIterator::into_iter([u16::MIN, u16::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, u16::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_truncated_varint() {
+        fn deserialize<T: serde::de::DeserializeOwned>(buf: &[u8]) -> Result<T, Error> {
+            let mut reader = buf;
+            T::deserialize(&mut Deserializer::new(&mut reader))
+        }
+
+        let truncated = Error::TruncatedVarint { offset: 1 };
+        assert_eq!(deserialize::<u16>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<u32>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<u64>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<i64>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<u128>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<i128>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<String>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(deserialize::<Vec<u8>>(&[0x80]).unwrap_err(), truncated);
+        assert_eq!(
+            deserialize::<(u8, u64)>(&[7, 0xf0, 1, 2]),
+            Err(Error::TruncatedVarint { offset: 4 })
+        );
+        assert_eq!(
+            deserialize::<u128>(&[0xff, 0xff, 0x80]),
+            Err(Error::TruncatedVarint { offset: 3 })
+        );
+        assert_eq!(
+            Error::TruncatedVarint { offset: 1 }.to_string(),
+            "Truncated varint at offset 1"
+        );
+
+        // Other early ends are still plain read errors.
+        assert_eq!(deserialize::<u64>(&[]), Err(Error::Read { offset: 0 }));
+        assert_eq!(
+            deserialize::<String>(&[2, b'a']),
+            Err(Error::Read { offset: 2 })
+        );
+    }
+
+    #[test]
+    fn deserialize_u32() {
+        IntoIterator::into_iter([u32::MIN, u32::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, u32::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_u64() {
+        IntoIterator::into_iter([u64::MIN, u64::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, u64::deserialize(&mut deserializer).unwrap());
+        });
+    }
+
+    #[test]
+    fn deserialize_u128() {
+        IntoIterator::into_iter([u128::MIN, 1, u128::MAX]).for_each(|v| {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
+        })
+    }
+
+    #[test]
+    fn deserialize_struct_with_128bit_fields() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test {
+            a: u128,
+            b: i128,
+            c: bool,
+        }
+
+        let body = Test {
+            a: u128::MAX,
+            b: i128::MIN,
+            c: true,
+        };
+        let buf = serialize(&body);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(body, Test::deserialize(&mut deserializer).unwrap());
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn deserialize_f32() {
+        IntoIterator::into_iter([-f32::INFINITY, f32::MIN, 0f32, f32::MAX, f32::INFINITY])
+            .for_each(|v| {
+                let buf = serialize(v);
+                let mut reader = buf.as_slice();
+                let mut deserializer = Deserializer::new(&mut reader);
+                assert_eq!(v, f32::deserialize(&mut deserializer).unwrap());
+            });
+    }
+
+    #[test]
+    fn deserialize_f64() {
+        IntoIterator::into_iter([-f64::INFINITY, f64::MIN, 0f64, f64::MAX, f64::INFINITY])
+            .for_each(|v| {
+                let buf = serialize(v);
+                let mut reader = buf.as_slice();
+                let mut deserializer = Deserializer::new(&mut reader);
+                assert_eq!(v, f64::deserialize(&mut deserializer).unwrap());
+            });
+    }
+
+    #[test]
+    fn deserialize_char() {
+        {
+            let buf = serialize('a');
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = char::deserialize(&mut deserializer).unwrap();
+            assert_eq!('a', result)
+        }
+
+        {
+            let buf = serialize('あ');
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = char::deserialize(&mut deserializer).unwrap();
+            assert_eq!('あ', result)
+        }
+    }
+
+    #[test]
+    fn deserialize_string() {
+        let buf = serialize("test".to_string());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = String::deserialize(&mut deserializer).unwrap();
+        assert_eq!("test".to_string(), result);
+    }
+
+    #[test]
+    fn deserialize_string129_issue() {
+        // Thanks @caibear and @udoprog
+        // https://github.com/otake84/dlhn/issues/14
+        // https://github.com/otake84/dlhn/issues/15
+        let original = " ".repeat(129);
+        let mut serialized = vec![];
+        original
+            .serialize(&mut Serializer::new(&mut serialized))
+            .unwrap();
+
+        let deserialized =
+            String::deserialize(&mut Deserializer::new(&mut serialized.as_slice())).unwrap();
+        assert_eq!(original, deserialized);
+    }
+
+    #[test]
+    fn deserialize_byte_buf() {
+        let buf = serialize(ByteBuf::from(vec![0u8, 1, 2, 3, 255].repeat(1000)));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = ByteBuf::deserialize(&mut deserializer).unwrap();
+        assert_eq!([0u8, 1, 2, 3, 255].repeat(1000), result.as_slice());
+    }
+
+    #[test]
+    fn deserialize_byte_buf_4097() {
+        let buf = serialize(ByteBuf::from(vec![0u8].repeat(4097)));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = ByteBuf::deserialize(&mut deserializer).unwrap();
+        assert_eq!([0u8].repeat(4097), result.as_slice());
+    }
+
+    #[test]
+    fn deserialize_byte_buf_100000() {
+        let buf = serialize(ByteBuf::from(vec![0u8].repeat(100000)));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = ByteBuf::deserialize(&mut deserializer).unwrap();
+        assert_eq!([0u8].repeat(100000), result.as_slice());
+    }
+
+    #[test]
+    fn deserialize_bytes_and_byte_buf() {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = (&'static str, Vec<u8>);
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("bytes")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(("bytes", v.to_vec()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(("byte_buf", v))
+            }
+        }
+
+        let buf = serialize(Bytes::new(&[1, 2, 3]));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            serde::Deserializer::deserialize_bytes(&mut deserializer, Visitor).unwrap(),
+            ("bytes", vec![1, 2, 3])
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            serde::Deserializer::deserialize_byte_buf(&mut deserializer, Visitor).unwrap(),
+            ("byte_buf", vec![1, 2, 3])
+        );
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test<'a> {
+            #[serde(with = "serde_bytes")]
+            a: Vec<u8>,
+            #[serde(borrow, with = "serde_bytes")]
+            b: Cow<'a, [u8]>,
+        }
+
+        let body = Test {
+            a: vec![0, 255],
+            b: Cow::Borrowed(&[1, 2, 3]),
+        };
+        let buf = serialize(&body);
+        let mut reader = buf.as_slice();
+        let result = Test::deserialize(&mut Deserializer::new(&mut reader)).unwrap();
+        assert_eq!(body, result);
+        assert!(matches!(result.b, Cow::Owned(_)));
+
+        // Borrowing requires the input to outlive the deserializer, which a `Read` source cannot offer.
+        let mut reader = buf.as_slice();
+        assert!(<(&[u8], &[u8])>::deserialize(&mut Deserializer::new(&mut reader)).is_err());
+    }
+
+    #[test]
+    fn deserialize_borrowed() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test<'a> {
+            name: &'a str,
+            #[serde(with = "serde_bytes")]
+            data: &'a [u8],
+            #[serde(borrow)]
+            label: Cow<'a, str>,
+            tags: Vec<&'a str>,
+        }
+
+        let body = Test {
+            name: "abc",
+            data: &[1, 2, 3],
+            label: Cow::Borrowed("label"),
+            tags: vec!["x", "yz"],
+        };
+        let buf = serialize(&body);
+        let input = buf.as_slice().as_ptr_range();
+        let mut reader = buf.as_slice();
+        let result = {
+            let mut deserializer = Deserializer::from_slice(&mut reader);
+            let result = Test::deserialize(&mut deserializer).unwrap();
+            assert_eq!(deserializer.end(), Ok(()));
+            result
+        };
+        // The deserializer is gone, but every field still points into `buf`.
+        assert_eq!(result, body);
+        assert!(input.contains(&result.name.as_ptr()));
+        assert!(input.contains(&result.data.as_ptr()));
+        assert!(matches!(result.label, Cow::Borrowed(v) if input.contains(&v.as_ptr())));
+        assert!(result.tags.iter().all(|v| input.contains(&v.as_ptr())));
+
+        let mut reader = buf.as_slice();
+        let result = Test::deserialize(&mut Deserializer::from_slice(&mut reader));
+        assert_eq!(result, Ok(body));
+
+        // Truncated and invalid input is still rejected.
+        let mut reader = &buf[..5];
+        assert!(Test::deserialize(&mut Deserializer::from_slice(&mut reader)).is_err());
+        let mut reader = [2, 0xff, 0xfe].as_ref();
+        assert!(<&str>::deserialize(&mut Deserializer::from_slice(&mut reader)).is_err());
+    }
+
+    #[test]
+    fn deserialize_option() {
+        {
+            let buf = serialize(Option::<u8>::None);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = <Option<u8>>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(None, result);
+        }
+
+        {
+            let buf = serialize(Some(255u8));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = <Option<u8>>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(Some(255), result);
+        }
+    }
+
+    #[test]
+    fn deserialize_option_non_zero() {
+        {
+            let buf = serialize(Option::<NonZeroU32>::None);
+            assert_eq!(buf, [0]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = <Option<NonZeroU32>>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(None, result);
+        }
+
+        {
+            let buf = serialize(NonZeroU32::new(5));
+            assert_eq!(buf, [1, 5]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = <Option<NonZeroU32>>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(NonZeroU32::new(5), result);
+        }
+
+        {
+            let buf = [1u8, 0];
+            let mut reader = buf.as_ref();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert!(<Option<NonZeroU32>>::deserialize(&mut deserializer).is_err());
+        }
+    }
+
+    #[test]
+    fn deserialize_unit() {
+        let buf = serialize(());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = <()>::deserialize(&mut deserializer).unwrap();
+        assert_eq!((), result);
+    }
+
+    #[test]
+    fn deserialize_unit_nested() {
+        {
+            let body = vec![(); 3];
+            let buf = serialize(&body);
+            assert_eq!(buf, serialize(3u64));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(body, Vec::<()>::deserialize(&mut deserializer).unwrap());
+            assert_eq!(deserializer.end(), Ok(()));
+
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                Body::deserialize(&Header::Array(Box::new(Header::Unit)), &mut deserializer),
+                Ok(Body::Array(vec![Body::Unit; 3]))
+            );
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+
+        {
+            #[derive(Debug, PartialEq, Serialize, Deserialize)]
+            struct Test {
+                a: (),
+                b: (),
+                c: u8,
+            }
+
+            let body = Test {
+                a: (),
+                b: (),
+                c: 123,
+            };
+            let buf = serialize(&body);
+            assert_eq!(buf, [123]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(body, Test::deserialize(&mut deserializer).unwrap());
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+
+        {
+            let some = serialize(Some(()));
+            let none = serialize(None::<()>);
+            assert_eq!(some, [1]);
+            assert_eq!(none, [0]);
+            assert_eq!(
+                Some(()),
+                Option::<()>::deserialize(&mut Deserializer::new(&mut some.as_slice())).unwrap()
+            );
+            assert_eq!(
+                None,
+                Option::<()>::deserialize(&mut Deserializer::new(&mut none.as_slice())).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_unit_struct() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test;
+
+        let buf = serialize(Test);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = Test::deserialize(&mut deserializer).unwrap();
+        assert_eq!(Test, result);
+
+        // A unit struct occupies no bytes, so nothing after it is consumed.
+        assert!(buf.is_empty());
+        let buf = [7u8];
+        let mut reader = buf.as_ref();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(Test::deserialize(&mut deserializer), Ok(Test));
+        assert_eq!(u8::deserialize(&mut deserializer), Ok(7));
+        assert_eq!(deserializer.end(), Ok(()));
+    }
+
+    #[test]
+    fn deserialize_untagged_enum() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(untagged)]
+        enum Test {
+            A(u8),
+            B(String),
+        }
+
+        // No variant index is written, so the bytes alone cannot tell the variants apart.
+        assert_eq!(serialize(Test::A(1)), serialize(1u8));
+        assert_eq!(serialize(Test::B("a".to_string())), serialize("a"));
+
+        let buf = serialize(Test::A(1));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let error = Test::deserialize(&mut deserializer).unwrap_err();
+        assert_eq!(error, Error::NotSelfDescribing);
+        assert!(error.to_string().contains("untagged enums need a Header"));
+
+        let header = Header::UInt8;
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            Body::deserialize(&header, &mut deserializer),
+            Ok(Body::UInt8(1))
+        );
+    }
+
+    #[test]
+    fn deserialize_unit_and_newtype_struct_roundtrip() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct U;
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct N(u32);
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test {
+            a: U,
+            b: N,
+            c: Vec<N>,
+            d: Option<N>,
+            e: U,
+        }
+
+        assert_eq!(serialize(N(300)), serialize(300u32));
+        assert_eq!(serialize(vec![U, U]), serialize(vec![(), ()]));
+
+        let body = Test {
+            a: U,
+            b: N(u32::MAX),
+            c: vec![N(0), N(1 << 20)],
+            d: Some(N(5)),
+            e: U,
+        };
+        let buf = serialize(&body);
+        assert_eq!(buf, serialize((u32::MAX, vec![0u32, 1 << 20], Some(5u32))));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(Test::deserialize(&mut deserializer), Ok(body));
+        assert_eq!(deserializer.end(), Ok(()));
+    }
+
+    #[test]
+    fn deserialize_newtype_struct() {
+        {
+            #[derive(Debug, PartialEq, Serialize, Deserialize)]
+            struct Inner {
+                c: String,
+                a: bool,
+                b: u8,
+            }
+            #[derive(Debug, PartialEq, Serialize, Deserialize)]
+            struct Test(Inner);
+            let buf = serialize(Test(Inner {
+                c: "test".to_string(),
+                a: true,
+                b: 123,
+            }));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = Test::deserialize(&mut deserializer).unwrap();
+
+            assert_eq!(
+                Test(Inner {
+                    c: "test".to_string(),
+                    a: true,
+                    b: 123,
+                }),
+                result
+            );
+        }
+
+        {
+            #[derive(Debug, PartialEq, Serialize, Deserialize)]
+            struct Inner(u8);
+            #[derive(Debug, PartialEq, Serialize, Deserialize)]
+            struct Test {
+                c: String,
+                a: Inner,
+                b: bool,
+            }
+            let buf = serialize(Test {
+                c: "test".to_string(),
+                a: Inner(123),
+                b: true,
+            });
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = Test::deserialize(&mut deserializer).unwrap();
+
+            assert_eq!(
+                Test {
+                    c: "test".to_string(),
+                    a: Inner(123),
+                    b: true,
+                },
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_seq() {
+        {
+            let buf = serialize(Vec::<bool>::new());
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = Vec::<bool>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(Vec::<bool>::new(), result);
+        }
+
+        {
+            let buf = serialize(vec![true, false, true]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = Vec::<bool>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(vec![true, false, true], result);
+        }
+
+        {
+            let buf = serialize(vec![0u8, 1, 2, 3, 255]);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = Vec::<u8>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(vec![0, 1, 2, 3, 255], result);
+        }
+
+        {
+            let buf = serialize(vec!['a', 'b', 'c']);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let result = Vec::<char>::deserialize(&mut deserializer).unwrap();
+            assert_eq!(vec!['a', 'b', 'c'], result);
+        }
+    }
+
+    #[test]
+    fn deserialize_decode_limits() {
+        let limits = DecodeLimits {
+            max_depth: 2,
+            max_seq_len: 2,
+            max_map_len: 2,
+            max_bignum_len: 2,
+            max_string_bytes: 2,
+            max_binary_bytes: 2,
+        };
+
+        let buf = serialize(vec![vec![vec![1u8]]]);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            Body::deserialize(
+                &Header::Array(Box::new(Header::Array(Box::new(Header::Array(Box::new(
+                    Header::UInt8
+                )))))),
+                &mut deserializer
+            ),
+            Err(Error::DepthLimit)
+        );
+
+        let buf = serialize(vec![1u8; 3]);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            Vec::<u8>::deserialize(&mut deserializer),
+            Err(Error::SeqLengthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            deserializer.skip_value(&Header::Array(Box::new(Header::UInt8))),
+            Err(Error::SeqLengthLimit)
+        );
+
+        let buf = serialize(BTreeMap::from([("a", 1u8), ("b", 2), ("c", 3)]));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            BTreeMap::<String, u8>::deserialize(&mut deserializer),
+            Err(Error::MapLengthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            Body::deserialize(&Header::Map(Box::new(Header::UInt8)), &mut deserializer),
+            Err(Error::MapLengthLimit)
+        );
+
+        let buf = serialize(Bytes::new(&[1, 2, 3]));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            BigUint::deserialize(&mut deserializer),
+            Err(Error::BigNumLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            Body::deserialize(&Header::BigInt, &mut deserializer),
+            Err(Error::BigNumLimit)
+        );
+
+        let buf = serialize((vec![vec![1u8; 2]], Bytes::new(&[1, 2])));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(DecodeLimits {
+            max_depth: 3,
+            ..limits
+        });
+        assert!(<(Vec<Vec<u8>>, BigUint)>::deserialize(&mut deserializer).is_ok());
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let buf = serialize("abc");
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            String::deserialize(&mut deserializer),
+            Err(Error::StringLengthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            deserializer.skip_value(&Header::String),
+            Err(Error::StringLengthLimit)
+        );
+
+        let buf = serialize(Bytes::new(&[1, 2, 3]));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            Err(Error::BinaryLengthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).decode_limits(limits);
+        assert_eq!(
+            Body::deserialize(&Header::Binary, &mut deserializer),
+            Err(Error::BinaryLengthLimit)
+        );
+
+        let buf = serialize(vec![0u8; 1 << 21]);
+        let mut reader = buf.as_slice();
+        // The default limits apply without asking for them.
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            Vec::<u8>::deserialize(&mut deserializer),
+            Err(Error::SeqLengthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer =
+            Deserializer::new(&mut reader).decode_limits(DecodeLimits::UNLIMITED);
+        assert_eq!(
+            Vec::<u8>::deserialize(&mut deserializer),
+            Ok(vec![0u8; 1 << 21])
+        );
+    }
+
+    #[test]
+    fn deserialize_default_string_binary_limits() {
+        let limit = DecodeLimits::default().max_string_bytes;
+        assert_eq!(limit, DecodeLimits::default().max_binary_bytes);
+        let over = serialize(limit + 1);
+        let at = serialize(limit);
+
+        let mut reader = over.as_slice();
+        assert_eq!(
+            String::deserialize(&mut Deserializer::new(&mut reader)),
+            Err(Error::StringLengthLimit)
+        );
+        let mut reader = over.as_slice();
+        assert_eq!(
+            ByteBuf::deserialize(&mut Deserializer::new(&mut reader)),
+            Err(Error::BinaryLengthLimit)
+        );
+        let mut reader = over.as_slice();
+        assert_eq!(
+            Body::deserialize(&Header::Binary, &mut Deserializer::new(&mut reader)),
+            Err(Error::BinaryLengthLimit)
+        );
+
+        // A length at the cap is accepted and fails only because the input is short.
+        let mut reader = at.as_slice();
+        assert!(matches!(
+            String::deserialize(&mut Deserializer::new(&mut reader)),
+            Err(Error::Read { .. })
+        ));
+        let mut reader = at.as_slice();
+        assert!(matches!(
+            ByteBuf::deserialize(&mut Deserializer::new(&mut reader)),
+            Err(Error::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn deserialize_decode_limits_serde_depth() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Tree {
+            Leaf,
+            Node(Vec<Tree>),
+        }
+
+        let nest = |depth| (0..depth).fold(Tree::Leaf, |tree, _| Tree::Node(vec![tree]));
+        let buf = serialize(nest(20));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).max_depth(4);
+        assert_eq!(Tree::deserialize(&mut deserializer), Err(Error::DepthLimit));
+
+        let buf = serialize(nest(20));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).max_depth(64);
+        assert_eq!(Tree::deserialize(&mut deserializer), Ok(nest(20)));
+        assert_eq!(deserializer.end(), Ok(()));
+    }
+
+    #[test]
+    fn deserialize_forged_bignum_length() {
+        let buf = [0xffu8; 9];
+
+        // `max_depth` alone keeps the default caps on everything else.
+        let mut reader = buf.as_ref();
+        let mut deserializer = Deserializer::new(&mut reader).max_depth(8);
+        assert_eq!(
+            BigUint::deserialize(&mut deserializer),
+            Err(Error::BigNumLimit)
+        );
+
+        // Without a cap the length is believed only as far as the input goes.
+        let mut reader = buf.as_ref();
+        let mut deserializer =
+            Deserializer::new(&mut reader).decode_limits(DecodeLimits::UNLIMITED);
+        assert!(matches!(
+            BigUint::deserialize(&mut deserializer),
+            Err(Error::Read { .. })
+        ));
+        let mut reader = buf.as_ref();
+        let mut deserializer = Deserializer::new(&mut reader)
+            .decode_limits(DecodeLimits::UNLIMITED)
+            .big_decimal_limits(BigDecimalLimits {
+                max_scale: u64::MAX,
+                max_coefficient_len: usize::MAX,
+            });
+        assert!(matches!(
+            Body::deserialize(&Header::BigDecimal, &mut deserializer),
+            Err(Error::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn deserialize_deadline() {
+        let buf = serialize(vec![u32::MAX; 1_000_000]);
+        let header = Header::Array(Box::new(Header::UInt32));
+
+        // A deadline that has already passed stops decoding before the first element.
+        let deadline = Instant::now();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).deadline(deadline);
+        assert_eq!(
+            Vec::<u32>::deserialize(&mut deserializer),
+            Err(Error::Cancelled)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).deadline(deadline);
+        assert_eq!(
+            Body::deserialize(&header, &mut deserializer),
+            Err(Error::Cancelled)
+        );
+
+        // One that passes partway through is noticed within the next few elements.
+        let mut reader = buf.as_slice();
+        let mut deserializer =
+            Deserializer::new(&mut reader).deadline(Instant::now() + Duration::from_millis(1));
+        assert_eq!(
+            Body::deserialize(&header, &mut deserializer),
+            Err(Error::Cancelled)
+        );
+        assert!(!reader.is_empty());
+
+        let mut reader = buf.as_slice();
+        let mut deserializer =
+            Deserializer::new(&mut reader).deadline(Instant::now() + Duration::from_secs(3600));
+        assert_eq!(
+            Vec::<u32>::deserialize(&mut deserializer).unwrap().len(),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn deserialize_big_decimal_limits() {
+        let limits = BigDecimalLimits {
+            max_scale: 100,
+            max_coefficient_len: 4,
+        };
+
+        {
+            let buf = serialize((vec![1u8], 100i64));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).big_decimal_limits(limits);
+            assert_eq!(
+                deserializer
+                    .deserialize_guided::<BigDecimal>(&Header::BigDecimal)
+                    .unwrap(),
+                BigDecimal::deserialize(&mut Deserializer::new(&mut buf.as_slice())).unwrap()
+            );
+            assert!(reader.is_empty());
+        }
+
+        {
+            let buf = serialize(0u8);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).big_decimal_limits(limits);
+            assert!(Body::deserialize(&Header::BigDecimal, &mut deserializer).is_ok());
+        }
+
+        {
+            let buf = serialize((vec![1u8], i64::MIN));
+            let mut reader = buf.as_slice();
+            assert!(
+                Body::deserialize(&Header::BigDecimal, &mut Deserializer::new(&mut reader)).is_ok()
+            );
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).big_decimal_limits(limits);
+            assert_eq!(
+                Body::deserialize(&Header::BigDecimal, &mut deserializer),
+                Err(Error::BigDecimalOutOfBounds)
+            );
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).big_decimal_limits(limits);
+            assert_eq!(
+                deserializer.deserialize_guided::<BigDecimal>(&Header::BigDecimal),
+                Err(Error::BigDecimalOutOfBounds)
+            );
+
+            // Without a header the value is a plain tuple, as in any other format.
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).big_decimal_limits(limits);
+            assert!(BigDecimal::deserialize(&mut deserializer).is_ok());
+        }
+
+        {
+            let buf = serialize((vec![1u8; 5], 0i64));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).big_decimal_limits(limits);
+            assert_eq!(
+                Body::deserialize(&Header::BigDecimal, &mut deserializer),
+                Err(Error::BigDecimalOutOfBounds)
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_bitmap_structs() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test {
+            a: Option<u8>,
+            b: Option<String>,
+            c: Option<u16>,
+            d: Option<bool>,
+            e: Option<Vec<u8>>,
+            f: Option<u32>,
+            g: Option<Option<u8>>,
+            h: Option<i64>,
+            i: Option<(u8, u8)>,
+            j: Option<u64>,
+        }
+
+        let body = Test {
+            a: Some(1),
+            b: None,
+            c: Some(300),
+            d: None,
+            e: Some(vec![1, 2]),
+            f: None,
+            g: Some(None),
+            h: None,
+            i: Some((3, 4)),
+            j: None,
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf).bitmap_structs(true))
+            .unwrap();
+        let bitmap_len = 10usize.div_ceil(8);
+        assert_eq!(bitmap_len, 2);
+        assert_eq!(buf[..bitmap_len], [0b0101_0101, 0b01]);
+        assert_eq!(
+            buf[bitmap_len..],
+            [
+                serialize(1u8),
+                serialize(300u16),
+                serialize(vec![1u8, 2]),
+                serialize(None::<u8>),
+                serialize((3u8, 4u8)),
+            ]
+            .concat()
+        );
+        assert!(buf.len() < serialize(&body).len());
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).bitmap_structs(true);
+        assert_eq!(Test::deserialize(&mut deserializer), Ok(body));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let header = Header::BitmapStruct(vec![
+            Header::Optional(Box::new(Header::UInt8)),
+            Header::UInt8,
+            Header::Optional(Box::new(Header::String)),
+        ]);
+        let body = Body::BitmapStruct(vec![
+            Body::Optional(None),
+            Body::UInt8(2),
+            Body::Optional(Some(Box::new(Body::String("a".to_string())))),
+        ]);
+        let buf = serialize(&body);
+        assert_eq!(buf, [0b110, 2, 1, b'a']);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            Body::deserialize(&header, &mut deserializer).as_ref(),
+            Ok(&body)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.skip_value(&header), Ok(()));
+        assert!(reader.is_empty());
+
+        // Optional fields keep their presence in the bitmap under annotations, tags and reserved
+        // slots.
+        let header = Header::BitmapStruct(vec![
+            Header::Tagged(Box::new(Header::Optional(Box::new(Header::UInt8))), 1),
+            Header::UInt8,
+            Header::Reserved(Box::new(Header::Annotated(
+                Box::new(Header::Optional(Box::new(Header::String))),
+                "name".to_string(),
+            ))),
+        ]);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(Body::deserialize(&header, &mut deserializer), Ok(body));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.skip_value(&header), Ok(()));
+        assert!(reader.is_empty());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<(Option<u8>, u8)>(&header),
+            Ok((None, 2))
+        );
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn deserialize_bitmap_structs_struct_variant() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Test {
+            A,
+            B { y: u8, x: Option<u8> },
+        }
+
+        for body in [
+            Test::A,
+            Test::B { y: 7, x: Some(5) },
+            Test::B { y: 7, x: None },
+        ] {
+            let mut buf = Vec::new();
+            body.serialize(&mut Serializer::new(&mut buf).bitmap_structs(true))
+                .unwrap();
+            assert_eq!(buf, serialize(&body));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).bitmap_structs(true);
+            assert_eq!(Test::deserialize(&mut deserializer), Ok(body));
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn deserialize_columnar_arrays() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Point {
+            x: i16,
+            y: i16,
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct SmallStruct {
+            id: u32,
+            name: String,
+            flag: Option<bool>,
+            points: Vec<Point>,
+        }
+
+        let body = (0..100u32)
+            .map(|i| SmallStruct {
+                id: i,
+                name: format!("row{}", i % 7),
+                flag: if i % 3 == 0 { None } else { Some(i % 2 == 0) },
+                points: (0..i % 4)
+                    .map(|j| Point {
+                        x: j as i16,
+                        y: -(i as i16),
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf).columnar_arrays(true))
+            .unwrap();
+        assert_ne!(buf, serialize(&body));
+
+        let ids = body
+            .iter()
+            .map(|v| serialize(v.id))
+            .collect::<Vec<_>>()
+            .concat();
+        let prefix = [serialize(100usize), serialize(ids.len() as u64), ids].concat();
+        assert_eq!(buf[..prefix.len()], prefix);
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).columnar_arrays(true);
+        assert_eq!(Vec::<SmallStruct>::deserialize(&mut deserializer), Ok(body));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let point = Header::Tuple(vec![Header::Int16, Header::Int16]);
+        let header = Header::Columnar(Box::new(Header::Tuple(vec![
+            Header::UInt32,
+            Header::String,
+            Header::Optional(Box::new(Header::Boolean)),
+            Header::Columnar(Box::new(point)),
+        ])));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let value = Body::deserialize(&header, &mut deserializer).unwrap();
+        assert!(reader.is_empty());
+        assert!(value.validate(&header));
+        match &value {
+            Body::Columnar(rows) => {
+                assert_eq!(rows.len(), 100);
+                assert_eq!(
+                    rows[5],
+                    Body::Tuple(vec![
+                        Body::UInt32(5),
+                        Body::String("row5".to_string()),
+                        Body::Optional(Some(Box::new(Body::Boolean(false)))),
+                        Body::Columnar(vec![Body::Tuple(vec![Body::Int16(0), Body::Int16(-5)])]),
+                    ])
+                );
+            }
+            _ => panic!("expected columnar rows"),
+        }
+        assert_eq!(serialize(&value), buf);
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.skip_value(&header), Ok(()));
+        assert!(reader.is_empty());
+
+        // A column longer than its values is rejected rather than silently dropped.
+        #[derive(Debug, Deserialize)]
+        struct Pair {
+            _a: u8,
+            _b: u8,
+        }
+        let buf = [1, 2, 5, 9, 1, 6];
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).columnar_arrays(true);
+        assert_eq!(
+            Vec::<Pair>::deserialize(&mut deserializer).map(drop),
+            Err(Error::Read { offset: 6 })
+        );
+        let header = Header::Columnar(Box::new(Header::Tuple(vec![Header::UInt8, Header::UInt8])));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer
+                .deserialize_guided::<Vec<Pair>>(&header)
+                .map(drop),
+            Err(Error::Read { offset: 6 })
+        );
+    }
+
+    #[test]
+    fn deserialize_delta_encode_arrays() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Event {
+            id: u8,
+            timestamps: Vec<u64>,
+            offsets: Vec<i32>,
+        }
+
+        let sorted = (0..100u64)
+            .map(|i| 1_614_834_367_000 + i * 250)
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        sorted
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        assert!(buf.len() < serialize(&sorted).len());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).delta_encode_arrays(true);
+        assert_eq!(
+            Vec::<u64>::deserialize(&mut deserializer),
+            Ok(sorted.clone())
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let body = Body::deserialize(
+            &Header::DeltaArray(Box::new(Header::UInt64)),
+            &mut Deserializer::new(&mut buf.as_slice()),
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            Body::DeltaArray(sorted.iter().copied().map(Body::UInt64).collect())
+        );
+        assert_eq!(serialize(&body), buf);
+
+        let unsorted = vec![
+            Event {
+                id: 1,
+                timestamps: vec![u64::MAX, 0, 7, 3, u64::MAX - 1],
+                offsets: vec![i32::MIN, i32::MAX, 0, -1],
+            },
+            Event {
+                id: 2,
+                timestamps: vec![],
+                offsets: vec![5],
+            },
+        ];
+        let mut buf = Vec::new();
+        unsorted
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).delta_encode_arrays(true);
+        assert_eq!(Vec::<Event>::deserialize(&mut deserializer), Ok(unsorted));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let header = Header::Array(Box::new(Header::Tuple(vec![
+            Header::UInt8,
+            Header::DeltaArray(Box::new(Header::UInt64)),
+            Header::DeltaArray(Box::new(Header::Int32)),
+        ])));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let body = Body::deserialize(&header, &mut deserializer).unwrap();
+        assert!(body.validate(&header));
+        assert_eq!(serialize(&body), buf);
+        let mut reader = buf.as_slice();
+        assert_eq!(Deserializer::new(&mut reader).skip_value(&header), Ok(()));
+        assert!(reader.is_empty());
+
+        // A difference that leaves the range of the element type is rejected.
+        let mut buf = Vec::new();
+        vec![0i64, -1]
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).delta_encode_arrays(true);
+        assert!(Vec::<u32>::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn deserialize_rle_arrays() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Frame {
+            id: u8,
+            flags: Vec<u16>,
+            levels: Vec<i64>,
+        }
+
+        let runs = [0u16, 3, 65535]
+            .iter()
+            .flat_map(|v| std::iter::repeat_n(*v, 100))
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        runs.serialize(&mut Serializer::new(&mut buf).rle_arrays(true))
+            .unwrap();
+        assert!(buf.len() < serialize(&runs).len() / 10);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).rle_arrays(true);
+        assert_eq!(Vec::<u16>::deserialize(&mut deserializer), Ok(runs.clone()));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let body = Body::deserialize(
+            &Header::RleArray(Box::new(Header::UInt16)),
+            &mut Deserializer::new(&mut buf.as_slice()),
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            Body::RleArray(runs.iter().copied().map(Body::UInt16).collect())
+        );
+        assert_eq!(serialize(&body), buf);
+
+        // Without repeats every element costs an extra byte for its run length.
+        let frames = vec![
+            Frame {
+                id: 1,
+                flags: vec![1, 2, 3],
+                levels: vec![i64::MIN, -1, i64::MAX],
+            },
+            Frame {
+                id: 2,
+                flags: vec![],
+                levels: vec![5],
+            },
+        ];
+        let mut buf = Vec::new();
+        frames
+            .serialize(&mut Serializer::new(&mut buf).rle_arrays(true))
+            .unwrap();
+        assert_eq!(buf.len(), serialize(&frames).len() + 7);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).rle_arrays(true);
+        assert_eq!(Vec::<Frame>::deserialize(&mut deserializer), Ok(frames));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let header = Header::Array(Box::new(Header::Tuple(vec![
+            Header::UInt8,
+            Header::RleArray(Box::new(Header::UInt16)),
+            Header::RleArray(Box::new(Header::Int64)),
+        ])));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let body = Body::deserialize(&header, &mut deserializer).unwrap();
+        assert!(body.validate(&header));
+        assert_eq!(serialize(&body), buf);
+        let mut reader = buf.as_slice();
+        assert_eq!(Deserializer::new(&mut reader).skip_value(&header), Ok(()));
+        assert!(reader.is_empty());
+
+        // Runs of zero length or running past the end of the array are rejected.
+        for buf in [[2u8, 7, 0, 7, 2], [2, 7, 3, 0, 0]] {
+            let mut reader = buf.as_ref();
+            let mut deserializer = Deserializer::new(&mut reader).rle_arrays(true);
+            assert!(Vec::<u8>::deserialize(&mut deserializer).is_err());
+            let header = Header::RleArray(Box::new(Header::UInt8));
+            assert!(Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_ref())).is_err());
+            assert!(Deserializer::new(&mut buf.as_ref())
+                .skip_value(&header)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn deserialize_forged_rle_run() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        (u64::MAX / 2).serialize(&mut serializer).unwrap();
+        7u32.serialize(&mut serializer).unwrap();
+        (u64::MAX / 2).serialize(&mut serializer).unwrap();
+        assert_eq!(buf.len(), 19);
+
+        let header = Header::RleArray(Box::new(Header::UInt32));
+        assert_eq!(
+            Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())),
+            Err(Error::SeqLengthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).rle_arrays(true);
+        assert_eq!(
+            Vec::<u32>::deserialize(&mut deserializer),
+            Err(Error::SeqLengthLimit)
+        );
+        assert_eq!(
+            Deserializer::new(&mut buf.as_slice()).skip_value(&header),
+            Err(Error::SeqLengthLimit)
+        );
+
+        // With the limits turned off the run still fails instead of aborting on allocation.
+        let mut reader = buf.as_slice();
+        let mut deserializer =
+            Deserializer::new(&mut reader).decode_limits(DecodeLimits::UNLIMITED);
+        assert_eq!(
+            Body::deserialize(&header, &mut deserializer),
+            Err(Error::SeqLengthLimit)
+        );
+    }
+
+    #[test]
+    fn deserialize_segmented_array() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        let mut array = serializer.start_segmented_array(4);
+        for v in ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"] {
+            array.element(v).unwrap();
+        }
+        array.finish().unwrap();
+        assert_eq!(buf.iter().filter(|v| **v == 4).count(), 2);
+
+        let header = Header::SegmentedArray(Box::new(Header::String));
+        let body = Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())).unwrap();
+        assert_eq!(
+            body,
+            Body::SegmentedArray(
+                "abcdefghij"
+                    .chars()
+                    .map(|v| Body::String(v.to_string()))
+                    .collect()
+            )
+        );
+        assert!(body.validate(&header));
+        let mut reader = buf.as_slice();
+        assert_eq!(Deserializer::new(&mut reader).skip_value(&header), Ok(()));
+        assert!(reader.is_empty());
+
+        // A body is written back as a single chunk.
+        let rewritten = serialize(&body);
+        assert_eq!(rewritten[0], 10);
+        assert_eq!(
+            Body::deserialize(&header, &mut Deserializer::new(&mut rewritten.as_slice())),
+            Ok(body)
+        );
+        assert_eq!(serialize(Body::SegmentedArray(vec![])), [0]);
+
+        // The terminating chunk is required.
+        let buf = [1, 1, b'a'];
+        assert!(Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_ref())).is_err());
+        assert!(Deserializer::new(&mut buf.as_ref())
+            .skip_value(&header)
+            .is_err());
+    }
+
+    #[test]
+    fn deserialize_string_interning() {
+        let body = vec![
+            BTreeMap::from([("a".to_string(), "b".to_string())]),
+            BTreeMap::from([("b".to_string(), "a".to_string())]),
+        ];
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf).string_interning(true))
+            .unwrap();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).string_interning(true);
+        assert_eq!(
+            Vec::<BTreeMap<String, String>>::deserialize(&mut deserializer),
+            Ok(body)
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let buf = [3, 6, b'a', b'b', b'c', 1, 1];
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            Body::deserialize(
+                &Header::Array(Box::new(Header::InternedString)),
+                &mut deserializer
+            ),
+            Ok(Body::Array(vec![Body::String("abc".to_string()); 3]))
+        );
 
-    #[test]
-    fn deserialize_u8() {
-        IntoIterator::into_iter([u8::MIN, u8::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-        });
+        let buf = [1, 3];
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).string_interning(true);
+        assert_eq!(
+            Vec::<String>::deserialize(&mut deserializer),
+            Err(Error::Read { offset: 2 })
+        );
     }
 
     #[test]
-    fn deserialize_u16() {
-        IntoIterator::into_iter([u16::MIN, u16::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, u16::deserialize(&mut deserializer).unwrap());
-        });
-    }
+    fn deserialize_self_describing() {
+        let mut buf = Vec::new();
+        Serializer::new(&mut buf)
+            .serialize_self_describing(&(true, Some(123u8), "test".to_string()))
+            .unwrap();
+        assert_eq!(
+            buf,
+            [21, 3, 2, 1, 3, 18, 1, 1, 123, 4, b't', b'e', b's', b't']
+        );
 
-    #[test]
-    fn deserialize_u32() {
-        IntoIterator::into_iter([u32::MIN, u32::MAX]).for_each(|v| {
-            let buf = serialize(v);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let (header, body) = deserializer.deserialize_self_describing().unwrap();
+        assert_eq!(deserializer.end(), Ok(()));
+        assert_eq!(
+            header,
+            Header::Tuple(vec![
+                Header::Boolean,
+                Header::Optional(Box::new(Header::UInt8)),
+                Header::String,
+            ])
+        );
+        assert_eq!(
+            body,
+            Body::Tuple(vec![
+                Body::Boolean(true),
+                Body::Optional(Some(Box::new(Body::UInt8(123)))),
+                Body::String("test".to_string()),
+            ])
+        );
+
+        // A bad header is reported where it went wrong, with its cause.
+        for (buf, offset) in [(vec![255, 0], 1), (vec![21, 3, 2], 3)] {
             let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, u32::deserialize(&mut deserializer).unwrap());
-        });
+            match Deserializer::new(&mut reader).deserialize_self_describing() {
+                Err(Error::Header {
+                    offset: at,
+                    message,
+                }) => {
+                    assert_eq!(at, offset);
+                    assert!(!message.is_empty());
+                }
+                result => panic!("unexpected {:?}", result),
+            }
+        }
     }
 
     #[test]
-    fn deserialize_u64() {
-        IntoIterator::into_iter([u64::MIN, u64::MAX]).for_each(|v| {
-            let buf = serialize(v);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            assert_eq!(v, u64::deserialize(&mut deserializer).unwrap());
-        });
+    fn try_decode() {
+        let buf = [serialize(300u16), serialize("test")].concat();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.try_decode::<u16>(), Ok(Some(300)));
+        assert_eq!(
+            deserializer.try_decode::<String>(),
+            Ok(Some("test".to_string()))
+        );
+        assert_eq!(deserializer.try_decode::<u16>(), Ok(None));
     }
 
-    // #[test]
-    // fn deserialize_u128() {
-    //     IntoIterator::into_iter([u128::MIN, u128::MAX]).for_each(|v| {
-    //         let buf = serialize(v);
-    //         let mut reader = buf.as_slice();
-    //         let mut deserializer = Deserializer::new(&mut reader);
-    //         assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-    //     })
-    // }
-
     #[test]
-    fn deserialize_f32() {
-        IntoIterator::into_iter([-f32::INFINITY, f32::MIN, 0f32, f32::MAX, f32::INFINITY])
-            .for_each(|v| {
-                let buf = serialize(v);
-                let mut reader = buf.as_slice();
-                let mut deserializer = Deserializer::new(&mut reader);
-                assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-            });
+    fn try_decode_truncated() {
+        let buf = serialize(300u16);
+        let mut reader = &buf[..1];
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.try_decode::<u16>(),
+            Err(Error::TruncatedVarint { offset: 1 })
+        );
+
+        let buf = serialize("test");
+        let mut reader = &buf[..1];
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.try_decode::<String>(),
+            Err(Error::Read { offset: 1 })
+        );
     }
 
     #[test]
-    fn deserialize_f64() {
-        IntoIterator::into_iter([-f64::INFINITY, f64::MIN, 0f64, f64::MAX, f64::INFINITY])
-            .for_each(|v| {
-                let buf = serialize(v);
-                let mut reader = buf.as_slice();
-                let mut deserializer = Deserializer::new(&mut reader);
-                assert_eq!(v, Deserialize::deserialize(&mut deserializer).unwrap());
-            });
+    fn deserialize_error_offset() {
+        let mut buf = serialize(("abcdef", true, 1u8));
+        assert_eq!(buf[7], 1);
+        buf[7] = 2;
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = <(String, bool, u8)>::deserialize(&mut deserializer);
+        assert_eq!(result, Err(Error::Read { offset: 7 }));
+        assert_eq!(result.unwrap_err().to_string(), "Read error at offset 7");
+
+        let buf = serialize(("abcdef", true, 1u8));
+        let mut reader = &buf[..8];
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            <(String, bool, u8)>::deserialize(&mut deserializer),
+            Err(Error::Read { offset: 8 })
+        );
     }
 
     #[test]
-    fn deserialize_char() {
-        {
-            let buf = serialize('a');
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = char::deserialize(&mut deserializer).unwrap();
-            assert_eq!('a', result)
+    fn deserialize_compact_enum_tags() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Test {
+            A,
+            B(u8),
+            C { a: bool, b: String },
         }
 
-        {
-            let buf = serialize('あ');
+        let header = Header::Array(Box::new(Header::Enum(vec![
+            Header::Unit,
+            Header::UInt8,
+            Header::Tuple(vec![Header::Boolean, Header::String]),
+        ])));
+        let values = vec![
+            Test::A,
+            Test::B(123),
+            Test::C {
+                a: true,
+                b: "test".to_string(),
+            },
+        ];
+        for compact in [false, true] {
+            let mut buf = Vec::new();
+            Serializer::new(&mut buf)
+                .compact_enum_tags(compact)
+                .serialize_with_header(&values, &header)
+                .unwrap();
+            assert_eq!(buf, serialize(&values));
             let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = char::deserialize(&mut deserializer).unwrap();
-            assert_eq!('あ', result)
+            let mut deserializer = Deserializer::new(&mut reader).compact_enum_tags(compact);
+            assert_eq!(
+                values,
+                deserializer
+                    .deserialize_guided::<Vec<Test>>(&header)
+                    .unwrap()
+            );
         }
     }
 
     #[test]
-    fn deserialize_string() {
-        let buf = serialize("test".to_string());
+    fn deserialize_compact_enum_tags_large_index() {
+        let small = Header::Enum(vec![Header::Unit; 256]);
+        let large = Header::Enum(vec![Header::Unit; 300]);
+        for (header, index, compact_len) in [
+            (&small, 200u32, 1),
+            (&small, 255, 1),
+            (&large, 200, 2),
+            (&large, 299, 2),
+        ] {
+            let body = Body::Enum(index, Box::new(Body::Unit));
+
+            let mut buf = Vec::new();
+            Serializer::new(&mut buf)
+                .serialize_with_header(&body, header)
+                .unwrap();
+            assert_eq!(buf.len(), 2);
+
+            let mut compact_buf = Vec::new();
+            Serializer::new(&mut compact_buf)
+                .compact_enum_tags(true)
+                .serialize_with_header(&body, header)
+                .unwrap();
+            assert_eq!(compact_buf.len(), compact_len);
+
+            let mut reader = compact_buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader).compact_enum_tags(true);
+            assert_eq!(Body::deserialize(header, &mut deserializer), Ok(body));
+            assert!(reader.is_empty());
+        }
+
+        // Without a header nothing proves the variant count, so tags stay varints.
+        let mut buf = Vec::new();
+        Body::Enum(200, Box::new(Body::Unit))
+            .serialize(&mut Serializer::new(&mut buf).compact_enum_tags(true))
+            .unwrap();
+        assert_eq!(buf.len(), 2);
+
+        // A large enum anywhere in the header keeps every tag a varint.
+        let header = Header::Tuple(vec![small.clone(), large]);
+        let body = Body::Tuple(vec![
+            Body::Enum(200, Box::new(Body::Unit)),
+            Body::Enum(0, Box::new(Body::Unit)),
+        ]);
+        let mut buf = Vec::new();
+        Serializer::new(&mut buf)
+            .compact_enum_tags(true)
+            .serialize_with_header(&body, &header)
+            .unwrap();
+        assert_eq!(buf, serialize(&body));
         let mut reader = buf.as_slice();
-        let mut deserializer = Deserializer::new(&mut reader);
-        let result = String::deserialize(&mut deserializer).unwrap();
-        assert_eq!("test".to_string(), result);
+        let mut deserializer = Deserializer::new(&mut reader).compact_enum_tags(true);
+        assert_eq!(Body::deserialize(&header, &mut deserializer), Ok(body));
+
+        let mut buf = Vec::new();
+        assert!(Serializer::new(&mut buf)
+            .compact_enum_tags(true)
+            .serialize_with_header(&Body::Enum(256, Box::new(Body::Unit)), &small)
+            .is_err());
     }
 
     #[test]
-    fn deserialize_string129_issue() {
-        // Thanks @caibear and @udoprog
-        // https://github.com/otake84/dlhn/issues/14
-        // https://github.com/otake84/dlhn/issues/15
-        let original = " ".repeat(129);
-        let mut serialized = vec![];
-        original
-            .serialize(&mut Serializer::new(&mut serialized))
+    fn deserialize_with_config() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Test {
+            A,
+            B(BigDecimal),
+        }
+
+        let mut map = HashMap::new();
+        map.insert("b".to_string(), Test::A);
+        map.insert(
+            "a".to_string(),
+            Test::B(BigDecimal::from(bigdecimal::BigDecimal::from(123))),
+        );
+        map.insert(
+            "c".to_string(),
+            Test::B(BigDecimal::from(bigdecimal::BigDecimal::from(0))),
+        );
+
+        let config = Serializer::new(Vec::new())
+            .map_sort(SortMode::Bytes)
+            .compact_enum_tags(true)
+            .reject_non_finite_floats(true)
+            .config();
+        let mut buf = Vec::new();
+        map.serialize(&mut Serializer::with_config(&mut buf, config))
             .unwrap();
+        assert_eq!(buf, serialize(map.iter().collect::<BTreeMap<_, _>>()));
 
-        let deserialized =
-            String::deserialize(&mut Deserializer::new(&mut serialized.as_slice())).unwrap();
-        assert_eq!(original, deserialized);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::with_config(&mut reader, config)
+            .big_decimal_limits(BigDecimalLimits {
+                max_scale: 10,
+                max_coefficient_len: 10,
+            })
+            .max_depth(8);
+        assert_eq!(
+            map,
+            HashMap::<String, Test>::deserialize(&mut deserializer).unwrap()
+        );
+        assert!(reader.is_empty());
     }
 
+    #[cfg(feature = "smallvec")]
     #[test]
-    fn deserialize_byte_buf() {
-        let buf = serialize(ByteBuf::from(vec![0u8, 1, 2, 3, 255].repeat(1000)));
+    fn deserialize_small_vec() {
+        let buf = serialize(vec![1u8, 2, 3]);
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = ByteBuf::deserialize(&mut deserializer).unwrap();
-        assert_eq!([0u8, 1, 2, 3, 255].repeat(1000), result.as_slice());
+        let result = smallvec::SmallVec::<[u8; 8]>::deserialize(&mut deserializer).unwrap();
+        assert!(!result.spilled());
+        assert_eq!(result.as_slice(), [1, 2, 3]);
     }
 
+    #[cfg(feature = "arrayvec")]
     #[test]
-    fn deserialize_byte_buf_4097() {
-        let buf = serialize(ByteBuf::from(vec![0u8].repeat(4097)));
+    fn deserialize_array_vec() {
+        let buf = serialize(vec![1u32, 2]);
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = ByteBuf::deserialize(&mut deserializer).unwrap();
-        assert_eq!([0u8].repeat(4097), result.as_slice());
+        let result = arrayvec::ArrayVec::<u32, 2>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(result.as_slice(), [1, 2]);
+
+        let buf = serialize(vec![1u32, 2, 3]);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert!(arrayvec::ArrayVec::<u32, 2>::deserialize(&mut deserializer).is_err());
     }
 
+    #[cfg(feature = "indexmap")]
     #[test]
-    fn deserialize_byte_buf_100000() {
-        let buf = serialize(ByteBuf::from(vec![0u8].repeat(100000)));
+    fn deserialize_index_map() {
+        let body = ["e", "b", "d", "a", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), i as u32))
+            .collect::<indexmap::IndexMap<_, _>>();
+        let buf = serialize(&body);
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = ByteBuf::deserialize(&mut deserializer).unwrap();
-        assert_eq!([0u8].repeat(100000), result.as_slice());
+        let result = indexmap::IndexMap::<String, u32>::deserialize(&mut deserializer).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(result.keys().collect::<Vec<_>>(), ["e", "b", "d", "a", "c"]);
+        assert_eq!(result, body);
+
+        let header = Header::Map(Box::new(Header::UInt32));
+        let value = Body::deserialize_ordered(&header, &mut Deserializer::new(&mut buf.as_slice()));
+        assert_eq!(serialize(value.unwrap()), buf);
+
+        let body = [3u8, 1, 2]
+            .iter()
+            .copied()
+            .collect::<indexmap::IndexSet<_>>();
+        let buf = serialize(&body);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = indexmap::IndexSet::<u8>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(result.iter().collect::<Vec<_>>(), [&3, &1, &2]);
     }
 
     #[test]
-    fn deserialize_option() {
-        {
-            let buf = serialize(Option::<u8>::None);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = <Option<u8>>::deserialize(&mut deserializer).unwrap();
-            assert_eq!(None, result);
-        }
-
-        {
-            let buf = serialize(Some(255u8));
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = <Option<u8>>::deserialize(&mut deserializer).unwrap();
-            assert_eq!(Some(255), result);
-        }
+    fn deserialize_is_human_readable() {
+        let buf = [];
+        let mut reader = buf.as_ref();
+        assert!(!serde::Deserializer::is_human_readable(
+            &&mut Deserializer::new(&mut reader)
+        ));
     }
 
     #[test]
-    fn deserialize_unit() {
-        let buf = serialize(());
+    fn deserialize_uuid() {
+        let uuid = uuid::Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let buf = serialize(uuid);
+        assert_eq!(buf, [&[16], uuid.as_bytes().as_ref()].concat());
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = <()>::deserialize(&mut deserializer).unwrap();
-        assert_eq!((), result);
+        assert_eq!(uuid, uuid::Uuid::deserialize(&mut deserializer).unwrap());
     }
 
     #[test]
-    fn deserialize_unit_struct() {
-        #[derive(Debug, PartialEq, Serialize, Deserialize)]
-        struct Test;
-
-        let buf = serialize(Test);
+    fn deserialize_tuple() {
+        let buf = serialize((true, 123u8, 'a'));
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = Test::deserialize(&mut deserializer).unwrap();
-        assert_eq!(Test, result);
+        let result = <(bool, u8, char)>::deserialize(&mut deserializer).unwrap();
+        assert_eq!((true, 123, 'a'), result);
     }
 
     #[test]
-    fn deserialize_newtype_struct() {
-        {
-            #[derive(Debug, PartialEq, Serialize, Deserialize)]
-            struct Inner {
-                c: String,
-                a: bool,
-                b: u8,
-            }
-            #[derive(Debug, PartialEq, Serialize, Deserialize)]
-            struct Test(Inner);
-            let buf = serialize(Test(Inner {
-                c: "test".to_string(),
-                a: true,
-                b: 123,
-            }));
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = Test::deserialize(&mut deserializer).unwrap();
+    fn deserialize_tuple_12() {
+        let body = (
+            true,
+            123u8,
+            -1i8,
+            u16::MAX,
+            i16::MIN,
+            u32::MAX,
+            i32::MIN,
+            u64::MAX,
+            i64::MIN,
+            1.5f32,
+            -2.5f64,
+            "test".to_string(),
+        );
+        let buf = serialize(body.clone());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = <(bool, u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, String)>::deserialize(
+            &mut deserializer,
+        )
+        .unwrap();
+        assert_eq!(body, result);
+        assert!(reader.is_empty());
+    }
 
-            assert_eq!(
-                Test(Inner {
-                    c: "test".to_string(),
-                    a: true,
-                    b: 123,
-                }),
-                result
-            );
-        }
+    #[test]
+    fn deserialize_tuple_16() {
+        let body = (
+            true,
+            123u8,
+            -1i8,
+            u16::MAX,
+            i16::MIN,
+            u32::MAX,
+            i32::MIN,
+            u64::MAX,
+            i64::MIN,
+            1.5f32,
+            -2.5f64,
+            "test".to_string(),
+            'a',
+            Some(1u8),
+            vec![1u32, 2, 3],
+            (),
+        );
+        let buf = serialize(body.clone());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = <(
+            bool,
+            u8,
+            i8,
+            u16,
+            i16,
+            u32,
+            i32,
+            u64,
+            i64,
+            f32,
+            f64,
+            String,
+            char,
+            Option<u8>,
+            Vec<u32>,
+            (),
+        )>::deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(serialize(result.clone()), buf);
+        assert_eq!(result.0, body.0);
+        assert_eq!(result.11, body.11);
+        assert_eq!(result.13, body.13);
+        assert_eq!(result.14, body.14);
+        assert!(reader.is_empty());
+    }
 
-        {
-            #[derive(Debug, PartialEq, Serialize, Deserialize)]
-            struct Inner(u8);
-            #[derive(Debug, PartialEq, Serialize, Deserialize)]
-            struct Test {
-                c: String,
-                a: Inner,
-                b: bool,
-            }
-            let buf = serialize(Test {
-                c: "test".to_string(),
-                a: Inner(123),
-                b: true,
-            });
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = Test::deserialize(&mut deserializer).unwrap();
+    #[test]
+    fn deserialize_tuple_struct() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Test(bool, u8, char);
 
-            assert_eq!(
-                Test {
-                    c: "test".to_string(),
-                    a: Inner(123),
-                    b: true,
-                },
-                result
-            );
-        }
+        let buf = serialize(Test(true, 123, 'a'));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = Test::deserialize(&mut deserializer).unwrap();
+        assert_eq!(Test(true, 123, 'a'), result);
     }
 
     #[test]
-    fn deserialize_seq() {
-        {
-            let buf = serialize(Vec::<bool>::new());
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = Vec::<bool>::deserialize(&mut deserializer).unwrap();
-            assert_eq!(Vec::<bool>::new(), result);
-        }
+    fn deserialize_ip_addr() {
+        use crate::{DeserializeHeader, SerializeHeader};
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-        {
-            let buf = serialize(vec![true, false, true]);
-            let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = Vec::<bool>::deserialize(&mut deserializer).unwrap();
-            assert_eq!(vec![true, false, true], result);
-        }
+        let mut header = Vec::new();
+        IpAddr::serialize_header(&mut header).unwrap();
+        let header = std::io::Cursor::new(header).deserialize_header().unwrap();
 
-        {
-            let buf = serialize(vec![0u8, 1, 2, 3, 255]);
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1));
+        let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        // The variant index is pinned by serde: V4 = 0, V6 = 1.
+        assert_eq!(serialize(v4), [0, 192, 168, 0, 1]);
+        assert_eq!(
+            serialize(v6),
+            [1, 0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+
+        for (addr, index) in [(v4, 0), (v6, 1)] {
+            let buf = serialize(addr);
             let mut reader = buf.as_slice();
             let mut deserializer = Deserializer::new(&mut reader);
-            let result = Vec::<u8>::deserialize(&mut deserializer).unwrap();
-            assert_eq!(vec![0, 1, 2, 3, 255], result);
-        }
+            assert_eq!(IpAddr::deserialize(&mut deserializer), Ok(addr));
+            assert_eq!(deserializer.end(), Ok(()));
 
-        {
-            let buf = serialize(vec!['a', 'b', 'c']);
             let mut reader = buf.as_slice();
-            let mut deserializer = Deserializer::new(&mut reader);
-            let result = Vec::<char>::deserialize(&mut deserializer).unwrap();
-            assert_eq!(vec!['a', 'b', 'c'], result);
+            match Body::deserialize(&header, &mut Deserializer::new(&mut reader)) {
+                Ok(Body::Enum(i, _)) => assert_eq!(i, index),
+                v => panic!("unexpected body: {:?}", v),
+            }
+            assert!(reader.is_empty());
         }
     }
 
     #[test]
-    fn deserialize_tuple() {
-        let buf = serialize((true, 123u8, 'a'));
+    fn deserialize_bool_vec() {
+        let body = (0..10_000).map(|i| i % 3 == 0).collect::<Vec<_>>();
+        let mut buf = serialize(&body);
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = <(bool, u8, char)>::deserialize(&mut deserializer).unwrap();
-        assert_eq!((true, 123, 'a'), result);
+        assert_eq!(deserializer.deserialize_bool_vec(), Ok(body.clone()));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let header = Header::Array(Box::new(Header::Boolean));
+        assert_eq!(
+            Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())),
+            Ok(Body::Array(
+                body.iter().copied().map(Body::Boolean).collect()
+            ))
+        );
+
+        let prefix = buf.len() - body.len();
+        for i in [0, 5_000, body.len() - 1] {
+            let original = buf[prefix + i];
+            buf[prefix + i] = 2;
+            let offset = (prefix + i) as u64;
+            let mut reader = buf.as_slice();
+            assert_eq!(
+                Deserializer::new(&mut reader).deserialize_bool_vec(),
+                Err(Error::Read { offset })
+            );
+            let mut reader = buf.as_slice();
+            assert_eq!(
+                Vec::<bool>::deserialize(&mut Deserializer::new(&mut reader)),
+                Err(Error::Read { offset })
+            );
+            buf[prefix + i] = original;
+        }
+
+        let mut reader = &buf[..buf.len() - 1];
+        assert!(Deserializer::new(&mut reader)
+            .deserialize_bool_vec()
+            .is_err());
     }
 
     #[test]
-    fn deserialize_tuple_struct() {
+    fn deserialize_tuple_struct_and_variant() {
         #[derive(Serialize, Deserialize, PartialEq, Debug)]
-        struct Test(bool, u8, char);
+        struct T(bool, u8, String);
 
-        let buf = serialize(Test(true, 123, 'a'));
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum E {
+            U,
+            V(bool, u8),
+        }
+
+        let buf = serialize(T(true, 123, "ab".to_string()));
+        assert_eq!(buf, [1, 123, 2, b'a', b'b']);
         let mut reader = buf.as_slice();
         let mut deserializer = Deserializer::new(&mut reader);
-        let result = Test::deserialize(&mut deserializer).unwrap();
-        assert_eq!(Test(true, 123, 'a'), result);
+        assert_eq!(
+            T::deserialize(&mut deserializer),
+            Ok(T(true, 123, "ab".to_string()))
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+        let header = Header::Tuple(vec![Header::Boolean, Header::UInt8, Header::String]);
+        assert_eq!(
+            Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())),
+            Ok(Body::Tuple(vec![
+                Body::Boolean(true),
+                Body::UInt8(123),
+                Body::String("ab".to_string()),
+            ]))
+        );
+        let mut reader = &buf[..4];
+        assert!(T::deserialize(&mut Deserializer::new(&mut reader)).is_err());
+
+        let buf = serialize(vec![E::V(false, 7), E::U, E::V(true, 255)]);
+        assert_eq!(buf, [3, 1, 0, 7, 0, 1, 1, 255]);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            Vec::<E>::deserialize(&mut deserializer),
+            Ok(vec![E::V(false, 7), E::U, E::V(true, 255)])
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+        let header = Header::Enum(vec![
+            Header::Unit,
+            Header::Tuple(vec![Header::Boolean, Header::UInt8]),
+        ]);
+        assert_eq!(
+            Body::deserialize(&header, &mut Deserializer::new(&mut &buf[1..4])),
+            Ok(Body::Enum(
+                1,
+                Box::new(Body::Tuple(vec![Body::Boolean(false), Body::UInt8(7)]))
+            ))
+        );
+        let mut reader = [2u8, 0, 0].as_ref();
+        assert!(E::deserialize(&mut Deserializer::new(&mut reader)).is_err());
     }
 
     #[test]
@@ -992,6 +4760,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deserialize_map_require_sorted_keys() {
+        // A map is written like a sequence of key-value pairs, so this is a map with "b" before "a".
+        let buf = serialize(vec![("b".to_string(), true), ("a".to_string(), false)]);
+        let expected = BTreeMap::from([("a".to_string(), false), ("b".to_string(), true)]);
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            BTreeMap::<String, bool>::deserialize(&mut deserializer),
+            Ok(expected)
+        );
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).require_sorted_map_keys(true);
+        assert_eq!(
+            BTreeMap::<String, bool>::deserialize(&mut deserializer),
+            Err(Error::UnsortedMapKey { offset: 4 })
+        );
+
+        let header = Header::Map(Box::new(Header::Boolean));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).require_sorted_map_keys(true);
+        assert_eq!(
+            Body::deserialize(&header, &mut deserializer),
+            Err(Error::UnsortedMapKey { offset: 4 })
+        );
+
+        let buf = serialize(vec![("a".to_string(), true), ("a".to_string(), false)]);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).require_sorted_map_keys(true);
+        assert_eq!(
+            BTreeMap::<String, bool>::deserialize(&mut deserializer),
+            Err(Error::UnsortedMapKey { offset: 4 })
+        );
+
+        let buf = serialize(BTreeMap::from([
+            ("a".to_string(), false),
+            ("b".to_string(), true),
+        ]));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).require_sorted_map_keys(true);
+        assert_eq!(
+            BTreeMap::<String, bool>::deserialize(&mut deserializer),
+            Ok(BTreeMap::from([
+                ("a".to_string(), false),
+                ("b".to_string(), true)
+            ]))
+        );
+    }
+
     #[test]
     fn deserialize_struct() {
         #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -1020,6 +4839,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deserialize_struct_rename_all() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Test {
+            first_field: bool,
+            #[serde(rename = "other")]
+            second_field: u8,
+        }
+
+        let buf = serialize(Test {
+            first_field: true,
+            second_field: 123,
+        });
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result = Test::deserialize(&mut deserializer).unwrap();
+
+        assert_eq!(
+            Test {
+                first_field: true,
+                second_field: 123,
+            },
+            result
+        );
+    }
+
     #[test]
     fn deserialize_enum() {
         #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -1078,6 +4924,452 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deserialize_with_header() {
+        #[derive(Serialize)]
+        struct Test {
+            a: bool,
+            b: u8,
+            c: String,
+        }
+
+        let buf = serialize(Test {
+            a: true,
+            b: 123,
+            c: "test".to_string(),
+        });
+        let header = Header::Tuple(vec![Header::Boolean, Header::UInt8, Header::String]);
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            Deserializer::deserialize_with_header(&mut reader, &header).unwrap(),
+            Body::Tuple(vec![
+                Body::Boolean(true),
+                Body::UInt8(123),
+                Body::String("test".to_string())
+            ])
+        );
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn deserialize_guided_fixed_point() {
+        use crate::FixedPoint;
+
+        #[derive(Serialize)]
+        struct Stored {
+            name: String,
+            value: FixedPoint<2>,
+            history: Vec<Option<FixedPoint<2>>>,
+            limit: Level<FixedPoint<1>>,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Level<T> {
+            Off,
+            On(T),
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Reading {
+            name: String,
+            value: f64,
+            history: Vec<Option<f32>>,
+            limit: Level<f64>,
+        }
+
+        let buf = serialize(Stored {
+            name: "temp".to_string(),
+            value: FixedPoint(-273.15),
+            history: vec![Some(FixedPoint(21.5)), None],
+            limit: Level::On(FixedPoint(99.9)),
+        });
+        let header = Header::Tuple(vec![
+            Header::String,
+            Header::FixedPoint { scale: 2 },
+            Header::Array(Box::new(Header::Optional(Box::new(Header::FixedPoint {
+                scale: 2,
+            })))),
+            Header::Enum(vec![
+                Header::Unit,
+                Header::Annotated(Box::new(Header::FixedPoint { scale: 1 }), "max".to_string()),
+            ]),
+        ]);
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<Reading>(&header),
+            Ok(Reading {
+                name: "temp".to_string(),
+                value: -273.15,
+                history: vec![Some(21.5), None],
+                limit: Level::On(99.9),
+            })
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+
+        // Without the header the scaled integer cannot be told apart from a float.
+        let mut reader = buf.as_slice();
+        assert_ne!(
+            Reading::deserialize(&mut Deserializer::new(&mut reader)).ok(),
+            Some(Reading {
+                name: "temp".to_string(),
+                value: -273.15,
+                history: vec![Some(21.5), None],
+                limit: Level::On(99.9),
+            })
+        );
+    }
+
+    #[test]
+    fn deserialize_guided_reserved() {
+        #[derive(Serialize)]
+        struct V1 {
+            id: u32,
+            nickname: String,
+            score: u16,
+            legacy: Option<u8>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct V2 {
+            id: u32,
+            score: u16,
+        }
+
+        let buf = serialize(V1 {
+            id: 7,
+            nickname: "retired".to_string(),
+            score: 300,
+            legacy: Some(1),
+        });
+        let header = Header::Tuple(vec![
+            Header::UInt32,
+            Header::Reserved(Box::new(Header::String)),
+            Header::UInt16,
+            Header::Reserved(Box::new(Header::Optional(Box::new(Header::UInt8)))),
+        ]);
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<V2>(&header),
+            Ok(V2 { id: 7, score: 300 })
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+
+        // The reserved slot is still consumed positionally by untyped decoding.
+        let mut reader = buf.as_slice();
+        let body = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+        assert!(reader.is_empty());
+        assert!(body.validate(&header));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.skip_value(&header), Ok(()));
+        assert_eq!(deserializer.end(), Ok(()));
+    }
+
+    #[test]
+    fn deserialize_guided_unread_fields() {
+        #[derive(Serialize)]
+        struct V2 {
+            id: u32,
+            tags: Vec<String>,
+            score: Option<u16>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct V1 {
+            id: u32,
+        }
+
+        let v2 = V2 {
+            id: 7,
+            tags: vec!["a".to_string()],
+            score: Some(300),
+        };
+        let fields = vec![
+            Header::UInt32,
+            Header::Array(Box::new(Header::String)),
+            Header::Optional(Box::new(Header::UInt16)),
+        ];
+        for (header, bitmap_structs) in [
+            (Header::Tuple(fields.clone()), false),
+            (Header::BitmapStruct(fields), true),
+        ] {
+            let mut buf = Vec::new();
+            v2.serialize(&mut Serializer::new(&mut buf).bitmap_structs(bitmap_structs))
+                .unwrap();
+            buf.extend(serialize(9u8));
+
+            // The fields an older type does not know are skipped, so the next value still lines up.
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(
+                deserializer.deserialize_guided::<V1>(&header),
+                Ok(V1 { id: 7 })
+            );
+            assert_eq!(u8::deserialize(&mut deserializer), Ok(9));
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn deserialize_guided_bitmap_struct() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test {
+            id: u8,
+            name: Option<String>,
+            score: Option<u16>,
+            nested: Option<Option<u8>>,
+        }
+
+        let header = Header::BitmapStruct(vec![
+            Header::UInt8,
+            Header::Optional(Box::new(Header::String)),
+            Header::Optional(Box::new(Header::UInt16)),
+            Header::Optional(Box::new(Header::Optional(Box::new(Header::UInt8)))),
+        ]);
+        for body in [
+            Test {
+                id: 1,
+                name: Some("a".to_string()),
+                score: None,
+                nested: Some(None),
+            },
+            Test {
+                id: 2,
+                name: None,
+                score: Some(300),
+                nested: None,
+            },
+        ] {
+            let mut buf = Vec::new();
+            body.serialize(&mut Serializer::new(&mut buf).bitmap_structs(true))
+                .unwrap();
+            let bits = buf[0];
+            assert_eq!(bits >> 1 & 1 == 1, body.name.is_some());
+            assert_eq!(bits >> 2 & 1 == 1, body.score.is_some());
+            assert_eq!(bits >> 3 & 1 == 1, body.nested.is_some());
+
+            // The header alone tells the deserializer where presence comes from.
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(deserializer.deserialize_guided::<Test>(&header), Ok(body));
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+
+        let body = Body::BitmapStruct(vec![
+            Body::UInt8(3),
+            Body::Optional(None),
+            Body::Optional(Some(Box::new(Body::UInt16(7)))),
+            Body::Optional(Some(Box::new(Body::Optional(Some(Box::new(Body::UInt8(
+                9,
+            ))))))),
+        ]);
+        let buf = serialize(&body);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<Test>(&header),
+            Ok(Test {
+                id: 3,
+                name: None,
+                score: Some(7),
+                nested: Some(Some(9)),
+            })
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer
+                .deserialize_guided::<(u8, Option<String>, Option<u16>, Option<Option<u8>>)>(
+                    &header
+                ),
+            Ok((3, None, Some(7), Some(Some(9))))
+        );
+    }
+
+    #[test]
+    fn deserialize_guided_open_enum() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Shape {
+            Circle(u16),
+            Rect { w: u8, h: u8 },
+        }
+
+        let header = Header::OpenEnum(vec![
+            Header::UInt16,
+            Header::Tuple(vec![Header::UInt8, Header::UInt8]),
+        ]);
+        let buf = [
+            serialize(Body::OpenEnum(
+                1,
+                Box::new(Body::Tuple(vec![Body::UInt8(2), Body::UInt8(3)])),
+            )),
+            serialize(Body::UnknownVariant(2, vec![1, 2])),
+        ]
+        .concat();
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<Shape>(&header),
+            Ok(Shape::Rect { w: 2, h: 3 })
+        );
+        assert_eq!(
+            deserializer.deserialize_guided::<Shape>(&header),
+            Err(Error::Read { offset: 5 })
+        );
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.skip_value(&header), Ok(()));
+        assert_eq!(deserializer.skip_value(&header), Ok(()));
+        assert_eq!(deserializer.end(), Ok(()));
+    }
+
+    #[test]
+    fn deserialize_guided_flatten() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Record {
+            id: u32,
+            name: String,
+            #[serde(flatten)]
+            extra: BTreeMap<String, u32>,
+        }
+
+        let record = Record {
+            id: 7,
+            name: "dlhn".to_string(),
+            extra: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 300)]),
+        };
+        let buf = serialize(&record);
+        assert_eq!(buf[0], 4);
+        let header = Header::FlattenedMap(
+            vec![
+                ("id".to_string(), Header::UInt32),
+                ("name".to_string(), Header::String),
+            ],
+            Box::new(Header::UInt32),
+        );
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<Record>(&header),
+            Ok(record)
+        );
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let mut reader = buf.as_slice();
+        let body = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+        assert!(reader.is_empty());
+        assert!(body.validate(&header));
+        assert_eq!(
+            body,
+            Body::Map(BTreeMap::from([
+                ("id".to_string(), Body::UInt32(7)),
+                ("name".to_string(), Body::String("dlhn".to_string())),
+                ("a".to_string(), Body::UInt32(1)),
+                ("b".to_string(), Body::UInt32(300)),
+            ]))
+        );
+
+        // Without the header the buffered field values cannot be decoded.
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            Record::deserialize(&mut Deserializer::new(&mut reader)),
+            Err(Error::NotSelfDescribing)
+        );
+    }
+
+    #[test]
+    fn deserialize_guided_arrays() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: u8,
+            name: String,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            deltas: Vec<u32>,
+            runs: Vec<i16>,
+            segments: Vec<String>,
+            points: Vec<Point>,
+            interned: Vec<String>,
+        }
+
+        let header = Header::Tuple(vec![
+            Header::DeltaArray(Box::new(Header::UInt32)),
+            Header::RleArray(Box::new(Header::Int16)),
+            Header::SegmentedArray(Box::new(Header::String)),
+            Header::Columnar(Box::new(Header::Tuple(vec![Header::UInt8, Header::String]))),
+            Header::Array(Box::new(Header::InternedString)),
+        ]);
+        let body = Body::Tuple(vec![
+            Body::DeltaArray(vec![
+                Body::UInt32(300),
+                Body::UInt32(200),
+                Body::UInt32(70000),
+            ]),
+            Body::RleArray(vec![Body::Int16(-1), Body::Int16(-1), Body::Int16(5)]),
+            Body::SegmentedArray(vec![
+                Body::String("a".to_string()),
+                Body::String("b".to_string()),
+            ]),
+            Body::Columnar(vec![
+                Body::Tuple(vec![Body::UInt8(1), Body::String("p".to_string())]),
+                Body::Tuple(vec![Body::UInt8(2), Body::String("q".to_string())]),
+            ]),
+        ]);
+        let buf = [serialize(&body), vec![3, 6, b'a', b'b', b'c', 1, 1]].concat();
+        let test = Test {
+            deltas: vec![300, 200, 70000],
+            runs: vec![-1, -1, 5],
+            segments: vec!["a".to_string(), "b".to_string()],
+            points: vec![
+                Point {
+                    x: 1,
+                    name: "p".to_string(),
+                },
+                Point {
+                    x: 2,
+                    name: "q".to_string(),
+                },
+            ],
+            interned: vec!["abc".to_string(); 3],
+        };
+
+        // The header, not the configuration, says how each array was written.
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(deserializer.deserialize_guided::<Test>(&header), Ok(test));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        // The rows of the columnar array are three levels deep.
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).max_depth(2);
+        assert_eq!(
+            deserializer.deserialize_guided::<Test>(&header),
+            Err(Error::DepthLimit)
+        );
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).max_depth(3);
+        assert!(deserializer.deserialize_guided::<Test>(&header).is_ok());
+
+        // A run may not reach past the end of the array.
+        let buf = [2, 7, 3];
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.deserialize_guided::<Vec<u8>>(&Header::RleArray(Box::new(Header::UInt8))),
+            Err(Error::Read { offset: 3 })
+        );
+    }
+
     fn serialize<T: Serialize>(v: T) -> Vec<u8> {
         let mut buf = Vec::new();
         let mut serializer = Serializer::new(&mut buf);
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+
+// An exact decimal number stored as an unscaled integer, e.g. `Decimal::<2>(123456)` is 1234.56.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal<const SCALE: u8>(pub i128);
+
+// Text formats get the exact digits as a string, as they do for `BigDecimal`.
+#[cfg(any(feature = "serde_json", feature = "toml", feature = "yaml"))]
+pub(crate) fn to_string(value: i128, scale: u8) -> String {
+    struct Digits(i128, u8);
+
+    impl Display for Digits {
+        fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            fmt(self.0, self.1, formatter)
+        }
+    }
+
+    Digits(value, scale).to_string()
+}
+
+pub(crate) fn fmt(value: i128, scale: u8, formatter: &mut fmt::Formatter) -> fmt::Result {
+    let digits = format!(
+        "{:0>width$}",
+        value.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (int, frac) = digits.split_at(digits.len() - scale as usize);
+    if value < 0 {
+        formatter.write_str("-")?;
+    }
+    formatter.write_str(int)?;
+    if !frac.is_empty() {
+        write!(formatter, ".{}", frac)?;
+    }
+    Ok(())
+}
+
+impl<const SCALE: u8> Display for Decimal<SCALE> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        fmt(self.0, SCALE, formatter)
+    }
+}
+
+impl<const SCALE: u8> Serialize for Decimal<SCALE> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, const SCALE: u8> Deserialize<'de> for Decimal<SCALE> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        i128::deserialize(deserializer).map(Decimal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decimal;
+    use crate::{Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn serialize_decimal() {
+        assert_eq!(serialize(Decimal::<2>(123456)), serialize(123456i128));
+        assert_eq!(serialize(Decimal::<2>(-5)), [9]);
+    }
+
+    #[test]
+    fn deserialize_decimal() {
+        for v in [
+            Decimal::<2>(0),
+            Decimal(123456),
+            Decimal(-99),
+            Decimal(i128::MAX),
+            Decimal(i128::MIN),
+        ] {
+            let buf = serialize(v);
+            let mut reader = buf.as_slice();
+            assert_eq!(
+                Decimal::<2>::deserialize(&mut Deserializer::new(&mut reader)),
+                Ok(v)
+            );
+        }
+    }
+
+    #[test]
+    fn display_decimal() {
+        assert_eq!(Decimal::<2>(123456).to_string(), "1234.56");
+        assert_eq!(Decimal::<2>(-1999).to_string(), "-19.99");
+        assert_eq!(Decimal::<2>(-5).to_string(), "-0.05");
+        assert_eq!(Decimal::<2>(0).to_string(), "0.00");
+        assert_eq!(Decimal::<0>(42).to_string(), "42");
+        assert_eq!(
+            Decimal::<40>(i128::MIN).to_string(),
+            "-0.0170141183460469231731687303715884105728"
+        );
+    }
+
+    fn serialize<T: Serialize>(v: T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+}
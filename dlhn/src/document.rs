@@ -0,0 +1,136 @@
+use crate::{de, ser, Body, DeserializeHeader, Deserializer, Header, SerializeHeader, Serializer};
+use serde::Serialize;
+use std::{
+    fmt::{self, Display},
+    io::{Read, Write},
+};
+
+// A document is the magic bytes, the header of the value, then its body.
+pub const MAGIC: [u8; 4] = *b"DLHN";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Read,
+    Write,
+    Magic,
+    Header,
+    Serialize(ser::Error),
+    Deserialize(de::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read => formatter.write_str("read error"),
+            Error::Write => formatter.write_str("write error"),
+            Error::Magic => formatter.write_str("not a dlhn document"),
+            Error::Header => formatter.write_str("invalid document header"),
+            Error::Serialize(e) => Display::fmt(e, formatter),
+            Error::Deserialize(e) => Display::fmt(e, formatter),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct Document;
+
+impl Document {
+    pub fn write<W: Write, T: SerializeHeader + Serialize>(
+        writer: &mut W,
+        value: &T,
+    ) -> Result<(), Error> {
+        writer.write_all(&MAGIC).or(Err(Error::Write))?;
+        Serializer::new(writer)
+            .serialize_self_describing(value)
+            .map_err(Error::Serialize)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<(Header, Body), Error> {
+        let header = Self::read_schema_only(reader)?;
+        let body = Body::deserialize(&header, &mut Deserializer::new(reader))
+            .map_err(Error::Deserialize)?;
+        Ok((header, body))
+    }
+
+    // Reads the magic bytes and the header, leaving the reader at the start of the body.
+    pub fn read_schema_only<R: Read>(reader: &mut R) -> Result<Header, Error> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).or(Err(Error::Read))?;
+        if magic != MAGIC {
+            return Err(Error::Magic);
+        }
+        reader.deserialize_header().or(Err(Error::Header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Document, Error, MAGIC};
+    use crate::{de, Body, Header, SerializeHeader};
+
+    #[test]
+    fn document_roundtrip() {
+        let mut buf = Vec::new();
+        Document::write(&mut buf, &(Some(123u8), vec!["test".to_string()])).unwrap();
+        assert_eq!(buf[..4], MAGIC);
+
+        // Read back without the original type; the header in the document drives the decode.
+        let mut reader = buf.as_slice();
+        let (header, body) = Document::read(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(
+            header,
+            Header::Tuple(vec![
+                Header::Optional(Box::new(Header::UInt8)),
+                Header::Array(Box::new(Header::String)),
+            ])
+        );
+        assert_eq!(
+            body,
+            Body::Tuple(vec![
+                Body::Optional(Some(Box::new(Body::UInt8(123)))),
+                Body::Array(vec![Body::String("test".to_string())]),
+            ])
+        );
+    }
+
+    #[test]
+    fn document_read_schema_only() {
+        type Row = (u32, Option<String>, Vec<bool>);
+
+        let mut buf = Vec::new();
+        Document::write(&mut buf, &(7u32, Some("a".to_string()), vec![true])).unwrap();
+        let mut expected = Vec::new();
+        Row::serialize_header(&mut expected).unwrap();
+
+        let mut reader = buf.as_slice();
+        let header = Document::read_schema_only(&mut reader).unwrap();
+        let mut schema = Vec::new();
+        header.serialize(&mut schema).unwrap();
+        assert_eq!(schema, expected);
+        // The body is left unread.
+        assert_eq!(reader, &buf[4 + expected.len()..]);
+        assert_eq!(
+            Document::read_schema_only(&mut b"DLHN".as_ref()),
+            Err(Error::Header)
+        );
+    }
+
+    #[test]
+    fn document_invalid() {
+        assert_eq!(Document::read(&mut [b'D', b'L'].as_ref()), Err(Error::Read));
+        assert_eq!(
+            Document::read(&mut b"JSON\x02\x01".as_ref()),
+            Err(Error::Magic)
+        );
+        assert_eq!(
+            Document::read(&mut b"DLHN\xff".as_ref()),
+            Err(Error::Header)
+        );
+        assert_eq!(
+            Document::read(&mut b"DLHN\x05".as_ref()),
+            Err(Error::Deserialize(de::Error::Read { offset: 0 }))
+        );
+    }
+}
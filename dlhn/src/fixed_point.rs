@@ -0,0 +1,111 @@
+use serde::{ser, Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct FixedPoint<const SCALE: i8>(pub f64);
+
+pub(crate) fn encode(value: f64, scale: i8) -> Option<i64> {
+    let v = (value * 10f64.powi(scale as i32)).round();
+    // `i64::MAX as f64` rounds up to 2^63, so the upper bound is exclusive.
+    if v >= i64::MIN as f64 && v < i64::MAX as f64 {
+        Some(v as i64)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn decode(value: i64, scale: i8) -> f64 {
+    value as f64 / 10f64.powi(scale as i32)
+}
+
+pub(crate) fn serialize<S: serde::Serializer>(
+    value: f64,
+    scale: i8,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    encode(value, scale)
+        .ok_or_else(|| ser::Error::custom("fixed-point value out of range"))?
+        .serialize(serializer)
+}
+
+impl<const SCALE: i8> Serialize for FixedPoint<SCALE> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serialize(self.0, SCALE, serializer)
+    }
+}
+
+impl<'de, const SCALE: i8> Deserialize<'de> for FixedPoint<SCALE> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        i64::deserialize(deserializer).map(|v| FixedPoint(decode(v, SCALE)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedPoint;
+    use crate::{ser::Error, Deserializer, PrefixVarint, Serializer, ZigZag};
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn serialize_fixed_point() {
+        assert_eq!(
+            serialize(FixedPoint::<2>(12.34)).unwrap(),
+            1234i64.encode_zigzag().encode_prefix_varint_vec()
+        );
+        assert_eq!(
+            serialize(FixedPoint::<-3>(-12345.0)).unwrap(),
+            (-12i64).encode_zigzag().encode_prefix_varint_vec()
+        );
+        assert_eq!(serialize(FixedPoint::<2>(1.5)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn serialize_fixed_point_out_of_range() {
+        for v in [1e17, -1e17, f64::INFINITY, f64::NAN] {
+            assert_eq!(
+                serialize(FixedPoint::<2>(v)),
+                Err(Error::Message("fixed-point value out of range".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_fixed_point() {
+        for v in [0.0, 12.34, -0.01, 21.5, -273.15, 92_233_720_368_547.0] {
+            assert_eq!(deserialize::<2>(v), v);
+        }
+        for v in [0.0, 12_000.0, -5_000_000.0] {
+            assert_eq!(deserialize::<-3>(v), v);
+        }
+    }
+
+    #[test]
+    fn deserialize_fixed_point_precision_loss() {
+        // Digits beyond the scale are rounded to the nearest representable value.
+        assert_eq!(deserialize::<2>(0.123), 0.12);
+        assert_eq!(deserialize::<2>(0.125), 0.13);
+        assert_eq!(deserialize::<0>(-2.5), -3.0);
+        assert_eq!(deserialize::<-3>(12_345.0), 12_000.0);
+        // 1.005 is stored as 1.00499..., which rounds down.
+        assert_eq!(deserialize::<2>(1.005), 1.0);
+    }
+
+    fn serialize<T: Serialize>(v: T) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        v.serialize(&mut Serializer::new(&mut buf))?;
+        Ok(buf)
+    }
+
+    fn deserialize<const SCALE: i8>(v: f64) -> f64 {
+        let buf = serialize(FixedPoint::<SCALE>(v)).unwrap();
+        let mut reader = buf.as_slice();
+        FixedPoint::<SCALE>::deserialize(&mut Deserializer::new(&mut reader))
+            .unwrap()
+            .0
+    }
+}
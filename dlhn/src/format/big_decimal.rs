@@ -23,7 +23,10 @@ impl<'de> Visitor<'de> for BigDecimalVisitor {
     {
         let digits = BigInt::from_signed_bytes_le(
             seq.next_element::<Vec<u8>>()?
-                .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?
+                .ok_or(de::Error::invalid_value(
+                    Unexpected::Seq,
+                    &Error::Read { offset: 0 },
+                ))?
                 .as_slice(),
         );
         if digits.is_zero() {
@@ -31,8 +34,10 @@ impl<'de> Visitor<'de> for BigDecimalVisitor {
         } else {
             Ok(BigDecimal::new(
                 digits,
-                seq.next_element::<i64>()?
-                    .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?,
+                seq.next_element::<i64>()?.ok_or(de::Error::invalid_value(
+                    Unexpected::Seq,
+                    &Error::Read { offset: 0 },
+                ))?,
             ))
         }
     }
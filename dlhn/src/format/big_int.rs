@@ -1,4 +1,4 @@
-use crate::de::Error;
+use crate::{big_int::BIG_INT_NAME, de::Error};
 use num_bigint::BigInt;
 use num_traits::Zero;
 use serde::{
@@ -22,7 +22,10 @@ impl<'de> Visitor<'de> for BigIntVisitor {
     {
         let v = seq
             .next_element::<Vec<u8>>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
+            .ok_or(de::Error::invalid_value(
+                Unexpected::Seq,
+                &Error::Read { offset: 0 },
+            ))?;
         Ok(BigInt::from_signed_bytes_le(v.as_slice()))
     }
 }
@@ -40,7 +43,7 @@ pub fn serialize<T: Serializer>(big_int: &BigInt, serializer: T) -> Result<T::Ok
 }
 
 pub fn deserialize<'de, T: Deserializer<'de>>(deserializer: T) -> Result<BigInt, T::Error> {
-    deserializer.deserialize_tuple(1, BigIntVisitor)
+    deserializer.deserialize_tuple_struct(BIG_INT_NAME, 1, BigIntVisitor)
 }
 
 #[cfg(test)]
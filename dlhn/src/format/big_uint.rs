@@ -1,4 +1,4 @@
-use crate::de::Error;
+use crate::{big_uint::BIG_UINT_NAME, de::Error};
 use num_bigint::BigUint;
 use num_traits::Zero;
 use serde::{
@@ -22,7 +22,10 @@ impl<'de> Visitor<'de> for BigUintVisitor {
     {
         let v = seq
             .next_element::<Vec<u8>>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
+            .ok_or(de::Error::invalid_value(
+                Unexpected::Seq,
+                &Error::Read { offset: 0 },
+            ))?;
         Ok(BigUint::from_bytes_le(v.as_slice()))
     }
 }
@@ -40,7 +43,7 @@ pub fn serialize<T: Serializer>(big_uint: &BigUint, serializer: T) -> Result<T::
 }
 
 pub fn deserialize<'de, T: Deserializer<'de>>(deserializer: T) -> Result<BigUint, T::Error> {
-    deserializer.deserialize_tuple(1, BigUintVisitor)
+    deserializer.deserialize_tuple_struct(BIG_UINT_NAME, 1, BigUintVisitor)
 }
 
 #[cfg(test)]
@@ -0,0 +1,134 @@
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTupleStruct,
+    Deserializer, Serializer,
+};
+use std::convert::TryInto;
+
+pub(crate) const BYTE_ARRAY_NAME: &str = "$dlhn::ByteArray";
+
+struct ByteArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ByteArrayVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "an array of {} bytes", N)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        v.try_into()
+            .or(Err(de::Error::invalid_length(v.len(), &self)))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut buf = [0u8; N];
+        for (i, v) in buf.iter_mut().enumerate() {
+            *v = seq
+                .next_element::<u8>()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        Ok(buf)
+    }
+}
+
+pub fn serialize<T: Serializer, const N: usize>(
+    bytes: &[u8; N],
+    serializer: T,
+) -> Result<T::Ok, T::Error> {
+    let mut tuple = serializer.serialize_tuple_struct(BYTE_ARRAY_NAME, N)?;
+    for v in bytes {
+        tuple.serialize_field(v)?;
+    }
+    tuple.end()
+}
+
+pub fn deserialize<'de, T: Deserializer<'de>, const N: usize>(
+    deserializer: T,
+) -> Result<[u8; N], T::Error> {
+    deserializer.deserialize_tuple_struct(BYTE_ARRAY_NAME, N, ByteArrayVisitor::<N>)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn serialize_byte_array() {
+        let bytes = [1u8, 2, 3, 255];
+        assert_eq!(serialize(&bytes), bytes);
+    }
+
+    #[test]
+    fn deserialize_byte_array() {
+        let bytes: [u8; 1024] = {
+            let mut buf = [0u8; 1024];
+            buf.iter_mut()
+                .enumerate()
+                .for_each(|(i, v)| *v = (i * 31 % 256) as u8);
+            buf
+        };
+        let buf = serialize(&bytes);
+
+        let element_wise = {
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            let mut result = [0u8; 1024];
+            for v in result.iter_mut() {
+                *v = u8::deserialize(&mut deserializer).unwrap();
+            }
+            result
+        };
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let result: [u8; 1024] = super::deserialize(&mut deserializer).unwrap();
+        assert_eq!(result, element_wise);
+        assert_eq!(result, bytes);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn deserialize_byte_array_short_input() {
+        let buf = [1u8, 2, 3];
+        let mut reader = buf.as_ref();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert!(super::deserialize::<_, 4>(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn byte_array_field() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Test {
+            a: u16,
+            #[serde(with = "crate::format::byte_array")]
+            b: [u8; 64],
+            c: bool,
+        }
+
+        let body = Test {
+            a: 300,
+            b: [7u8; 64],
+            c: true,
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(Test::deserialize(&mut deserializer).unwrap(), body);
+    }
+
+    fn serialize<const N: usize>(bytes: &[u8; N]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        super::serialize(bytes, &mut serializer).unwrap();
+        buf
+    }
+}
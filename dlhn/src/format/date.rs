@@ -22,16 +22,18 @@ impl<'de> Visitor<'de> for DateVisitor {
     where
         A: SeqAccess<'de>,
     {
-        let year = seq
-            .next_element::<i32>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?
-            + DATE_YEAR_OFFSET;
-        let ordinal = seq
-            .next_element::<u16>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?
-            + DATE_ORDINAL_OFFSET;
-        let date = Date::from_ordinal_date(year, ordinal)
-            .or(Err(de::Error::invalid_value(Unexpected::Seq, &Error::Read)))?;
+        let year = seq.next_element::<i32>()?.ok_or(de::Error::invalid_value(
+            Unexpected::Seq,
+            &Error::Read { offset: 0 },
+        ))? + DATE_YEAR_OFFSET;
+        let ordinal = seq.next_element::<u16>()?.ok_or(de::Error::invalid_value(
+            Unexpected::Seq,
+            &Error::Read { offset: 0 },
+        ))? + DATE_ORDINAL_OFFSET;
+        let date = Date::from_ordinal_date(year, ordinal).or(Err(de::Error::invalid_value(
+            Unexpected::Seq,
+            &Error::Read { offset: 0 },
+        )))?;
         Ok(date)
     }
 }
@@ -19,15 +19,17 @@ impl<'de> Visitor<'de> for OffsetDateTimeVisitor {
     where
         A: SeqAccess<'de>,
     {
-        let unix_timestamp = seq
-            .next_element::<i64>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
-        let nanosecond = seq
-            .next_element::<u32>()?
-            .ok_or(de::Error::invalid_value(Unexpected::Seq, &Error::Read))?;
-        Ok(OffsetDateTime::from_unix_timestamp(unix_timestamp)
-            .or(Err(de::Error::invalid_value(Unexpected::Seq, &Error::Read)))?
-            + (nanosecond as i64).nanoseconds())
+        let unix_timestamp = seq.next_element::<i64>()?.ok_or(de::Error::invalid_value(
+            Unexpected::Seq,
+            &Error::Read { offset: 0 },
+        ))?;
+        let nanosecond = seq.next_element::<u32>()?.ok_or(de::Error::invalid_value(
+            Unexpected::Seq,
+            &Error::Read { offset: 0 },
+        ))?;
+        Ok(OffsetDateTime::from_unix_timestamp(unix_timestamp).or(Err(
+            de::Error::invalid_value(Unexpected::Seq, &Error::Read { offset: 0 }),
+        ))? + (nanosecond as i64).nanoseconds())
     }
 }
 
@@ -0,0 +1,63 @@
+use serde::{de, Deserialize, Deserializer, Serializer};
+use std::{convert::TryFrom, time::Duration};
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+// Writes the duration as its total nanoseconds in an `i128`, matching `Header::DurationNanos128`.
+// Use with `#[serde(with = "dlhn::format::duration_nanos")]`.
+pub fn serialize<T: Serializer>(duration: &Duration, serializer: T) -> Result<T::Ok, T::Error> {
+    serializer.serialize_i128(duration.as_nanos() as i128)
+}
+
+pub fn deserialize<'de, T: Deserializer<'de>>(deserializer: T) -> Result<Duration, T::Error> {
+    let nanos = i128::deserialize(deserializer)?;
+    let secs = u64::try_from(nanos.div_euclid(NANOS_PER_SEC))
+        .map_err(|_| de::Error::custom("duration out of range"))?;
+    Ok(Duration::new(secs, nanos.rem_euclid(NANOS_PER_SEC) as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Deserializer, Serializer};
+    use std::time::Duration;
+
+    #[test]
+    fn duration_nanos_roundtrip() {
+        for duration in [
+            Duration::ZERO,
+            Duration::new(1, 5),
+            Duration::from_secs(600 * 365 * 24 * 60 * 60),
+            Duration::MAX,
+        ] {
+            let buf = serialize(&duration);
+            assert_eq!(buf, serialize_i128(duration.as_nanos() as i128));
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert_eq!(super::deserialize(&mut deserializer), Ok(duration));
+            assert!(reader.is_empty());
+        }
+        assert!(Duration::from_secs(600 * 365 * 24 * 60 * 60).as_nanos() > u64::MAX as u128);
+    }
+
+    #[test]
+    fn duration_nanos_out_of_range() {
+        for nanos in [-1, i128::MAX] {
+            let buf = serialize_i128(nanos);
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::new(&mut reader);
+            assert!(super::deserialize(&mut deserializer).is_err());
+        }
+    }
+
+    fn serialize(duration: &Duration) -> Vec<u8> {
+        let mut buf = Vec::new();
+        super::serialize(duration, &mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+
+    fn serialize_i128(v: i128) -> Vec<u8> {
+        let mut buf = Vec::new();
+        serde::Serialize::serialize(&v, &mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+}
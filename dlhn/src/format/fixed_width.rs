@@ -0,0 +1,232 @@
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_bytes::Bytes;
+use std::{convert::TryInto, fmt, marker::PhantomData};
+
+// Writes a slice of `u8`, `i8`, `f32` or `f64` exactly as the element-wise sequence would be
+// written, but in one bulk write instead of one call per element. Other formats see a byte string
+// of the little-endian elements. Use with `#[serde(with = "dlhn::format::fixed_width")]` on a
+// `Vec` of one of those types.
+pub trait FixedWidth: Copy + Serialize + for<'de> Deserialize<'de> {
+    const NAME: &'static str;
+    const SIZE: usize;
+
+    fn write_le(self, buf: &mut Vec<u8>);
+
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! fixed_width {
+    ($($ty:ty => $name:literal),*) => {
+        $(
+            impl FixedWidth for $ty {
+                const NAME: &'static str = $name;
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn write_le(self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_le(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+
+        // The element width of a fixed-width sequence, if `name` marks one.
+        pub(crate) fn element_size(name: &str) -> Option<usize> {
+            $(
+                if name == <$ty as FixedWidth>::NAME {
+                    return Some(<$ty as FixedWidth>::SIZE);
+                }
+            )*
+            None
+        }
+    };
+}
+
+fixed_width! {
+    u8 => "$dlhn::FixedWidth::u8",
+    i8 => "$dlhn::FixedWidth::i8",
+    f32 => "$dlhn::FixedWidth::f32",
+    f64 => "$dlhn::FixedWidth::f64"
+}
+
+// Writes the little-endian elements of a fixed-width sequence one by one, for the modes that
+// transform array elements as they are written.
+pub(crate) fn serialize_elements<S: Serializer>(
+    name: &str,
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    fn elements<T: FixedWidth, S: Serializer>(
+        bytes: &[u8],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(bytes.chunks_exact(T::SIZE).map(T::read_le))
+    }
+
+    match name {
+        name if name == u8::NAME => elements::<u8, S>(bytes, serializer),
+        name if name == i8::NAME => elements::<i8, S>(bytes, serializer),
+        name if name == f32::NAME => elements::<f32, S>(bytes, serializer),
+        _ => elements::<f64, S>(bytes, serializer),
+    }
+}
+
+struct FixedWidthVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FixedWidth> Visitor<'de> for FixedWidthVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of {}-byte elements", T::SIZE)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(self)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if !v.len().is_multiple_of(T::SIZE) {
+            return Err(de::Error::invalid_length(v.len(), &self));
+        }
+        Ok(v.chunks_exact(T::SIZE).map(T::read_le).collect())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut buf = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(v) = seq.next_element()? {
+            buf.push(v);
+        }
+        Ok(buf)
+    }
+}
+
+pub fn serialize<T: FixedWidth, S: Serializer>(v: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut buf = Vec::with_capacity(v.len() * T::SIZE);
+    for v in v.iter() {
+        v.write_le(&mut buf);
+    }
+    serializer.serialize_newtype_struct(T::NAME, Bytes::new(&buf))
+}
+
+pub fn deserialize<'de, T: FixedWidth, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    deserializer.deserialize_newtype_struct(T::NAME, FixedWidthVisitor(PhantomData))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Body, Deserializer, Header, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Samples {
+        #[serde(with = "super")]
+        bytes: Vec<u8>,
+        #[serde(with = "super")]
+        signed: Vec<i8>,
+        #[serde(with = "super")]
+        floats: Vec<f32>,
+        #[serde(with = "super")]
+        doubles: Vec<f64>,
+    }
+
+    #[derive(Serialize)]
+    struct Elements {
+        bytes: Vec<u8>,
+        signed: Vec<i8>,
+        floats: Vec<f32>,
+        doubles: Vec<f64>,
+    }
+
+    fn samples() -> (Samples, Elements) {
+        let bytes = (0..1000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        let signed = vec![i8::MIN, -1, 0, 1, i8::MAX];
+        let floats = vec![0.5, -1.25, f32::MAX];
+        let doubles = vec![f64::MIN_POSITIVE, -0.0, 1e300];
+        (
+            Samples {
+                bytes: bytes.clone(),
+                signed: signed.clone(),
+                floats: floats.clone(),
+                doubles: doubles.clone(),
+            },
+            Elements {
+                bytes,
+                signed,
+                floats,
+                doubles,
+            },
+        )
+    }
+
+    #[test]
+    fn fixed_width_matches_elements() {
+        let (samples, elements) = samples();
+        for delta in [false, true] {
+            let mut bulk = Vec::new();
+            samples
+                .serialize(&mut Serializer::new(&mut bulk).delta_encode_arrays(delta))
+                .unwrap();
+            let mut expected = Vec::new();
+            elements
+                .serialize(&mut Serializer::new(&mut expected).delta_encode_arrays(delta))
+                .unwrap();
+            assert_eq!(bulk, expected);
+        }
+    }
+
+    #[test]
+    fn fixed_width_roundtrip() {
+        let (samples, _) = samples();
+        let mut buf = Vec::new();
+        samples.serialize(&mut Serializer::new(&mut buf)).unwrap();
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(Samples::deserialize(&mut deserializer).unwrap(), samples);
+        assert!(reader.is_empty());
+
+        // Delta-encoded sequences are read element by element.
+        let mut buf = Vec::new();
+        samples
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader).delta_encode_arrays(true);
+        assert_eq!(Samples::deserialize(&mut deserializer).unwrap(), samples);
+        assert!(reader.is_empty());
+
+        let mut buf = Vec::new();
+        samples.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let header = Header::Tuple(vec![
+            Header::Array(Box::new(Header::UInt8)),
+            Header::Array(Box::new(Header::Int8)),
+            Header::Array(Box::new(Header::Float32)),
+            Header::Array(Box::new(Header::Float64)),
+        ]);
+        assert_eq!(
+            deserializer.deserialize_guided::<Samples>(&header).unwrap(),
+            samples
+        );
+        let mut reader = buf.as_slice();
+        let body = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+        assert!(body.validate(&header));
+    }
+}
@@ -4,7 +4,11 @@ pub mod big_decimal;
 pub mod big_int;
 #[cfg(all(feature = "num-traits", feature = "num-bigint"))]
 pub mod big_uint;
+pub mod byte_array;
 #[cfg(feature = "time")]
 pub mod date;
 #[cfg(feature = "time")]
 pub mod date_time;
+pub mod duration_nanos;
+pub mod fixed_width;
+pub mod path;
@@ -0,0 +1,101 @@
+use serde::{de, Deserialize, Deserializer, Serializer};
+use serde_bytes::{ByteBuf, Bytes};
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+fn to_bytes(path: &Path) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(path.as_os_str().as_bytes().to_vec())
+}
+
+#[cfg(unix)]
+fn from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+#[cfg(windows)]
+fn to_bytes(path: &Path) -> Option<Vec<u8>> {
+    use std::os::windows::ffi::OsStrExt;
+    Some(
+        path.as_os_str()
+            .encode_wide()
+            .flat_map(|v| v.to_le_bytes())
+            .collect(),
+    )
+}
+
+#[cfg(windows)]
+fn from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let wide = bytes
+        .chunks_exact(2)
+        .map(|v| u16::from_le_bytes([v[0], v[1]]))
+        .collect::<Vec<_>>();
+    Some(PathBuf::from(OsString::from_wide(&wide)))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn to_bytes(path: &Path) -> Option<Vec<u8>> {
+    path.to_str().map(|v| v.as_bytes().to_vec())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+pub fn serialize<T: Serializer>(path: &Path, serializer: T) -> Result<T::Ok, T::Error> {
+    let bytes = to_bytes(path)
+        .ok_or_else(|| serde::ser::Error::custom("path cannot be represented as bytes"))?;
+    serializer.serialize_bytes(Bytes::new(&bytes))
+}
+
+pub fn deserialize<'de, T: Deserializer<'de>>(deserializer: T) -> Result<PathBuf, T::Error> {
+    let bytes = ByteBuf::deserialize(deserializer)?.into_vec();
+    from_bytes(bytes).ok_or_else(|| de::Error::custom("invalid path bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Deserializer, Serializer};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn serialize_path() {
+        assert_eq!(serialize(Path::new("a/b")), [3, b'a', b'/', b'b']);
+    }
+
+    #[test]
+    fn deserialize_path() {
+        let path = PathBuf::from("/tmp/テスト.txt");
+        let buf = serialize(&path);
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(path, super::deserialize(&mut deserializer).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn deserialize_non_utf8_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/tmp/\xff\xfe.bin"));
+        assert!(path.to_str().is_none());
+        let buf = serialize(&path);
+        assert_eq!(buf, [&[11][..], b"/tmp/\xff\xfe.bin"].concat());
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(path, super::deserialize(&mut deserializer).unwrap());
+    }
+
+    fn serialize(path: &Path) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        super::serialize(path, &mut serializer).unwrap();
+        buf
+    }
+}
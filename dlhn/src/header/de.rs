@@ -1,53 +1,104 @@
-use super::Header;
+use super::{Header, HeaderCode};
 use crate::PrefixVarint;
-use std::io::{ErrorKind, Read, Result};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Read, Result},
+};
 
 pub trait DeserializeHeader<R: Read> {
     fn deserialize_header(&mut self) -> Result<Header>;
 }
 
+impl Header {
+    pub fn read_from_counted<R: Read>(reader: R) -> Result<(Header, usize)> {
+        let mut reader = CountingReader { reader, count: 0 };
+        let header = reader.deserialize_header()?;
+        Ok((header, reader.count))
+    }
+}
+
+struct CountingReader<R: Read> {
+    reader: R,
+    count: usize,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.count += size;
+        Ok(size)
+    }
+}
+
+// Headers identify a schema byte for byte, so only the minimal varint encoding is accepted.
+fn decode_canonical<T: PrefixVarint<N> + Copy, const N: usize>(
+    reader: &mut impl Read,
+) -> Result<T> {
+    let mut reader = CountingReader { reader, count: 0 };
+    let value = T::decode_prefix_varint(&mut reader)?;
+    let count = reader.count;
+    if value.encode_prefix_varint(&mut [0u8; N]) != count {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "non-canonical varint in header",
+        ));
+    }
+    Ok(value)
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String> {
+    let len = decode_canonical::<u64, 9>(reader)?;
+    let mut buf = Vec::new();
+    reader.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+    String::from_utf8(buf).or(Err(Error::from(ErrorKind::InvalidData)))
+}
+
 impl<R: Read> DeserializeHeader<R> for R {
     fn deserialize_header(&mut self) -> Result<Header> {
         let mut buf = [0u8; 1];
         self.read_exact(&mut buf)?;
 
-        match buf[0] {
-            super::UNIT_CODE => Ok(Header::Unit),
-            super::OPTIONAL_CODE => {
+        match HeaderCode::try_from(buf[0])? {
+            HeaderCode::Unit => Ok(Header::Unit),
+            HeaderCode::Null => Ok(Header::Null),
+            HeaderCode::Optional => {
                 let inner = self.deserialize_header()?;
                 Ok(Header::Optional(Box::new(inner)))
             }
-            super::BOOLEAN_CODE => Ok(Header::Boolean),
-            super::UINT8_CODE => Ok(Header::UInt8),
-            super::UINT16_CODE => Ok(Header::UInt16),
-            super::UINT32_CODE => Ok(Header::UInt32),
-            super::UINT64_CODE => Ok(Header::UInt64),
-            // super::UINT128_CODE => Ok(Header::UInt128),
-            super::INT8_CODE => Ok(Header::Int8),
-            super::INT16_CODE => Ok(Header::Int16),
-            super::INT32_CODE => Ok(Header::Int32),
-            super::INT64_CODE => Ok(Header::Int64),
-            // super::INT128_CODE => Ok(Header::Int128),
-            super::FLOAT32_CODE => Ok(Header::Float32),
-            super::FLOAT64_CODE => Ok(Header::Float64),
-            super::BIG_UINT_CODE => Ok(Header::BigUInt),
-            super::BIG_INT_CODE => Ok(Header::BigInt),
-            super::BIG_DECIMAL_CODE => Ok(Header::BigDecimal),
-            super::STRING_CODE => Ok(Header::String),
-            super::BINARY_CODE => Ok(Header::Binary),
-            super::ARRAY_CODE => {
+            HeaderCode::Boolean => Ok(Header::Boolean),
+            HeaderCode::UInt8 => Ok(Header::UInt8),
+            HeaderCode::UInt16 => Ok(Header::UInt16),
+            HeaderCode::UInt32 => Ok(Header::UInt32),
+            HeaderCode::UInt64 => Ok(Header::UInt64),
+            // HeaderCode::UInt128 => Ok(Header::UInt128),
+            HeaderCode::Int8 => Ok(Header::Int8),
+            HeaderCode::Int16 => Ok(Header::Int16),
+            HeaderCode::Int32 => Ok(Header::Int32),
+            HeaderCode::Int64 => Ok(Header::Int64),
+            // HeaderCode::Int128 => Ok(Header::Int128),
+            HeaderCode::Float32 => Ok(Header::Float32),
+            HeaderCode::Float64 => Ok(Header::Float64),
+            HeaderCode::BigUInt => Ok(Header::BigUInt),
+            HeaderCode::BigInt => Ok(Header::BigInt),
+            HeaderCode::BigDecimal => Ok(Header::BigDecimal),
+            HeaderCode::String => Ok(Header::String),
+            HeaderCode::Binary => Ok(Header::Binary),
+            HeaderCode::Array => {
                 let inner = self.deserialize_header()?;
                 Ok(Header::Array(Box::new(inner)))
             }
-            super::TUPLE_CODE => {
-                let size = u16::decode_prefix_varint(self)?;
+            HeaderCode::Tuple => {
+                let size = decode_canonical::<u16, 3>(self)?;
                 let mut vec = Vec::with_capacity(size as usize);
                 for _ in 0..size {
                     vec.push(self.deserialize_header()?);
                 }
                 Ok(Header::Tuple(vec))
             }
-            // super::STRUCT_CODE => {
+            // HeaderCode::Struct => {
             //     let size = u16::decode_prefix_varint(self)?;
             //     let mut buf = Vec::with_capacity(size as usize);
             //     for _ in 0..size {
@@ -55,24 +106,93 @@ impl<R: Read> DeserializeHeader<R> for R {
             //     }
             //     Ok(Header::Struct(buf))
             // }
-            super::MAP_CODE => {
+            HeaderCode::Map => {
                 let inner = self.deserialize_header()?;
                 Ok(Header::Map(Box::new(inner)))
             }
-            super::ENUM_CODE => {
-                let size = u16::decode_prefix_varint(self)?;
+            HeaderCode::Enum => {
+                let size = decode_canonical::<u16, 3>(self)?;
                 let mut buf = Vec::with_capacity(size as usize);
                 for _ in 0..size {
                     buf.push(self.deserialize_header()?);
                 }
                 Ok(Header::Enum(buf))
             }
-            super::DATE_CODE => Ok(Header::Date),
-            super::DATETIME_CODE => Ok(Header::DateTime),
-            code => Err(std::io::Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid header code: {}", code),
-            )),
+            HeaderCode::OpenEnum => {
+                let size = decode_canonical::<u16, 3>(self)?;
+                let mut buf = Vec::with_capacity(size as usize);
+                for _ in 0..size {
+                    buf.push(self.deserialize_header()?);
+                }
+                Ok(Header::OpenEnum(buf))
+            }
+            HeaderCode::Date => Ok(Header::Date),
+            HeaderCode::DateTime => Ok(Header::DateTime),
+            HeaderCode::Annotated => {
+                let inner = self.deserialize_header()?;
+                let annotation = read_string(self)?;
+                Ok(Header::Annotated(Box::new(inner), annotation))
+            }
+            HeaderCode::Tagged => {
+                let inner = self.deserialize_header()?;
+                let tag = decode_canonical::<u16, 3>(self)?;
+                Ok(Header::Tagged(Box::new(inner), tag))
+            }
+            HeaderCode::InternedString => Ok(Header::InternedString),
+            HeaderCode::Duration => Ok(Header::Duration),
+            HeaderCode::SystemTime => Ok(Header::SystemTime),
+            HeaderCode::TypedBinary => Ok(Header::TypedBinary),
+            HeaderCode::DurationNanos128 => Ok(Header::DurationNanos128),
+            HeaderCode::BitmapStruct => {
+                let size = decode_canonical::<u16, 3>(self)?;
+                let mut buf = Vec::with_capacity(size as usize);
+                for _ in 0..size {
+                    buf.push(self.deserialize_header()?);
+                }
+                Ok(Header::BitmapStruct(buf))
+            }
+            HeaderCode::Columnar => {
+                let inner = self.deserialize_header()?;
+                Ok(Header::Columnar(Box::new(inner)))
+            }
+            HeaderCode::DeltaArray => {
+                let inner = self.deserialize_header()?;
+                Ok(Header::DeltaArray(Box::new(inner)))
+            }
+            HeaderCode::Reserved => {
+                let inner = self.deserialize_header()?;
+                Ok(Header::Reserved(Box::new(inner)))
+            }
+            HeaderCode::RleArray => {
+                let inner = self.deserialize_header()?;
+                Ok(Header::RleArray(Box::new(inner)))
+            }
+            HeaderCode::SegmentedArray => {
+                let inner = self.deserialize_header()?;
+                Ok(Header::SegmentedArray(Box::new(inner)))
+            }
+            HeaderCode::FlattenedMap => {
+                let size = decode_canonical::<u16, 3>(self)?;
+                let mut fields = Vec::with_capacity(size as usize);
+                for _ in 0..size {
+                    let name = read_string(self)?;
+                    fields.push((name, self.deserialize_header()?));
+                }
+                let extra = self.deserialize_header()?;
+                Ok(Header::FlattenedMap(fields, Box::new(extra)))
+            }
+            HeaderCode::FixedPoint => {
+                let mut buf = [0u8; 1];
+                self.read_exact(&mut buf)?;
+                Ok(Header::FixedPoint {
+                    scale: buf[0] as i8,
+                })
+            }
+            HeaderCode::Decimal => {
+                let mut buf = [0u8; 1];
+                self.read_exact(&mut buf)?;
+                Ok(Header::Decimal { scale: buf[0] })
+            }
         }
     }
 }
@@ -390,4 +510,244 @@ mod tests {
             Header::DateTime
         );
     }
+
+    #[test]
+    fn deserialize_header_annotated() {
+        let header = Header::Annotated(
+            Box::new(Header::Tuple(vec![
+                Header::Annotated(Box::new(Header::Boolean), "enabled".to_string()),
+                Header::UInt8,
+            ])),
+            "設定".to_string(),
+        );
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_interned_string() {
+        let header = Header::Map(Box::new(Header::InternedString));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_fixed_point() {
+        let header = Header::Tuple(vec![
+            Header::FixedPoint { scale: 3 },
+            Header::FixedPoint { scale: -2 },
+        ]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_segmented_array() {
+        let header = Header::SegmentedArray(Box::new(Header::Optional(Box::new(Header::String))));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [44, 1, 18]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_null() {
+        let header = Header::Tuple(vec![Header::Null, Header::Unit]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [21, 2, 43, 0]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_decimal() {
+        let header = Header::Tuple(vec![
+            Header::Decimal { scale: 2 },
+            Header::Decimal { scale: 18 },
+        ]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [21, 2, 42, 2, 42, 18]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_bitmap_struct() {
+        let header = Header::BitmapStruct(vec![
+            Header::UInt8,
+            Header::Optional(Box::new(Header::String)),
+        ]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_duration() {
+        let header = Header::Tuple(vec![Header::String, Header::Duration, Header::SystemTime]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [21, 3, 18, 33, 34]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_columnar() {
+        let header = Header::Columnar(Box::new(Header::Tuple(vec![Header::UInt8, Header::String])));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [32, 21, 2, 3, 18]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_delta_array() {
+        let header = Header::DeltaArray(Box::new(Header::Int32));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [35, 10]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_rle_array() {
+        let header = Header::RleArray(Box::new(Header::Array(Box::new(Header::UInt16))));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [41, 20, 4]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_open_enum() {
+        let header = Header::OpenEnum(vec![Header::Unit, Header::Tuple(vec![Header::UInt8])]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [40, 2, 0, 21, 1, 3]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_reserved() {
+        let header = Header::Tuple(vec![
+            Header::UInt8,
+            Header::Reserved(Box::new(Header::String)),
+            Header::UInt32,
+        ]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [21, 3, 3, 38, 18, 5]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_flattened_map() {
+        let header = Header::FlattenedMap(
+            vec![
+                ("id".to_string(), Header::UInt32),
+                ("name".to_string(), Header::String),
+            ],
+            Box::new(Header::Optional(Box::new(Header::UInt8))),
+        );
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_typed_binary() {
+        let header = Header::Array(Box::new(Header::TypedBinary));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [20, 36]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_duration_nanos_128() {
+        let header = Header::Optional(Box::new(Header::DurationNanos128));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, [1, 37]);
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_non_canonical() {
+        assert_eq!(
+            Cursor::new([21, 1, 2]).deserialize_header().unwrap(),
+            Header::Tuple(vec![Header::Boolean])
+        );
+        for buf in [
+            &[21, 0x81, 0x00, 2][..],
+            &[24, 0xc0, 0x01, 0x00, 2],
+            &[27, 2, 0x81, 0x00, b'a'],
+        ] {
+            assert_eq!(
+                Cursor::new(buf).deserialize_header().unwrap_err().kind(),
+                std::io::ErrorKind::InvalidData
+            );
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn deserialize_header_canonical_roundtrip() {
+        use crate::arbitrary::gen_header;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..1000 {
+            let header = gen_header(&mut rng, 4);
+            let mut buf = Vec::new();
+            header.serialize(&mut buf).unwrap();
+            let decoded = Cursor::new(&buf).deserialize_header().unwrap();
+            assert_eq!(decoded, header);
+
+            let mut other = Vec::new();
+            decoded.clone().serialize(&mut other).unwrap();
+            assert_eq!(other, buf);
+        }
+    }
+
+    #[test]
+    fn deserialize_header_tagged() {
+        let header = Header::Tuple(vec![
+            Header::Tagged(Box::new(Header::Float64), 1),
+            Header::Tagged(Box::new(Header::UInt32), u16::MAX),
+        ]);
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(Cursor::new(buf).deserialize_header().unwrap(), header);
+    }
+
+    #[test]
+    fn deserialize_header_annotated_truncated() {
+        let mut buf = Vec::new();
+        Header::Annotated(Box::new(Header::Boolean), "enabled".to_string())
+            .serialize(&mut buf)
+            .unwrap();
+        buf.pop();
+        assert!(Cursor::new(buf).deserialize_header().is_err());
+    }
+
+    #[test]
+    fn read_from_counted() {
+        let header = Header::Map(Box::new(Header::Tuple(vec![
+            Header::Annotated(Box::new(Header::String), "name".to_string()),
+            Header::Optional(Box::new(Header::UInt32)),
+        ])));
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        let header_len = buf.len();
+        buf.extend_from_slice(&[1, 2, 3]);
+
+        let (result, len) = Header::read_from_counted(buf.as_slice()).unwrap();
+        assert_eq!(result, header);
+        assert_eq!(len, header_len);
+        assert_eq!(&buf[len..], [1, 2, 3]);
+    }
 }
@@ -1,4 +1,11 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    io::{Error, ErrorKind},
+};
+
 pub mod de;
+pub mod registry;
 pub mod ser;
 
 const UNIT_CODE: u8 = 0;
@@ -28,6 +35,184 @@ const MAP_CODE: u8 = 23;
 const ENUM_CODE: u8 = 24;
 const DATE_CODE: u8 = 25;
 const DATETIME_CODE: u8 = 26;
+const ANNOTATED_CODE: u8 = 27;
+const TAGGED_CODE: u8 = 28;
+const INTERNED_STRING_CODE: u8 = 29;
+const FIXED_POINT_CODE: u8 = 30;
+const BITMAP_STRUCT_CODE: u8 = 31;
+const COLUMNAR_CODE: u8 = 32;
+const DURATION_CODE: u8 = 33;
+const SYSTEM_TIME_CODE: u8 = 34;
+const DELTA_ARRAY_CODE: u8 = 35;
+const TYPED_BINARY_CODE: u8 = 36;
+const DURATION_NANOS_128_CODE: u8 = 37;
+const RESERVED_CODE: u8 = 38;
+const FLATTENED_MAP_CODE: u8 = 39;
+const OPEN_ENUM_CODE: u8 = 40;
+const RLE_ARRAY_CODE: u8 = 41;
+const DECIMAL_CODE: u8 = 42;
+const NULL_CODE: u8 = 43;
+const SEGMENTED_ARRAY_CODE: u8 = 44;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HeaderCode {
+    Unit = UNIT_CODE,
+    Optional = OPTIONAL_CODE,
+    Boolean = BOOLEAN_CODE,
+    UInt8 = UINT8_CODE,
+    UInt16 = UINT16_CODE,
+    UInt32 = UINT32_CODE,
+    UInt64 = UINT64_CODE,
+    // UInt128 = UINT128_CODE,
+    Int8 = INT8_CODE,
+    Int16 = INT16_CODE,
+    Int32 = INT32_CODE,
+    Int64 = INT64_CODE,
+    // Int128 = INT128_CODE,
+    Float32 = FLOAT32_CODE,
+    Float64 = FLOAT64_CODE,
+    BigUInt = BIG_UINT_CODE,
+    BigInt = BIG_INT_CODE,
+    BigDecimal = BIG_DECIMAL_CODE,
+    String = STRING_CODE,
+    Binary = BINARY_CODE,
+    Array = ARRAY_CODE,
+    Tuple = TUPLE_CODE,
+    // Struct = STRUCT_CODE,
+    Map = MAP_CODE,
+    Enum = ENUM_CODE,
+    Date = DATE_CODE,
+    DateTime = DATETIME_CODE,
+    Annotated = ANNOTATED_CODE,
+    Tagged = TAGGED_CODE,
+    InternedString = INTERNED_STRING_CODE,
+    FixedPoint = FIXED_POINT_CODE,
+    BitmapStruct = BITMAP_STRUCT_CODE,
+    Columnar = COLUMNAR_CODE,
+    Duration = DURATION_CODE,
+    SystemTime = SYSTEM_TIME_CODE,
+    DeltaArray = DELTA_ARRAY_CODE,
+    TypedBinary = TYPED_BINARY_CODE,
+    DurationNanos128 = DURATION_NANOS_128_CODE,
+    Reserved = RESERVED_CODE,
+    FlattenedMap = FLATTENED_MAP_CODE,
+    OpenEnum = OPEN_ENUM_CODE,
+    RleArray = RLE_ARRAY_CODE,
+    Decimal = DECIMAL_CODE,
+    Null = NULL_CODE,
+    SegmentedArray = SEGMENTED_ARRAY_CODE,
+}
+
+impl TryFrom<u8> for HeaderCode {
+    type Error = Error;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            UNIT_CODE => Ok(HeaderCode::Unit),
+            OPTIONAL_CODE => Ok(HeaderCode::Optional),
+            BOOLEAN_CODE => Ok(HeaderCode::Boolean),
+            UINT8_CODE => Ok(HeaderCode::UInt8),
+            UINT16_CODE => Ok(HeaderCode::UInt16),
+            UINT32_CODE => Ok(HeaderCode::UInt32),
+            UINT64_CODE => Ok(HeaderCode::UInt64),
+            // UINT128_CODE => Ok(HeaderCode::UInt128),
+            INT8_CODE => Ok(HeaderCode::Int8),
+            INT16_CODE => Ok(HeaderCode::Int16),
+            INT32_CODE => Ok(HeaderCode::Int32),
+            INT64_CODE => Ok(HeaderCode::Int64),
+            // INT128_CODE => Ok(HeaderCode::Int128),
+            FLOAT32_CODE => Ok(HeaderCode::Float32),
+            FLOAT64_CODE => Ok(HeaderCode::Float64),
+            BIG_UINT_CODE => Ok(HeaderCode::BigUInt),
+            BIG_INT_CODE => Ok(HeaderCode::BigInt),
+            BIG_DECIMAL_CODE => Ok(HeaderCode::BigDecimal),
+            STRING_CODE => Ok(HeaderCode::String),
+            BINARY_CODE => Ok(HeaderCode::Binary),
+            ARRAY_CODE => Ok(HeaderCode::Array),
+            TUPLE_CODE => Ok(HeaderCode::Tuple),
+            // STRUCT_CODE => Ok(HeaderCode::Struct),
+            MAP_CODE => Ok(HeaderCode::Map),
+            ENUM_CODE => Ok(HeaderCode::Enum),
+            DATE_CODE => Ok(HeaderCode::Date),
+            DATETIME_CODE => Ok(HeaderCode::DateTime),
+            ANNOTATED_CODE => Ok(HeaderCode::Annotated),
+            TAGGED_CODE => Ok(HeaderCode::Tagged),
+            INTERNED_STRING_CODE => Ok(HeaderCode::InternedString),
+            FIXED_POINT_CODE => Ok(HeaderCode::FixedPoint),
+            BITMAP_STRUCT_CODE => Ok(HeaderCode::BitmapStruct),
+            COLUMNAR_CODE => Ok(HeaderCode::Columnar),
+            DURATION_CODE => Ok(HeaderCode::Duration),
+            SYSTEM_TIME_CODE => Ok(HeaderCode::SystemTime),
+            DELTA_ARRAY_CODE => Ok(HeaderCode::DeltaArray),
+            TYPED_BINARY_CODE => Ok(HeaderCode::TypedBinary),
+            DURATION_NANOS_128_CODE => Ok(HeaderCode::DurationNanos128),
+            RESERVED_CODE => Ok(HeaderCode::Reserved),
+            FLATTENED_MAP_CODE => Ok(HeaderCode::FlattenedMap),
+            OPEN_ENUM_CODE => Ok(HeaderCode::OpenEnum),
+            RLE_ARRAY_CODE => Ok(HeaderCode::RleArray),
+            DECIMAL_CODE => Ok(HeaderCode::Decimal),
+            NULL_CODE => Ok(HeaderCode::Null),
+            SEGMENTED_ARRAY_CODE => Ok(HeaderCode::SegmentedArray),
+            code => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid header code: {}", code),
+            )),
+        }
+    }
+}
+
+impl Display for HeaderCode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            HeaderCode::Unit => "Unit",
+            HeaderCode::Optional => "Optional",
+            HeaderCode::Boolean => "Boolean",
+            HeaderCode::UInt8 => "UInt8",
+            HeaderCode::UInt16 => "UInt16",
+            HeaderCode::UInt32 => "UInt32",
+            HeaderCode::UInt64 => "UInt64",
+            // HeaderCode::UInt128 => "UInt128",
+            HeaderCode::Int8 => "Int8",
+            HeaderCode::Int16 => "Int16",
+            HeaderCode::Int32 => "Int32",
+            HeaderCode::Int64 => "Int64",
+            // HeaderCode::Int128 => "Int128",
+            HeaderCode::Float32 => "Float32",
+            HeaderCode::Float64 => "Float64",
+            HeaderCode::BigUInt => "BigUInt",
+            HeaderCode::BigInt => "BigInt",
+            HeaderCode::BigDecimal => "BigDecimal",
+            HeaderCode::String => "String",
+            HeaderCode::Binary => "Binary",
+            HeaderCode::Array => "Array",
+            HeaderCode::Tuple => "Tuple",
+            // HeaderCode::Struct => "Struct",
+            HeaderCode::Map => "Map",
+            HeaderCode::Enum => "Enum",
+            HeaderCode::Date => "Date",
+            HeaderCode::DateTime => "DateTime",
+            HeaderCode::Annotated => "Annotated",
+            HeaderCode::Tagged => "Tagged",
+            HeaderCode::InternedString => "InternedString",
+            HeaderCode::FixedPoint => "FixedPoint",
+            HeaderCode::BitmapStruct => "BitmapStruct",
+            HeaderCode::Columnar => "Columnar",
+            HeaderCode::Duration => "Duration",
+            HeaderCode::SystemTime => "SystemTime",
+            HeaderCode::DeltaArray => "DeltaArray",
+            HeaderCode::TypedBinary => "TypedBinary",
+            HeaderCode::DurationNanos128 => "DurationNanos128",
+            HeaderCode::Reserved => "Reserved",
+            HeaderCode::FlattenedMap => "FlattenedMap",
+            HeaderCode::OpenEnum => "OpenEnum",
+            HeaderCode::RleArray => "RleArray",
+            HeaderCode::Decimal => "Decimal",
+            HeaderCode::Null => "Null",
+            HeaderCode::SegmentedArray => "SegmentedArray",
+        })
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Header {
@@ -58,4 +243,396 @@ pub enum Header {
     Enum(Vec<Header>),
     Date,
     DateTime,
+    Annotated(Box<Header>, String),
+    Tagged(Box<Header>, u16),
+    InternedString,
+    FixedPoint { scale: i8 },
+    BitmapStruct(Vec<Header>),
+    Columnar(Box<Header>),
+    Duration,
+    SystemTime,
+    DeltaArray(Box<Header>),
+    // Binary data where every value carries its own content-type tag.
+    TypedBinary,
+    // A signed count of nanoseconds, for durations beyond the range of `u64` nanoseconds.
+    DurationNanos128,
+    // A retired field slot. It is still written and read positionally, but typed decoding skips
+    // it so the slot is never reused for a different meaning.
+    Reserved(Box<Header>),
+    // A struct with `#[serde(flatten)]` fields, written as a map keyed by field name. Values under
+    // a known field name use that field's header; any other key is an extra entry of the second.
+    FlattenedMap(Vec<(String, Header)>, Box<Header>),
+    // An enum that may gain variants this header does not list. Every payload is prefixed with
+    // its byte length, so an unknown variant can be kept as raw bytes instead of failing.
+    OpenEnum(Vec<Header>),
+    // An array whose integer elements are written as (value, run length) pairs, for long runs of
+    // repeated values. Other elements are written as in a plain array.
+    RleArray(Box<Header>),
+    // An exact decimal written as its unscaled `i128` value, with `scale` digits after the point.
+    Decimal { scale: u8 },
+    // An explicit null, such as a JSON `null`. Like `Unit` it takes no bytes, but it stays
+    // distinct from both `Unit` and a missing `Optional`.
+    Null,
+    // An array written as chunks, each its length followed by its elements, and ended by an empty
+    // chunk, so the total count need not be known up front.
+    SegmentedArray(Box<Header>),
+}
+
+impl Header {
+    // The fields stored as separate columns when this is the element of a columnar array.
+    pub(crate) fn columns(&self) -> Option<&Vec<Header>> {
+        match self {
+            Header::Tuple(inner) => Some(inner),
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) => inner.columns(),
+            _ => None,
+        }
+    }
+
+    // The inner header when this is an `Optional` field of a `BitmapStruct`, seen through
+    // annotations, tags and reserved slots. Such a field's presence is in the bitmap.
+    pub(crate) fn bitmap_optional(&self) -> Option<&Header> {
+        match self {
+            Header::Optional(inner) => Some(inner),
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) | Header::Reserved(inner) => {
+                inner.bitmap_optional()
+            }
+            _ => None,
+        }
+    }
+
+    // The header of the value stored under `key` in a `FlattenedMap`.
+    pub(crate) fn flattened_value<'a>(
+        fields: &'a [(String, Header)],
+        extra: &'a Header,
+        key: &str,
+    ) -> &'a Header {
+        fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map_or(extra, |(_, inner)| inner)
+    }
+
+    // Whether the elements of a delta array with this element header are stored as differences.
+    pub(crate) fn is_delta_integer(&self) -> bool {
+        match self {
+            Header::UInt8
+            | Header::UInt16
+            | Header::UInt32
+            | Header::UInt64
+            | Header::Int8
+            | Header::Int16
+            | Header::Int32
+            | Header::Int64 => true,
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) => inner.is_delta_integer(),
+            _ => false,
+        }
+    }
+
+    // Flattens the schema into one `(path, type)` pair per leaf, e.g. `$.1[].0: UInt32`. Tuple
+    // fields are `.i`, array elements `[]`, map values `{}`, optional values `?` and enum variants
+    // `::i`. Annotations and tags are appended to the type of every leaf beneath them.
+    pub fn describe(&self) -> Vec<(String, String)> {
+        let mut buf = Vec::new();
+        self.describe_into("$".to_string(), &mut buf);
+        buf
+    }
+
+    fn describe_into(&self, path: String, buf: &mut Vec<(String, String)>) {
+        match self {
+            Header::Optional(inner) => inner.describe_into(format!("{}?", path), buf),
+            Header::Array(inner)
+            | Header::Columnar(inner)
+            | Header::DeltaArray(inner)
+            | Header::RleArray(inner)
+            | Header::SegmentedArray(inner) => inner.describe_into(format!("{}[]", path), buf),
+            Header::Map(inner) => inner.describe_into(format!("{}{{}}", path), buf),
+            Header::FlattenedMap(fields, extra) => {
+                for (name, inner) in fields.iter() {
+                    inner.describe_into(format!("{}.{}", path, name), buf);
+                }
+                extra.describe_into(format!("{}{{}}", path), buf);
+            }
+            Header::Tuple(inner) | Header::BitmapStruct(inner) if !inner.is_empty() => {
+                for (i, inner) in inner.iter().enumerate() {
+                    inner.describe_into(format!("{}.{}", path, i), buf);
+                }
+            }
+            Header::Enum(inner) | Header::OpenEnum(inner) => {
+                for (i, inner) in inner.iter().enumerate() {
+                    inner.describe_into(format!("{}::{}", path, i), buf);
+                }
+            }
+            Header::Annotated(inner, annotation) => {
+                let start = buf.len();
+                inner.describe_into(path, buf);
+                for (_, ty) in buf[start..].iter_mut() {
+                    ty.push_str(&format!(" // {}", annotation));
+                }
+            }
+            Header::Tagged(inner, tag) => {
+                let start = buf.len();
+                inner.describe_into(path, buf);
+                for (_, ty) in buf[start..].iter_mut() {
+                    ty.push_str(&format!(" @{}", tag));
+                }
+            }
+            Header::Reserved(inner) => {
+                let start = buf.len();
+                inner.describe_into(path, buf);
+                for (_, ty) in buf[start..].iter_mut() {
+                    ty.push_str(" (reserved)");
+                }
+            }
+            leaf => buf.push((path, format!("{:?}", leaf))),
+        }
+    }
+
+    // An equivalent schema with the smallest encoding this can find. Values are written the same
+    // under both and decode to the same `Body`, so only rewrites that change neither are made:
+    // annotations and tags are dropped, and everything else is kept as is.
+    pub fn optimize(&self) -> Header {
+        fn all(inner: &[Header]) -> Vec<Header> {
+            inner.iter().map(Header::optimize).collect()
+        }
+
+        match self {
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) => inner.optimize(),
+            Header::Optional(inner) => Header::Optional(Box::new(inner.optimize())),
+            Header::Array(inner) => Header::Array(Box::new(inner.optimize())),
+            Header::Map(inner) => Header::Map(Box::new(inner.optimize())),
+            Header::Columnar(inner) => Header::Columnar(Box::new(inner.optimize())),
+            Header::DeltaArray(inner) => Header::DeltaArray(Box::new(inner.optimize())),
+            Header::RleArray(inner) => Header::RleArray(Box::new(inner.optimize())),
+            Header::SegmentedArray(inner) => Header::SegmentedArray(Box::new(inner.optimize())),
+            Header::Reserved(inner) => Header::Reserved(Box::new(inner.optimize())),
+            Header::Tuple(inner) => Header::Tuple(all(inner)),
+            Header::Enum(inner) => Header::Enum(all(inner)),
+            Header::BitmapStruct(inner) => Header::BitmapStruct(all(inner)),
+            Header::OpenEnum(inner) => Header::OpenEnum(all(inner)),
+            Header::FlattenedMap(fields, extra) => Header::FlattenedMap(
+                fields
+                    .iter()
+                    .map(|(name, inner)| (name.clone(), inner.optimize()))
+                    .collect(),
+                Box::new(extra.optimize()),
+            ),
+            leaf => leaf.clone(),
+        }
+    }
+
+    // Whether every enum in the schema has at most 256 variants, so each of its tags fits in a
+    // single byte. An open enum may gain variants, so its length proves nothing.
+    pub(crate) fn fits_compact_enum_tags(&self) -> bool {
+        match self {
+            Header::Enum(inner) => {
+                inner.len() <= 256 && inner.iter().all(Header::fits_compact_enum_tags)
+            }
+            Header::OpenEnum(_) => false,
+            Header::Tuple(inner) | Header::BitmapStruct(inner) => {
+                inner.iter().all(Header::fits_compact_enum_tags)
+            }
+            Header::Optional(inner)
+            | Header::Array(inner)
+            | Header::Map(inner)
+            | Header::Annotated(inner, _)
+            | Header::Tagged(inner, _)
+            | Header::Columnar(inner)
+            | Header::DeltaArray(inner)
+            | Header::RleArray(inner)
+            | Header::SegmentedArray(inner)
+            | Header::Reserved(inner) => inner.fits_compact_enum_tags(),
+            Header::FlattenedMap(fields, extra) => {
+                extra.fits_compact_enum_tags()
+                    && fields
+                        .iter()
+                        .all(|(_, inner)| inner.fits_compact_enum_tags())
+            }
+            _ => true,
+        }
+    }
+
+    pub fn min_format_version(&self) -> u16 {
+        match self {
+            Header::Optional(inner) | Header::Array(inner) | Header::Map(inner) => {
+                inner.min_format_version()
+            }
+            Header::Tuple(inner) | Header::Enum(inner) => inner
+                .iter()
+                .map(Header::min_format_version)
+                .max()
+                .unwrap_or(1),
+            Header::Annotated(inner, _)
+            | Header::Tagged(inner, _)
+            | Header::Columnar(inner)
+            | Header::DeltaArray(inner)
+            | Header::RleArray(inner)
+            | Header::SegmentedArray(inner)
+            | Header::Reserved(inner) => inner.min_format_version().max(2),
+            Header::FlattenedMap(fields, extra) => fields
+                .iter()
+                .map(|(_, inner)| inner.min_format_version())
+                .fold(extra.min_format_version().max(2), u16::max),
+            Header::BitmapStruct(inner) | Header::OpenEnum(inner) => inner
+                .iter()
+                .map(Header::min_format_version)
+                .fold(2, u16::max),
+            Header::InternedString
+            | Header::FixedPoint { .. }
+            | Header::Decimal { .. }
+            | Header::Null
+            | Header::Duration
+            | Header::SystemTime
+            | Header::TypedBinary
+            | Header::DurationNanos128 => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Header, HeaderCode};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn header_code_try_from() {
+        assert_eq!(HeaderCode::try_from(3).unwrap(), HeaderCode::UInt8);
+        assert_eq!(HeaderCode::try_from(26).unwrap(), HeaderCode::DateTime);
+        assert!(HeaderCode::try_from(7).is_err());
+        assert!(HeaderCode::try_from(255).is_err());
+    }
+
+    #[test]
+    fn optimize() {
+        let header = Header::Annotated(
+            Box::new(Header::Tuple(vec![
+                Header::Tagged(
+                    Box::new(Header::Annotated(
+                        Box::new(Header::UInt32),
+                        "id".to_string(),
+                    )),
+                    3,
+                ),
+                Header::Columnar(Box::new(Header::Annotated(
+                    Box::new(Header::Tuple(vec![Header::Int8])),
+                    "row".to_string(),
+                ))),
+                Header::FlattenedMap(
+                    vec![("a".to_string(), Header::Tagged(Box::new(Header::String), 1))],
+                    Box::new(Header::Reserved(Box::new(Header::Tagged(
+                        Box::new(Header::Boolean),
+                        2,
+                    )))),
+                ),
+            ])),
+            "record".to_string(),
+        );
+        assert_eq!(
+            header.optimize(),
+            Header::Tuple(vec![
+                Header::UInt32,
+                Header::Columnar(Box::new(Header::Tuple(vec![Header::Int8]))),
+                Header::FlattenedMap(
+                    vec![("a".to_string(), Header::String)],
+                    Box::new(Header::Reserved(Box::new(Header::Boolean))),
+                ),
+            ])
+        );
+        assert_eq!(Header::Null.optimize(), Header::Null);
+    }
+
+    #[test]
+    fn header_code_display() {
+        assert_eq!(HeaderCode::UInt8.to_string(), "UInt8");
+        assert_eq!(HeaderCode::BigDecimal.to_string(), "BigDecimal");
+    }
+
+    #[test]
+    fn describe() {
+        let point = Header::Tuple(vec![Header::Int16, Header::Int16]);
+        let header = Header::Tuple(vec![
+            Header::Annotated(Box::new(Header::UInt32), "id".to_string()),
+            Header::Array(Box::new(Header::Tuple(vec![
+                Header::String,
+                Header::Optional(Box::new(Header::FixedPoint { scale: 2 })),
+                Header::Map(Box::new(Header::Array(Box::new(point)))),
+            ]))),
+            Header::Enum(vec![
+                Header::Unit,
+                Header::Tagged(Box::new(Header::UInt8), 7),
+            ]),
+            Header::Tuple(vec![]),
+        ]);
+        let lines = header
+            .describe()
+            .into_iter()
+            .map(|(path, ty)| format!("{}: {}", path, ty))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "$.0: UInt32 // id",
+                "$.1[].0: String",
+                "$.1[].1?: FixedPoint { scale: 2 }",
+                "$.1[].2{}[].0: Int16",
+                "$.1[].2{}[].1: Int16",
+                "$.2::0: Unit",
+                "$.2::1: UInt8 @7",
+                "$.3: Tuple([])",
+            ]
+        );
+        assert_eq!(
+            Header::Boolean.describe(),
+            [("$".to_string(), "Boolean".to_string())]
+        );
+    }
+
+    #[test]
+    fn min_format_version() {
+        let v1 = Header::Tuple(vec![
+            Header::UInt32,
+            Header::Optional(Box::new(Header::String)),
+            Header::Map(Box::new(Header::Array(Box::new(Header::Date)))),
+            Header::Enum(vec![Header::Unit, Header::BigDecimal]),
+        ]);
+        assert_eq!(v1.min_format_version(), 1);
+        assert_eq!(Header::Tuple(vec![]).min_format_version(), 1);
+
+        let v2 = Header::Tuple(vec![
+            Header::UInt32,
+            Header::Map(Box::new(Header::Enum(vec![
+                Header::Unit,
+                Header::FixedPoint { scale: 2 },
+            ]))),
+        ]);
+        assert_eq!(v2.min_format_version(), 2);
+        assert_eq!(
+            Header::Array(Box::new(Header::InternedString)).min_format_version(),
+            2
+        );
+        assert_eq!(
+            Header::Tagged(Box::new(Header::Boolean), 1).min_format_version(),
+            2
+        );
+        assert_eq!(
+            Header::Columnar(Box::new(Header::Tuple(vec![Header::UInt8]))).min_format_version(),
+            2
+        );
+        assert_eq!(
+            Header::DeltaArray(Box::new(Header::UInt64)).min_format_version(),
+            2
+        );
+        assert_eq!(
+            Header::RleArray(Box::new(Header::Int8)).min_format_version(),
+            2
+        );
+        assert_eq!(Header::TypedBinary.min_format_version(), 2);
+        assert_eq!(Header::Decimal { scale: 2 }.min_format_version(), 2);
+        assert_eq!(Header::Null.min_format_version(), 2);
+        assert_eq!(
+            Header::SegmentedArray(Box::new(Header::String)).min_format_version(),
+            2
+        );
+        assert_eq!(Header::OpenEnum(vec![Header::Unit]).min_format_version(), 2);
+    }
 }
@@ -0,0 +1,103 @@
+use super::Header;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<(String, u16), Header>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, name: &str, version: u16, header: Header) -> Option<Header> {
+        self.schemas.insert((name.to_string(), version), header)
+    }
+
+    pub fn lookup(&self, name: &str, version: u16) -> Option<&Header> {
+        self.schemas.get(&(name.to_string(), version))
+    }
+
+    pub fn versions<'a>(&'a self, name: &'a str) -> impl Iterator<Item = u16> + 'a {
+        self.schemas
+            .keys()
+            .filter(move |(key, _)| key == name)
+            .map(|(_, version)| *version)
+    }
+}
+
+// Picks the highest version registered on both sides with the same schema. A version that both
+// sides know but describe differently is never chosen.
+pub fn negotiate(local: &SchemaRegistry, remote: &SchemaRegistry) -> Option<(String, u16)> {
+    local
+        .schemas
+        .iter()
+        .filter(|(key, header)| remote.schemas.get(key) == Some(header))
+        .max_by_key(|((name, version), _)| (*version, name.clone()))
+        .map(|((name, version), _)| (name.clone(), *version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{negotiate, SchemaRegistry};
+    use crate::Header;
+
+    fn v1() -> Header {
+        Header::Tuple(vec![Header::UInt32, Header::String])
+    }
+
+    fn v2() -> Header {
+        Header::Tuple(vec![
+            Header::UInt32,
+            Header::String,
+            Header::Optional(Box::new(Header::Boolean)),
+        ])
+    }
+
+    #[test]
+    fn register_lookup() {
+        let mut registry = SchemaRegistry::new();
+        assert_eq!(registry.register("ping", 1, v1()), None);
+        assert_eq!(registry.register("ping", 2, v2()), None);
+        assert_eq!(registry.register("ping", 1, v1()), Some(v1()));
+        assert_eq!(registry.lookup("ping", 2), Some(&v2()));
+        assert_eq!(registry.lookup("ping", 3), None);
+        assert_eq!(registry.lookup("pong", 1), None);
+        assert_eq!(registry.versions("ping").collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn negotiate_highest_shared_version() {
+        let mut local = SchemaRegistry::new();
+        local.register("ping", 1, v1());
+        local.register("ping", 2, v2());
+
+        let mut remote = SchemaRegistry::new();
+        remote.register("ping", 1, v1());
+        assert_eq!(negotiate(&local, &remote), Some(("ping".to_string(), 1)));
+
+        remote.register("ping", 2, v2());
+        remote.register("ping", 3, v2());
+        assert_eq!(negotiate(&local, &remote), Some(("ping".to_string(), 2)));
+        assert_eq!(negotiate(&remote, &local), Some(("ping".to_string(), 2)));
+    }
+
+    #[test]
+    fn negotiate_incompatible_schema() {
+        let mut local = SchemaRegistry::new();
+        local.register("ping", 1, v1());
+        local.register("ping", 2, v2());
+
+        // The remote reuses version 2 for a different schema, so only version 1 is safe.
+        let mut remote = SchemaRegistry::new();
+        remote.register("ping", 1, v1());
+        remote.register("ping", 2, Header::Tuple(vec![Header::UInt64]));
+        assert_eq!(negotiate(&local, &remote), Some(("ping".to_string(), 1)));
+
+        let mut remote = SchemaRegistry::new();
+        remote.register("ping", 1, Header::Tuple(vec![Header::String]));
+        assert_eq!(negotiate(&local, &remote), None);
+        assert_eq!(negotiate(&local, &SchemaRegistry::new()), None);
+    }
+}
@@ -1,9 +1,15 @@
 use super::Header;
-use crate::{BigDecimal, BigInt, BigUint, Date, DateTime, PrefixVarint};
+use crate::{BigDecimal, BigInt, BigUint, Date, DateTime, Decimal, FixedPoint, PrefixVarint};
 use serde_bytes::{ByteBuf, Bytes};
 use std::{
     collections::{BTreeMap, HashMap},
     io::{Result, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    num::{
+        NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64,
+        NonZeroU8,
+    },
+    time::{Duration, SystemTime},
 };
 
 pub trait SerializeHeader {
@@ -83,6 +89,29 @@ impl SerializeHeader for i64 {
     }
 }
 
+macro_rules! non_zero_impls {
+    ($($ty:ty => $code:ident)+) => {
+        $(
+            impl SerializeHeader for $ty {
+                fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+                    writer.write_all(&[super::$code])
+                }
+            }
+        )+
+    }
+}
+
+non_zero_impls! {
+    NonZeroU8 => UINT8_CODE
+    NonZeroU16 => UINT16_CODE
+    NonZeroU32 => UINT32_CODE
+    NonZeroU64 => UINT64_CODE
+    NonZeroI8 => INT8_CODE
+    NonZeroI16 => INT16_CODE
+    NonZeroI32 => INT32_CODE
+    NonZeroI64 => INT64_CODE
+}
+
 // impl SerializeHeader for i128 {
 //     fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
 //         writer.write_all(&[super::INT128_CODE])
@@ -171,6 +200,54 @@ impl<T: SerializeHeader> SerializeHeader for Vec<T> {
     }
 }
 
+#[cfg(feature = "smallvec")]
+impl<A: smallvec::Array> SerializeHeader for smallvec::SmallVec<A>
+where
+    A::Item: SerializeHeader,
+{
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::ARRAY_CODE])?;
+        A::Item::serialize_header(writer)
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<T: SerializeHeader, const CAP: usize> SerializeHeader for arrayvec::ArrayVec<T, CAP> {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::ARRAY_CODE])?;
+        T::serialize_header(writer)
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<T: SerializeHeader, S> SerializeHeader for indexmap::IndexSet<T, S> {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::ARRAY_CODE])?;
+        T::serialize_header(writer)
+    }
+}
+
+#[cfg(feature = "either")]
+impl<L: SerializeHeader, R: SerializeHeader> SerializeHeader for either::Either<L, R> {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::ENUM_CODE, 2])?;
+        L::serialize_header(writer)?;
+        R::serialize_header(writer)
+    }
+}
+
+impl<const SCALE: i8> SerializeHeader for FixedPoint<SCALE> {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::FIXED_POINT_CODE, SCALE as u8])
+    }
+}
+
+impl<const SCALE: u8> SerializeHeader for Decimal<SCALE> {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::DECIMAL_CODE, SCALE])
+    }
+}
+
 impl SerializeHeader for Date {
     fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
         writer.write_all(&[super::DATE_CODE])
@@ -197,6 +274,18 @@ impl SerializeHeader for time::OffsetDateTime {
     }
 }
 
+impl SerializeHeader for Duration {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::DURATION_CODE])
+    }
+}
+
+impl SerializeHeader for SystemTime {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::SYSTEM_TIME_CODE])
+    }
+}
+
 impl<K: AsRef<str>, V: SerializeHeader> SerializeHeader for BTreeMap<K, V> {
     fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
         writer.write_all(&[super::MAP_CODE])?;
@@ -211,6 +300,84 @@ impl<K: AsRef<str>, V: SerializeHeader> SerializeHeader for HashMap<K, V> {
     }
 }
 
+#[cfg(feature = "indexmap")]
+impl<K: AsRef<str>, V: SerializeHeader, S> SerializeHeader for indexmap::IndexMap<K, V, S> {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::MAP_CODE])?;
+        V::serialize_header(writer)
+    }
+}
+
+// A tuple of `len` fields that all have the header `code`.
+fn serialize_repeated_header<W: Write>(len: u16, code: u8, writer: &mut W) -> Result<()> {
+    writer.write_all(&[super::TUPLE_CODE])?;
+    let mut buf = [0u8; u16::PREFIX_VARINT_BUF_SIZE];
+    let size = len.encode_prefix_varint(&mut buf);
+    writer.write_all(&buf[..size])?;
+    writer.write_all(&vec![code; len as usize])
+}
+
+// serde writes addresses as tuples of their octets when the format is not human readable.
+impl SerializeHeader for Ipv4Addr {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        serialize_repeated_header(4, super::UINT8_CODE, writer)
+    }
+}
+
+impl SerializeHeader for Ipv6Addr {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        serialize_repeated_header(16, super::UINT8_CODE, writer)
+    }
+}
+
+// glam writes its vectors, quaternions and matrices as tuple structs of their components, with
+// matrices in column-major order.
+#[cfg(feature = "glam")]
+macro_rules! glam_impls {
+    ($($code:ident => ($($name:ident: $len:expr),+))+) => {
+        $(
+            $(
+                impl SerializeHeader for glam::$name {
+                    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+                        serialize_repeated_header($len, super::$code, writer)
+                    }
+                }
+            )+
+        )+
+    }
+}
+
+#[cfg(feature = "glam")]
+glam_impls! {
+    BOOLEAN_CODE => (BVec2: 2, BVec3: 3, BVec4: 4)
+    FLOAT32_CODE => (
+        Vec2: 2, Vec3: 3, Vec3A: 3, Vec4: 4, Quat: 4,
+        Mat2: 4, Mat3: 9, Mat3A: 9, Mat4: 16, Affine2: 6, Affine3A: 12
+    )
+    FLOAT64_CODE => (
+        DVec2: 2, DVec3: 3, DVec4: 4, DQuat: 4,
+        DMat2: 4, DMat3: 9, DMat4: 16, DAffine2: 6, DAffine3: 12
+    )
+    INT8_CODE => (I8Vec2: 2, I8Vec3: 3, I8Vec4: 4)
+    INT16_CODE => (I16Vec2: 2, I16Vec3: 3, I16Vec4: 4)
+    INT32_CODE => (IVec2: 2, IVec3: 3, IVec4: 4)
+    INT64_CODE => (I64Vec2: 2, I64Vec3: 3, I64Vec4: 4)
+    UINT8_CODE => (U8Vec2: 2, U8Vec3: 3, U8Vec4: 4)
+    UINT16_CODE => (U16Vec2: 2, U16Vec3: 3, U16Vec4: 4)
+    UINT32_CODE => (UVec2: 2, UVec3: 3, UVec4: 4)
+    UINT64_CODE => (U64Vec2: 2, U64Vec3: 3, U64Vec4: 4)
+}
+
+// The variant indexes come from serde (`V4` = 0, `V6` = 1) and are part of the wire format, so
+// stored addresses stay readable only as long as this order does not change.
+impl SerializeHeader for IpAddr {
+    fn serialize_header<W: Write>(writer: &mut W) -> Result<()> {
+        writer.write_all(&[super::ENUM_CODE, 2])?;
+        Ipv4Addr::serialize_header(writer)?;
+        Ipv6Addr::serialize_header(writer)
+    }
+}
+
 macro_rules! tuple_impls {
     ($($len:expr => ($($name:ident)+))+) => {
         $(
@@ -282,11 +449,70 @@ impl Header {
             // Header::Struct(inner) => Self::serialize_inner_vec(super::STRUCT_CODE, inner, writer),
             Header::Map(inner) => Self::serialize_inner_box(super::MAP_CODE, inner, writer),
             Header::Enum(inner) => Self::serialize_inner_vec(super::ENUM_CODE, inner, writer),
+            Header::OpenEnum(inner) => {
+                Self::serialize_inner_vec(super::OPEN_ENUM_CODE, inner, writer)
+            }
             Header::Date => Date::serialize_header(writer),
             Header::DateTime => DateTime::serialize_header(writer),
+            Header::Duration => Duration::serialize_header(writer),
+            Header::SystemTime => SystemTime::serialize_header(writer),
+            Header::Annotated(inner, annotation) => {
+                Self::serialize_inner_box(super::ANNOTATED_CODE, inner, writer)?;
+                Self::serialize_str(annotation, writer)
+            }
+            Header::Tagged(inner, tag) => {
+                Self::serialize_inner_box(super::TAGGED_CODE, inner, writer)?;
+                let mut buf = [0u8; u16::PREFIX_VARINT_BUF_SIZE];
+                let size = tag.encode_prefix_varint(&mut buf);
+                writer.write_all(&buf[..size])
+            }
+            Header::InternedString => writer.write_all(&[super::INTERNED_STRING_CODE]),
+            Header::TypedBinary => writer.write_all(&[super::TYPED_BINARY_CODE]),
+            Header::DurationNanos128 => writer.write_all(&[super::DURATION_NANOS_128_CODE]),
+            Header::BitmapStruct(inner) => {
+                Self::serialize_inner_vec(super::BITMAP_STRUCT_CODE, inner, writer)
+            }
+            Header::FixedPoint { scale } => {
+                writer.write_all(&[super::FIXED_POINT_CODE, *scale as u8])
+            }
+            Header::Decimal { scale } => writer.write_all(&[super::DECIMAL_CODE, *scale]),
+            Header::Null => writer.write_all(&[super::NULL_CODE]),
+            Header::Columnar(inner) => {
+                Self::serialize_inner_box(super::COLUMNAR_CODE, inner, writer)
+            }
+            Header::DeltaArray(inner) => {
+                Self::serialize_inner_box(super::DELTA_ARRAY_CODE, inner, writer)
+            }
+            Header::Reserved(inner) => {
+                Self::serialize_inner_box(super::RESERVED_CODE, inner, writer)
+            }
+            Header::RleArray(inner) => {
+                Self::serialize_inner_box(super::RLE_ARRAY_CODE, inner, writer)
+            }
+            Header::SegmentedArray(inner) => {
+                Self::serialize_inner_box(super::SEGMENTED_ARRAY_CODE, inner, writer)
+            }
+            Header::FlattenedMap(fields, extra) => {
+                writer.write_all(&[super::FLATTENED_MAP_CODE])?;
+                let mut buf = [0u8; u16::PREFIX_VARINT_BUF_SIZE];
+                let size = (fields.len() as u16).encode_prefix_varint(&mut buf);
+                writer.write_all(&buf[..size])?;
+                for (name, inner) in fields {
+                    Self::serialize_str(name, writer)?;
+                    inner.serialize(writer)?;
+                }
+                extra.serialize(writer)
+            }
         }
     }
 
+    fn serialize_str<W: Write>(v: &str, writer: &mut W) -> Result<()> {
+        let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
+        let size = (v.len() as u64).encode_prefix_varint(&mut buf);
+        writer.write_all(&buf[..size])?;
+        writer.write_all(v.as_bytes())
+    }
+
     fn serialize_inner_box<W: Write>(code: u8, inner: &Header, writer: &mut W) -> Result<()> {
         writer.write_all(&[code])?;
         inner.serialize(writer)
@@ -312,7 +538,11 @@ mod tests {
         date_time::DateTime,
     };
     use serde_bytes::{ByteBuf, Bytes};
-    use std::collections::{BTreeMap, HashMap};
+    use std::{
+        collections::{BTreeMap, HashMap},
+        net::{IpAddr, Ipv4Addr},
+        num::{NonZeroI64, NonZeroU32, NonZeroU8},
+    };
 
     #[test]
     fn serialize_header_unit() {
@@ -405,6 +635,110 @@ mod tests {
     //     assert_eq!(buf, [12]);
     // }
 
+    #[test]
+    fn serialize_header_non_zero() {
+        let mut buf = Vec::new();
+        Option::<NonZeroU8>::serialize_header(&mut buf).unwrap();
+        NonZeroU32::serialize_header(&mut buf).unwrap();
+        NonZeroI64::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [1, 3, 5, 11]);
+    }
+
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn serialize_header_small_vec() {
+        let mut buf = Vec::new();
+        smallvec::SmallVec::<[u8; 8]>::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [20, 3]);
+    }
+
+    #[cfg(feature = "arrayvec")]
+    #[test]
+    fn serialize_header_array_vec() {
+        let mut buf = Vec::new();
+        arrayvec::ArrayVec::<u32, 4>::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [20, 5]);
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn serialize_header_index_map() {
+        let mut buf = Vec::new();
+        indexmap::IndexMap::<String, u8>::serialize_header(&mut buf).unwrap();
+        indexmap::IndexSet::<bool>::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [23, 3, 20, 2]);
+    }
+
+    #[test]
+    fn serialize_header_ip_addr() {
+        let mut buf = Vec::new();
+        Ipv4Addr::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [21, 4, 3, 3, 3, 3]);
+
+        let mut buf = Vec::new();
+        IpAddr::serialize_header(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [
+                [24, 2, 21, 4, 3, 3, 3, 3, 21, 16].as_ref(),
+                [3; 16].as_ref()
+            ]
+            .concat()
+        );
+    }
+
+    #[cfg(feature = "either")]
+    #[test]
+    fn serialize_header_either() {
+        let mut buf = Vec::new();
+        either::Either::<bool, String>::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [24, 2, 2, 18]);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn serialize_header_glam() {
+        use crate::{Body, DeserializeHeader, Deserializer, Serializer};
+        use glam::{DVec2, IVec3, Mat4, Vec3};
+        use serde::{de::DeserializeOwned, Serialize};
+        use std::fmt::Debug;
+
+        fn roundtrip<T: SerializeHeader + Serialize + DeserializeOwned + PartialEq + Debug>(v: T) {
+            let mut buf = Vec::new();
+            v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+            let mut reader = buf.as_slice();
+            assert_eq!(T::deserialize(&mut Deserializer::new(&mut reader)), Ok(v));
+            assert!(reader.is_empty());
+
+            let mut header = Vec::new();
+            T::serialize_header(&mut header).unwrap();
+            let header = header.as_slice().deserialize_header().unwrap();
+            let body = Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice()));
+            assert!(body.unwrap().validate(&header));
+        }
+
+        let mut buf = Vec::new();
+        Vec3::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [21, 3, 13, 13, 13]);
+
+        let mut buf = Vec::new();
+        Mat4::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf[..2], [21, 16]);
+        assert_eq!(buf[2..], [13; 16]);
+
+        let mut buf = Vec::new();
+        IVec3::serialize_header(&mut buf).unwrap();
+        assert_eq!(buf, [21, 3, 10, 10, 10]);
+
+        roundtrip(Vec3::new(1.5, -0.0, f32::MAX));
+        roundtrip(Mat4::from_cols_array(&std::array::from_fn(|i| {
+            i as f32 / 3.0 - 2.0
+        })));
+        roundtrip(Mat4::IDENTITY);
+        roundtrip(DVec2::new(f64::MIN_POSITIVE, std::f64::consts::PI));
+        roundtrip(IVec3::new(i32::MIN, 0, i32::MAX));
+    }
+
     #[test]
     fn serialize_header_f32() {
         let mut buf = Vec::new();
@@ -559,7 +893,10 @@ mod tests {
             big_uint::BigUint,
             date::Date,
             date_time::DateTime,
+            decimal::Decimal,
+            fixed_point::FixedPoint,
             header::{ser::SerializeHeader, Header},
+            PrefixVarint,
         };
         use serde_bytes::ByteBuf;
         use std::collections::BTreeMap;
@@ -757,6 +1094,131 @@ mod tests {
             assert_eq!(serialize(Header::DateTime), serialize_header::<DateTime>());
         }
 
+        #[test]
+        fn serialize_tagged() {
+            assert_eq!(
+                serialize(Header::Tagged(Box::new(Header::Float64), 300)),
+                [&[28, 14][..], &300u16.encode_prefix_varint_vec()].concat()
+            );
+        }
+
+        #[test]
+        fn serialize_interned_string() {
+            assert_eq!(serialize(Header::InternedString), [29]);
+        }
+
+        #[test]
+        fn serialize_bitmap_struct() {
+            assert_eq!(
+                serialize(Header::BitmapStruct(vec![
+                    Header::Boolean,
+                    Header::Optional(Box::new(Header::UInt8))
+                ])),
+                [31, 2, 2, 1, 3]
+            );
+        }
+
+        #[test]
+        fn serialize_columnar() {
+            assert_eq!(
+                serialize(Header::Columnar(Box::new(Header::Tuple(vec![
+                    Header::Boolean,
+                    Header::UInt8
+                ])))),
+                [32, 21, 2, 2, 3]
+            );
+        }
+
+        #[test]
+        fn serialize_delta_array() {
+            assert_eq!(
+                serialize(Header::DeltaArray(Box::new(Header::UInt32))),
+                [35, 5]
+            );
+        }
+
+        #[test]
+        fn serialize_rle_array() {
+            assert_eq!(
+                serialize(Header::RleArray(Box::new(Header::UInt8))),
+                [41, 3]
+            );
+        }
+
+        #[test]
+        fn serialize_open_enum() {
+            assert_eq!(
+                serialize(Header::OpenEnum(vec![Header::Unit, Header::String])),
+                [40, 2, 0, 18]
+            );
+        }
+
+        #[test]
+        fn serialize_reserved() {
+            assert_eq!(
+                serialize(Header::Tuple(vec![
+                    Header::UInt8,
+                    Header::Reserved(Box::new(Header::String)),
+                ])),
+                [21, 2, 3, 38, 18]
+            );
+        }
+
+        #[test]
+        fn serialize_flattened_map() {
+            assert_eq!(
+                serialize(Header::FlattenedMap(
+                    vec![("id".to_string(), Header::UInt32)],
+                    Box::new(Header::String),
+                )),
+                [39, 1, 2, b'i', b'd', 5, 18]
+            );
+        }
+
+        #[test]
+        fn serialize_fixed_point() {
+            assert_eq!(serialize(Header::FixedPoint { scale: 2 }), [30, 2]);
+            assert_eq!(serialize(Header::FixedPoint { scale: -1 }), [30, 0xff]);
+            assert_eq!(
+                serialize(Header::FixedPoint { scale: 3 }),
+                serialize_header::<FixedPoint<3>>()
+            );
+        }
+
+        #[test]
+        fn serialize_segmented_array() {
+            assert_eq!(
+                serialize(Header::SegmentedArray(Box::new(Header::Int8))),
+                [44, 8]
+            );
+        }
+
+        #[test]
+        fn serialize_null() {
+            assert_eq!(serialize(Header::Null), [43]);
+            assert_ne!(serialize(Header::Null), serialize(Header::Unit));
+        }
+
+        #[test]
+        fn serialize_decimal() {
+            assert_eq!(serialize(Header::Decimal { scale: 2 }), [42, 2]);
+            assert_eq!(
+                serialize(Header::Decimal { scale: 4 }),
+                serialize_header::<Decimal<4>>()
+            );
+        }
+
+        #[test]
+        fn serialize_annotated() {
+            assert_eq!(
+                serialize(Header::Annotated(
+                    Box::new(Header::Boolean),
+                    "flag".to_string()
+                )),
+                [27, 2, 4, b'f', b'l', b'a', b'g']
+            );
+        }
+
         #[cfg(feature = "time")]
         #[test]
         fn serialize_date_time2() {
@@ -1,29 +1,42 @@
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
 pub mod big_decimal;
 pub mod big_int;
 pub mod big_uint;
 pub mod body;
+pub mod config;
 pub mod date;
 pub mod date_time;
 pub mod de;
+pub mod decimal;
+pub mod document;
+pub mod fixed_point;
 pub mod format;
 pub mod header;
-// pub(crate) mod leb128;
+pub(crate) mod leb128;
 pub(crate) mod prefix_varint;
+pub mod projection;
 pub mod ser;
+pub mod stream;
 pub(crate) mod zigzag;
 
 pub use big_decimal::*;
 pub use big_int::*;
 pub use big_uint::*;
 pub use body::*;
+pub use config::Config;
 pub use date::*;
 pub use date_time::*;
 pub use de::Deserializer;
+pub use decimal::Decimal;
+pub use document::Document;
+pub use fixed_point::FixedPoint;
 pub use header::de::*;
 pub use header::ser::*;
 pub use header::Header;
+pub(crate) use leb128::*;
 pub(crate) use prefix_varint::*;
 pub use ser::Serializer;
 pub(crate) use zigzag::*;
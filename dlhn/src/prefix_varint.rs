@@ -1,6 +1,9 @@
 // https://chromium.googlesource.com/chromiumos/third_party/libtextclassifier/+/adbbad2e0138453af45cc08cb3d04317ae2b8ba1/utils/base/prefixvarint.h
 
-use std::io::{Read, Result};
+use std::{
+    fmt::{self, Display},
+    io::{Error, ErrorKind, Read, Result},
+};
 
 pub(crate) trait PrefixVarint<const N: usize>: Sized {
     const PREFIX_VARINT_BUF_SIZE: usize = N;
@@ -15,12 +18,38 @@ pub(crate) trait PrefixVarint<const N: usize>: Sized {
     }
 }
 
+// The error source when the input ends after the first byte of a varint, which promised more.
+#[derive(Debug)]
+pub(crate) struct TruncatedVarint;
+
+impl TruncatedVarint {
+    pub(crate) fn is(e: &Error) -> bool {
+        e.get_ref().is_some_and(|e| e.is::<TruncatedVarint>())
+    }
+}
+
+impl Display for TruncatedVarint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("truncated varint")
+    }
+}
+
+impl std::error::Error for TruncatedVarint {}
+
 fn decode_prefix(reader: &mut impl Read) -> Result<u8> {
     let mut prefix_buf = [0u8; 1];
     reader.read_exact(&mut prefix_buf)?;
     Ok(prefix_buf[0])
 }
 
+// Reads the bytes that follow the first byte of a varint.
+pub(crate) fn read_rest(reader: &mut impl Read, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => Error::new(ErrorKind::UnexpectedEof, TruncatedVarint),
+        _ => e,
+    })
+}
+
 impl PrefixVarint<2> for u8 {
     const PREFIX_VARINT_BUF_SIZE: usize = 2;
 
@@ -42,7 +71,7 @@ impl PrefixVarint<2> for u8 {
             Ok(prefix)
         } else {
             let mut buf = [0u8; 1];
-            reader.read_exact(&mut buf)?;
+            read_rest(reader, &mut buf)?;
             Ok(buf[0])
         }
     }
@@ -80,12 +109,12 @@ impl PrefixVarint<3> for u16 {
             0 => Ok(prefix as u16),
             1 => {
                 let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok((prefix as u16 & 0x3f) | ((buf[0] as u16) << 6))
             }
             _ => {
                 let mut buf = [0u8; 2];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok(u16::from_le_bytes(buf))
             }
         }
@@ -139,24 +168,24 @@ impl PrefixVarint<5> for u32 {
             0 => Ok(prefix as u32),
             1 => {
                 let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok((prefix as u32 & 0x3f) | ((buf[0] as u32) << 6))
             }
             2 => {
                 let mut buf = [0u8; 2];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok((prefix as u32 & 0x1f) | ((u16::from_le_bytes(buf) as u32) << 5))
             }
             3 => {
                 let mut buf = [0u8; 3];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 let mut v = buf[2] as u32;
                 v = (v << 16) | (u16::from_le_bytes([buf[0], buf[1]]) as u32);
                 Ok((prefix as u32 & 0x0f) | (v << 4))
             }
             _ => {
                 let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok(u32::from_le_bytes(buf))
             }
         }
@@ -251,36 +280,36 @@ impl PrefixVarint<9> for u64 {
             0 => Ok(prefix as u64),
             1 => {
                 let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok((prefix as u64 & 0x3f) | ((buf[0] as u64) << 6))
             }
             2 => {
                 let mut buf = [0u8; 2];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok((prefix as u64 & 0x1f) | ((u16::from_le_bytes(buf) as u64) << 5))
             }
             3 => {
                 let mut buf = [0u8; 3];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 let mut v = buf[2] as u64;
                 v = (v << 16) | (u16::from_le_bytes([buf[0], buf[1]]) as u64);
                 Ok((prefix as u64 & 0x0f) | (v << 4))
             }
             4 => {
                 let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok((prefix as u64 & 0x07) | ((u32::from_le_bytes(buf) as u64) << 3))
             }
             5 => {
                 let mut buf = [0u8; 5];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 let mut v = buf[4] as u64;
                 v = (v << 32) | (u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64);
                 Ok((prefix as u64 & 0x03) | (v << 2))
             }
             6 => {
                 let mut buf = [0u8; 6];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 let mut v = u16::from_le_bytes([buf[4], buf[5]]) as u64;
                 v = (v << 32) | (u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64);
                 Ok((prefix as u64 & 0x01) | (v << 1))
@@ -288,12 +317,12 @@ impl PrefixVarint<9> for u64 {
             7 => {
                 let mut buf = [0u8; 8];
                 buf[0] = prefix as u8;
-                reader.read_exact(&mut buf[1..8])?;
+                read_rest(reader, &mut buf[1..8])?;
                 Ok(u64::from_le_bytes(buf) >> 8)
             }
             _ => {
                 let mut buf = [0u8; 8];
-                reader.read_exact(&mut buf)?;
+                read_rest(reader, &mut buf)?;
                 Ok(u64::from_le_bytes(buf))
             }
         }
@@ -0,0 +1,151 @@
+use crate::{de::Error, Body, Deserializer, Header};
+use std::io::Read;
+
+pub type Path = Vec<usize>;
+
+impl<'de, R: Read> Deserializer<'de, R> {
+    pub fn project(&mut self, header: &Header, paths: &[Path]) -> Result<Vec<Body>, Error> {
+        let targets = paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (i, path.as_slice()))
+            .collect::<Vec<_>>();
+        let mut result = vec![None; paths.len()];
+        self.with_header(header, |de| de.project_inner(header, &targets, &mut result))?;
+        Ok(result.into_iter().flatten().collect())
+    }
+
+    fn project_inner(
+        &mut self,
+        header: &Header,
+        targets: &[(usize, &[usize])],
+        result: &mut [Option<Body>],
+    ) -> Result<(), Error> {
+        if targets.is_empty() {
+            return self.skip_value(header);
+        }
+        if targets.iter().any(|(_, path)| path.is_empty()) {
+            let body = Body::deserialize(header, self)?;
+            for (i, path) in targets.iter() {
+                result[*i] = Some(select(&body, path)?.clone());
+            }
+            return Ok(());
+        }
+
+        match header {
+            Header::Tuple(inner) => {
+                if targets.iter().any(|(_, path)| path[0] >= inner.len()) {
+                    return Err(invalid_path());
+                }
+                for (index, inner) in inner.iter().enumerate() {
+                    let targets = targets
+                        .iter()
+                        .filter(|(_, path)| path[0] == index)
+                        .map(|(i, path)| (*i, &path[1..]))
+                        .collect::<Vec<_>>();
+                    self.project_inner(inner, &targets, result)?;
+                }
+                Ok(())
+            }
+            Header::Annotated(inner, _) | Header::Tagged(inner, _) | Header::Reserved(inner) => {
+                self.project_inner(inner, targets, result)
+            }
+            _ => Err(invalid_path()),
+        }
+    }
+}
+
+fn select<'a>(body: &'a Body, path: &[usize]) -> Result<&'a Body, Error> {
+    match (body, path.split_first()) {
+        (_, None) => Ok(body),
+        (Body::Tuple(inner), Some((index, path))) => {
+            select(inner.get(*index).ok_or_else(invalid_path)?, path)
+        }
+        _ => Err(invalid_path()),
+    }
+}
+
+fn invalid_path() -> Error {
+    Error::Message("invalid projection path".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{de::Error, Body, Deserializer, Header, Serializer};
+    use serde::Serialize;
+    use serde_bytes::Bytes;
+
+    fn header() -> Header {
+        Header::Tuple(vec![
+            Header::UInt32,
+            Header::String,
+            Header::Array(Box::new(Header::Binary)),
+            Header::Tuple(vec![
+                Header::Boolean,
+                Header::Optional(Box::new(Header::Int64)),
+            ]),
+            Header::Map(Box::new(Header::Float64)),
+        ])
+    }
+
+    fn serialize<T: Serialize>(v: T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+
+    #[test]
+    fn project() {
+        let buf = [
+            serialize(123u32),
+            // Not valid UTF-8, so materializing this field would fail.
+            [serialize(3u64), vec![0xff, 0xfe, 0xfd]].concat(),
+            serialize(vec![Bytes::new(&[0; 10_000]); 100]),
+            serialize((true, Some(-1i64))),
+            serialize(std::collections::BTreeMap::from([("a", 1.5f64)])),
+        ]
+        .concat();
+
+        assert!(Body::deserialize(&header(), &mut Deserializer::new(&mut buf.as_slice())).is_err());
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.project(&header(), &[vec![0], vec![3]]),
+            Ok(vec![
+                Body::UInt32(123),
+                Body::Tuple(vec![
+                    Body::Boolean(true),
+                    Body::Optional(Some(Box::new(Body::Int64(-1)))),
+                ]),
+            ])
+        );
+        assert!(reader.is_empty());
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            deserializer.project(&header(), &[vec![3, 1], vec![3], vec![0]]),
+            Ok(vec![
+                Body::Optional(Some(Box::new(Body::Int64(-1)))),
+                Body::Tuple(vec![
+                    Body::Boolean(true),
+                    Body::Optional(Some(Box::new(Body::Int64(-1)))),
+                ]),
+                Body::UInt32(123),
+            ])
+        );
+    }
+
+    #[test]
+    fn project_invalid_path() {
+        let buf = serialize((1u32, true));
+        let header = Header::Tuple(vec![Header::UInt32, Header::Boolean]);
+        for path in [vec![2], vec![0, 0]] {
+            assert_eq!(
+                Deserializer::new(&mut buf.as_slice()).project(&header, &[path]),
+                Err(Error::Message("invalid projection path".to_string()))
+            );
+        }
+    }
+}
@@ -1,17 +1,28 @@
-use crate::{PrefixVarint, ZigZag};
+use crate::{
+    body::LENGTH_PREFIXED_NAME, format::fixed_width, Config, DeserializeHeader, Header, Leb128,
+    PrefixVarint, SerializeHeader, ZigZag,
+};
 use serde::{
     ser::{self, Impossible},
     Serialize,
 };
 use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    convert::TryFrom,
     fmt::{self, Display},
-    io::Write,
+    io::{ErrorKind, Read, Write},
+    mem,
 };
 
+// New failure modes are added as variants, so matches need a wildcard arm.
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
-    Write,
+    Write { written: usize },
     UnsupportedKeyType,
+    NonFiniteFloat,
+    Source { read: u64, expected: u64 },
     Message(String),
 }
 
@@ -24,8 +35,16 @@ impl ser::Error for Error {
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Write => formatter.write_str("write error"),
+            Error::Write { written } => {
+                write!(formatter, "write error after {} bytes", written)
+            }
             Error::UnsupportedKeyType => formatter.write_str("unsupported key type"),
+            Error::NonFiniteFloat => formatter.write_str("non-finite float"),
+            Error::Source { read, expected } => write!(
+                formatter,
+                "binary source failed after {} of {} bytes",
+                read, expected
+            ),
             Error::Message(message) => formatter.write_str(message),
         }
     }
@@ -33,13 +52,541 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+#[derive(Clone, Copy)]
+pub enum SortMode {
+    Bytes,
+    Natural,
+    Custom(fn(&str, &str) -> Ordering),
+}
+
+impl SortMode {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            SortMode::Bytes => a.as_bytes().cmp(b.as_bytes()),
+            SortMode::Natural => natural_cmp(a, b),
+            SortMode::Custom(f) => f(a, b),
+        }
+    }
+}
+
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x = String::new();
+                while let Some(c) = a.next_if(|c| c.is_ascii_digit()) {
+                    x.push(c);
+                }
+                let mut y = String::new();
+                while let Some(c) = b.next_if(|c| c.is_ascii_digit()) {
+                    y.push(c);
+                }
+                let x = x.trim_start_matches('0');
+                let y = y.trim_start_matches('0');
+                match x.len().cmp(&y.len()).then_with(|| x.cmp(y)) {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                }
+            }
+            (Some(x), Some(y)) => match x.cmp(&y) {
+                Ordering::Equal => {
+                    a.next();
+                    b.next();
+                }
+                ordering => return ordering,
+            },
+        }
+    }
+}
+
+// Buffered map entries as encoded key and value bytes.
+type MapEntries = Vec<(Vec<u8>, Vec<u8>)>;
+
+// Map keys are usually a small set written over and over, so short ones are kept encoded.
+const MAX_CACHED_KEY_LEN: usize = 32;
+const MAX_CACHED_KEYS: usize = 256;
+
 pub struct Serializer<W: Write> {
     output: W,
+    written: usize,
+    // One level per open map; `None` when the entries go straight to the output.
+    map_entries: Vec<Option<MapEntries>>,
+    struct_fields: Vec<PresenceBitmap>,
+    columns: Vec<Vec<Vec<u8>>>,
+    deltas: Vec<Option<u64>>,
+    runs: Vec<Option<Run>>,
+    strings: HashMap<String, u64>,
+    keys: HashMap<String, Vec<u8>>,
+    // Set while the bytes of a `format::fixed_width` sequence are being written.
+    fixed_width: Option<&'static str>,
+    // Set while `serialize_with_header` writes a value whose header allows single-byte enum tags.
+    compact_tags: bool,
+    config: Config,
 }
 
 impl<W: Write> Serializer<W> {
     pub fn new(output: W) -> Self {
-        Self { output }
+        Self::with_config(output, Config::default())
+    }
+
+    pub fn with_config(output: W, config: Config) -> Self {
+        Self {
+            output,
+            written: 0,
+            map_entries: Vec::new(),
+            struct_fields: Vec::new(),
+            columns: Vec::new(),
+            deltas: Vec::new(),
+            runs: Vec::new(),
+            strings: HashMap::new(),
+            keys: HashMap::new(),
+            fixed_width: None,
+            compact_tags: false,
+            config,
+        }
+    }
+
+    pub fn map_sort(mut self, mode: SortMode) -> Self {
+        self.config.map_sort = Some(mode);
+        self
+    }
+
+    pub fn compact_enum_tags(mut self, compact_enum_tags: bool) -> Self {
+        self.config.compact_enum_tags = compact_enum_tags;
+        self
+    }
+
+    pub fn reject_non_finite_floats(mut self, reject_non_finite_floats: bool) -> Self {
+        self.config.reject_non_finite_floats = reject_non_finite_floats;
+        self
+    }
+
+    pub fn string_interning(mut self, string_interning: bool) -> Self {
+        self.config.string_interning = string_interning;
+        self
+    }
+
+    pub fn bitmap_structs(mut self, bitmap_structs: bool) -> Self {
+        self.config.bitmap_structs = bitmap_structs;
+        self
+    }
+
+    pub fn columnar_arrays(mut self, columnar_arrays: bool) -> Self {
+        self.config.columnar_arrays = columnar_arrays;
+        self
+    }
+
+    pub fn delta_encode_arrays(mut self, delta_encode_arrays: bool) -> Self {
+        self.config.delta_encode_arrays = delta_encode_arrays;
+        self
+    }
+
+    pub fn rle_arrays(mut self, rle_arrays: bool) -> Self {
+        self.config.rle_arrays = rle_arrays;
+        self
+    }
+
+    // The canonical form gives every logical value exactly one encoding, for hashing and signing:
+    // - map entries are sorted by the bytes of their keys, and a duplicate key is an error;
+    // - every NaN is written as the quiet NaN `0x7fc00000` (`f32`) or `0x7ff8000000000000` (`f64`);
+    // - big decimals are normalized, without trailing zeros in the coefficient;
+    // - integers and lengths use the shortest varint, as they always do;
+    // - string interning is turned off, since it cannot be combined with sorted maps.
+    // The remaining options keep their settings, and both sides must agree on them as usual.
+    pub fn canonical(mut self) -> Self {
+        self.config.map_sort = Some(SortMode::Bytes);
+        self.config.string_interning = false;
+        self.config.canonical = true;
+        self
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    // Writes `value`, whose schema is `header`. With `compact_enum_tags` set, enum tags are a
+    // single `u8` when the header proves every enum has at most 256 variants, and the usual
+    // varint otherwise.
+    pub fn serialize_with_header<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+        header: &Header,
+    ) -> Result<(), Error> {
+        let compact_tags = mem::replace(
+            &mut self.compact_tags,
+            self.config.compact_enum_tags && header.fits_compact_enum_tags(),
+        );
+        let result = value.serialize(&mut *self);
+        self.compact_tags = compact_tags;
+        result
+    }
+
+    // Writes the header of `T` ahead of `value`, so the output can be decoded without the type by
+    // `Deserializer::deserialize_self_describing`.
+    pub fn serialize_self_describing<T: SerializeHeader + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        T::serialize_header(&mut buf).map_err(|e| Error::Message(e.to_string()))?;
+        let header = buf
+            .as_slice()
+            .deserialize_header()
+            .map_err(|e| Error::Message(e.to_string()))?;
+        self.write(&buf)?;
+        self.serialize_with_header(value, &header)
+    }
+
+    // Same bytes as serializing the slice as a sequence, written with a single write.
+    pub fn serialize_f32_slice(&mut self, v: &[f32]) -> Result<(), Error> {
+        if self.config.reject_non_finite_floats && v.iter().any(|v| !v.is_finite()) {
+            return Err(Error::NonFiniteFloat);
+        }
+        v.len().serialize(&mut *self)?;
+        let canonical = self.config.canonical;
+        let buf = v
+            .iter()
+            .map(|v| {
+                if canonical && v.is_nan() {
+                    f32::NAN
+                } else {
+                    *v
+                }
+            })
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        self.write(&buf)
+    }
+
+    pub fn serialize_f64_slice(&mut self, v: &[f64]) -> Result<(), Error> {
+        if self.config.reject_non_finite_floats && v.iter().any(|v| !v.is_finite()) {
+            return Err(Error::NonFiniteFloat);
+        }
+        v.len().serialize(&mut *self)?;
+        let canonical = self.config.canonical;
+        let buf = v
+            .iter()
+            .map(|v| {
+                if canonical && v.is_nan() {
+                    f64::NAN
+                } else {
+                    *v
+                }
+            })
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        self.write(&buf)
+    }
+
+    // Same bytes as serializing the slice as a sequence. Integers are prefix varints, so their
+    // memory layout never matches the wire, but they are encoded into one buffer and written
+    // with a single write unless delta or run-length encoding changes the elements.
+    pub fn serialize_u32_slice(&mut self, v: &[u32]) -> Result<(), Error> {
+        if self.config.delta_encode_arrays || self.config.rle_arrays {
+            return v.serialize(self);
+        }
+        v.len().serialize(&mut *self)?;
+        let mut buf = Vec::with_capacity(v.len());
+        let mut bytes = [0u8; u32::PREFIX_VARINT_BUF_SIZE];
+        for v in v.iter() {
+            let size = v.encode_prefix_varint(&mut bytes);
+            buf.extend_from_slice(&bytes[..size]);
+        }
+        self.write(&buf)
+    }
+
+    // Same bytes as `serialize_bytes` with the `len` bytes read from `src`, copied through a
+    // fixed buffer so the binary never has to fit in memory.
+    pub fn serialize_binary_streaming(&mut self, len: u64, src: impl Read) -> Result<(), Error> {
+        len.serialize(&mut *self)?;
+        let mut src = src.take(len);
+        let mut buf = [0u8; 8192];
+        let mut read = 0;
+        while read < len {
+            match src.read(&mut buf) {
+                Ok(0) => {
+                    return Err(Error::Source {
+                        read,
+                        expected: len,
+                    })
+                }
+                Ok(n) => {
+                    self.write(&buf[..n])?;
+                    read += n as u64;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => {
+                    return Err(Error::Source {
+                        read,
+                        expected: len,
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn start_map(&mut self) -> MapBuilder<'_, W> {
+        MapBuilder {
+            serializer: self,
+            entries: Vec::new(),
+        }
+    }
+
+    // Starts an array of `Header::SegmentedArray` layout. Elements are buffered until a chunk
+    // holds `chunk_len` of them, so the total count is never needed.
+    pub fn start_segmented_array(&mut self, chunk_len: usize) -> SegmentedArrayBuilder<'_, W> {
+        SegmentedArrayBuilder {
+            serializer: self,
+            chunk_len: chunk_len.max(1),
+            chunk: Vec::new(),
+            len: 0,
+        }
+    }
+
+    // The buffer borrows the string table so entries written in order keep their indices in sync.
+    fn buffer(&mut self) -> Serializer<Vec<u8>> {
+        let mut buf = Serializer::with_config(Vec::new(), self.config);
+        buf.strings = mem::take(&mut self.strings);
+        buf.keys = mem::take(&mut self.keys);
+        buf.compact_tags = self.compact_tags;
+        buf
+    }
+
+    fn unbuffer(&mut self, buf: Serializer<Vec<u8>>) -> Vec<u8> {
+        self.strings = buf.strings;
+        self.keys = buf.keys;
+        buf.output
+    }
+
+    fn serialize_map_key(&mut self, v: &str) -> Result<(), Error> {
+        if self.config.string_interning || v.len() > MAX_CACHED_KEY_LEN {
+            return ser::Serializer::serialize_str(self, v);
+        }
+        if let Some(encoded) = self.keys.get(v) {
+            return write_all(&mut self.output, &mut self.written, encoded);
+        }
+        let mut encoded = (v.len() as u64).encode_prefix_varint_vec();
+        encoded.extend_from_slice(v.as_bytes());
+        self.write(&encoded)?;
+        if self.keys.len() < MAX_CACHED_KEYS {
+            self.keys.insert(v.to_string(), encoded);
+        }
+        Ok(())
+    }
+
+    // `bits` is the element widened to 64 bits, so the difference wraps instead of overflowing.
+    fn serialize_delta(
+        &mut self,
+        bits: u64,
+        first: impl FnOnce(&mut Self) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        match self.deltas.last_mut().map(|v| v.replace(bits)) {
+            Some(Some(prev)) => (bits.wrapping_sub(prev) as i64).serialize(self),
+            _ => first(self),
+        }
+    }
+
+    // An integer equal to the previous one only extends the current run; any other starts a new
+    // run, and the finished one is written as its value followed by its length.
+    fn serialize_run(
+        &mut self,
+        bits: u64,
+        encode: impl FnOnce(&mut Serializer<Vec<u8>>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if let Some(Some(run)) = self.runs.last_mut() {
+            if run.bits == bits {
+                run.len += 1;
+                return Ok(());
+            }
+        }
+        let mut buf = self.buffer();
+        let result = encode(&mut buf);
+        let value = self.unbuffer(buf);
+        result?;
+        match self.runs.last_mut() {
+            Some(run) => {
+                let prev = run.replace(Run {
+                    bits,
+                    value,
+                    len: 1,
+                });
+                self.end_run(prev)
+            }
+            None => self.write(&value),
+        }
+    }
+
+    fn end_run(&mut self, run: Option<Run>) -> Result<(), Error> {
+        match run {
+            Some(run) => {
+                self.write(&run.value)?;
+                run.len.serialize(self)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn check_map_sort(&self) -> Result<(), Error> {
+        if self.config.map_sort.is_some() && self.config.string_interning {
+            return Err(Error::Message(
+                "string interning cannot be combined with map sorting".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn serialize_variant_index(&mut self, variant_index: u32) -> Result<(), Error> {
+        if self.compact_tags {
+            let tag = u8::try_from(variant_index).map_err(|_| {
+                Error::Message(format!(
+                    "variant index {} does not fit a single-byte tag",
+                    variant_index
+                ))
+            })?;
+            return self.write(&[tag]);
+        }
+        variant_index.serialize(self)
+    }
+
+    fn write_map_entries(&mut self, mut entries: MapEntries) -> Result<(), Error> {
+        if let Some(mode) = self.config.map_sort {
+            entries.sort_by(|(a, _), (b, _)| mode.compare(map_key_str(a), map_key_str(b)));
+        }
+        // The order of entries with the same key depends on how the map was built.
+        if self.config.canonical && entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(Error::Message(
+                "duplicate map key in canonical form".to_string(),
+            ));
+        }
+        entries.len().serialize(&mut *self)?;
+        for (key, value) in entries.iter() {
+            self.write(key)?;
+            self.write(value)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
+        write_all(&mut self.output, &mut self.written, buf)
+    }
+}
+
+// Takes the output and the count of bytes written so far separately, so it can be called while
+// other fields of the serializer are borrowed.
+fn write_all<W: Write>(output: &mut W, written: &mut usize, mut buf: &[u8]) -> Result<(), Error> {
+    while !buf.is_empty() {
+        match output.write(buf) {
+            Ok(0) => return Err(Error::Write { written: *written }),
+            Ok(n) => {
+                *written += n;
+                buf = &buf[n..];
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(_) => return Err(Error::Write { written: *written }),
+        }
+    }
+    Ok(())
+}
+
+// Fields of a struct being written in bitmap mode, buffered until the bitmap is complete.
+struct PresenceBitmap {
+    bits: Vec<u8>,
+    index: usize,
+    fields: Vec<u8>,
+}
+
+// The run of equal integers being written in a run-length encoded array: the value widened to
+// 64 bits, its encoding and how many times it repeats so far.
+struct Run {
+    bits: u64,
+    value: Vec<u8>,
+    len: u64,
+}
+
+pub struct MapBuilder<'a, W: Write> {
+    serializer: &'a mut Serializer<W>,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a, W: Write> MapBuilder<'a, W> {
+    pub fn entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.serializer.check_map_sort()?;
+        let mut buf = self.serializer.buffer();
+        let key_result = key.serialize(MapKeySerializer::new(&mut buf));
+        let key_buf = self.serializer.unbuffer(buf);
+        key_result?;
+        let mut buf = self.serializer.buffer();
+        let value_result = value.serialize(&mut buf);
+        let value_buf = self.serializer.unbuffer(buf);
+        value_result?;
+        self.entries.push((key_buf, value_buf));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn finish(self) -> Result<(), Error> {
+        self.serializer.write_map_entries(self.entries)
+    }
+}
+
+pub struct SegmentedArrayBuilder<'a, W: Write> {
+    serializer: &'a mut Serializer<W>,
+    chunk_len: usize,
+    chunk: Vec<u8>,
+    len: usize,
+}
+
+impl<'a, W: Write> SegmentedArrayBuilder<'a, W> {
+    pub fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let mut buf = self.serializer.buffer();
+        let result = value.serialize(&mut buf);
+        let value_buf = self.serializer.unbuffer(buf);
+        result?;
+        self.chunk.extend_from_slice(&value_buf);
+        self.len += 1;
+        if self.len == self.chunk_len {
+            self.end_chunk()?;
+        }
+        Ok(())
+    }
+
+    // Writes the buffered elements as a chunk now, e.g. at the end of a batch of input. Does
+    // nothing when no element is buffered, since an empty chunk ends the array.
+    pub fn end_chunk(&mut self) -> Result<(), Error> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        self.len.serialize(&mut *self.serializer)?;
+        self.serializer.write(&self.chunk)?;
+        self.chunk.clear();
+        self.len = 0;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.end_chunk()?;
+        0usize.serialize(&mut *self.serializer)
     }
 }
 
@@ -56,82 +603,86 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
         if v {
-            self.output.write_all(&[1]).or(Err(Error::Write))
+            self.write(&[1])
         } else {
-            self.output.write_all(&[0]).or(Err(Error::Write))
+            self.write(&[0])
         }
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        self.output
-            .write_all(&v.to_le_bytes())
-            .or(Err(Error::Write))
+        self.write(&v.to_le_bytes())
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
         let mut buf = [0u8; u16::PREFIX_VARINT_BUF_SIZE];
         let size = v.encode_zigzag().encode_prefix_varint(&mut buf);
-        self.output.write_all(&buf[..size]).or(Err(Error::Write))
+        self.write(&buf[..size])
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
         let mut buf = [0u8; u32::PREFIX_VARINT_BUF_SIZE];
         let size = v.encode_zigzag().encode_prefix_varint(&mut buf);
-        self.output.write_all(&buf[..size]).or(Err(Error::Write))
+        self.write(&buf[..size])
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
         let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
         let size = v.encode_zigzag().encode_prefix_varint(&mut buf);
-        self.output.write_all(&buf[..size]).or(Err(Error::Write))
+        self.write(&buf[..size])
     }
 
-    // fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
-    //     let mut buf = [0u8; u128::LEB128_BUF_SIZE];
-    //     let size = v.encode_zigzag().encode_leb128(&mut buf);
-    //     self.output.write_all(&buf[..size]).or(Err(Error::Write))
-    // }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        let mut buf = [0u8; u128::LEB128_BUF_SIZE];
+        let size = v.encode_zigzag().encode_leb128(&mut buf);
+        self.write(&buf[..size])
+    }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        self.output
-            .write_all(&v.to_le_bytes())
-            .or(Err(Error::Write))
+        self.write(&v.to_le_bytes())
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
         let mut buf = [0u8; u16::PREFIX_VARINT_BUF_SIZE];
         let size = v.encode_prefix_varint(&mut buf);
-        self.output.write_all(&buf[..size]).or(Err(Error::Write))
+        self.write(&buf[..size])
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
         let mut buf = [0u8; u32::PREFIX_VARINT_BUF_SIZE];
         let size = v.encode_prefix_varint(&mut buf);
-        self.output.write_all(&buf[..size]).or(Err(Error::Write))
+        self.write(&buf[..size])
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
         let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
         let size = v.encode_prefix_varint(&mut buf);
-        self.output.write_all(&buf[..size]).or(Err(Error::Write))
+        self.write(&buf[..size])
     }
 
-    // fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
-    //     let mut buf = [0u8; u128::LEB128_BUF_SIZE];
-    //     let size = v.encode_leb128(&mut buf);
-    //     self.output.write_all(&buf[..size]).or(Err(Error::Write))
-    // }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        let mut buf = [0u8; u128::LEB128_BUF_SIZE];
+        let size = v.encode_leb128(&mut buf);
+        self.write(&buf[..size])
+    }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        self.output
-            .write_all(&v.to_le_bytes())
-            .or(Err(Error::Write))
+        if self.config.reject_non_finite_floats && !v.is_finite() {
+            return Err(Error::NonFiniteFloat);
+        }
+        if self.config.canonical && v.is_nan() {
+            return self.write(&f32::NAN.to_le_bytes());
+        }
+        self.write(&v.to_le_bytes())
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.output
-            .write_all(&v.to_le_bytes())
-            .or(Err(Error::Write))
+        if self.config.reject_non_finite_floats && !v.is_finite() {
+            return Err(Error::NonFiniteFloat);
+        }
+        if self.config.canonical && v.is_nan() {
+            return self.write(&f64::NAN.to_le_bytes());
+        }
+        self.write(&v.to_le_bytes())
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -139,24 +690,48 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if self.config.string_interning {
+            // Odd tags reference an earlier string; even tags carry the length of a new one.
+            if let Some(index) = self.strings.get(v) {
+                return (index << 1 | 1).serialize(&mut *self);
+            }
+            let index = self.strings.len() as u64;
+            self.strings.insert(v.to_string(), index);
+            ((v.len() as u64) << 1).serialize(&mut *self)?;
+            return self.write(v.as_bytes());
+        }
         (v.len() as u64).serialize(&mut *self)?;
-        self.output.write_all(v.as_bytes()).or(Err(Error::Write))
+        self.write(v.as_bytes())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(name) = self.fixed_width.take() {
+            let size = fixed_width::element_size(name).unwrap();
+            // Written element by element in canonical form so NaNs are replaced; the bytes are the
+            // same as in bulk.
+            if self.config.columnar_arrays
+                || self.config.delta_encode_arrays
+                || self.config.rle_arrays
+                || self.config.canonical
+            {
+                return fixed_width::serialize_elements(name, v, self);
+            }
+            (v.len() / size).serialize(&mut *self)?;
+            return self.write(v);
+        }
         (v.len() as u64).serialize(&mut *self)?;
-        self.output.write_all(v).or(Err(Error::Write))
+        self.write(v)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        self.output.write_all(&[0u8]).or(Err(Error::Write))
+        self.write(&[0u8])
     }
 
     fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: serde::Serialize,
     {
-        self.output.write_all(&[1u8]).or(Err(Error::Write))?;
+        self.write(&[1u8])?;
         value.serialize(self)
     }
 
@@ -174,17 +749,31 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         variant_index: u32,
         _variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        variant_index.serialize(self)
+        self.serialize_variant_index(variant_index)
     }
 
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: serde::Serialize,
     {
+        if fixed_width::element_size(name).is_some() {
+            self.fixed_width = Some(name);
+            let result = value.serialize(&mut *self);
+            self.fixed_width = None;
+            return result;
+        }
+        if name == LENGTH_PREFIXED_NAME {
+            let mut buf = self.buffer();
+            let result = value.serialize(&mut buf);
+            let output = self.unbuffer(buf);
+            result?;
+            (output.len() as u64).serialize(&mut *self)?;
+            return self.write(&output);
+        }
         value.serialize(self)
     }
 
@@ -198,14 +787,28 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     where
         T: serde::Serialize,
     {
-        variant_index.serialize(&mut *self)?;
+        self.serialize_variant_index(variant_index)?;
         value.serialize(self)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        if self.config.delta_encode_arrays && self.config.rle_arrays {
+            return Err(Error::Message(
+                "delta encoding cannot be combined with run-length encoding".to_string(),
+            ));
+        }
         if let Some(len) = len {
             len.serialize(&mut *self)?;
         }
+        if self.config.columnar_arrays {
+            self.columns.push(Vec::new());
+        }
+        if self.config.delta_encode_arrays {
+            self.deltas.push(None);
+        }
+        if self.config.rle_arrays {
+            self.runs.push(None);
+        }
         Ok(self)
     }
 
@@ -228,13 +831,22 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        variant_index.serialize(&mut *self)?;
+        self.serialize_variant_index(variant_index)?;
         Ok(self)
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        if let Some(len) = len {
-            len.serialize(&mut *self)?;
+        self.check_map_sort()?;
+        // Maps of unknown length, such as structs with `#[serde(flatten)]` fields, are buffered
+        // until the length is known.
+        match len {
+            Some(len) if self.config.map_sort.is_none() => {
+                len.serialize(&mut *self)?;
+                self.map_entries.push(None);
+            }
+            _ => self
+                .map_entries
+                .push(Some(Vec::with_capacity(len.unwrap_or(0)))),
         }
         Ok(self)
     }
@@ -243,8 +855,15 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     fn serialize_struct(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
+        if self.config.bitmap_structs {
+            self.struct_fields.push(PresenceBitmap {
+                bits: vec![0; len.div_ceil(8)],
+                index: 0,
+                fields: Vec::new(),
+            });
+        }
         Ok(self)
     }
 
@@ -255,7 +874,7 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        variant_index.serialize(&mut *self)?;
+        self.serialize_variant_index(variant_index)?;
         Ok(self)
     }
 
@@ -274,10 +893,31 @@ impl<'a, W: Write> ser::SerializeSeq for &'a mut Serializer<W> {
     where
         T: serde::Serialize,
     {
-        value.serialize(&mut **self)
+        if self.config.columnar_arrays || self.config.delta_encode_arrays || self.config.rle_arrays
+        {
+            value.serialize(ElementSerializer { ser: &mut **self })
+        } else {
+            value.serialize(&mut **self)
+        }
     }
 
+    // Each column is written as a byte length followed by that field's value for every row.
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.config.delta_encode_arrays {
+            self.deltas.pop();
+        }
+        if self.config.rle_arrays {
+            let run = self.runs.pop().flatten();
+            self.end_run(run)?;
+        }
+        if self.config.columnar_arrays {
+            if let Some(columns) = self.columns.pop() {
+                for column in columns.iter() {
+                    (column.len() as u64).serialize(&mut *self)?;
+                    self.write(column)?;
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -341,25 +981,63 @@ impl<'a, W: Write> ser::SerializeMap for &'a mut Serializer<W> {
     where
         T: serde::Serialize,
     {
-        key.serialize(MapKeySerializer::new(self))
+        if let Some(Some(_)) = self.map_entries.last() {
+            let mut buf = self.buffer();
+            let result = key.serialize(MapKeySerializer::new(&mut buf));
+            let output = self.unbuffer(buf);
+            result?;
+            if let Some(Some(entries)) = self.map_entries.last_mut() {
+                entries.push((output, Vec::new()));
+            }
+            Ok(())
+        } else {
+            key.serialize(MapKeySerializer::new(self))
+        }
     }
 
     fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: serde::Serialize,
     {
-        value.serialize(&mut **self)
+        if let Some(Some(_)) = self.map_entries.last() {
+            let mut buf = self.buffer();
+            let result = value.serialize(&mut buf);
+            let output = self.unbuffer(buf);
+            result?;
+            if let Some((_, v)) = self
+                .map_entries
+                .last_mut()
+                .and_then(|v| v.as_mut())
+                .and_then(|v| v.last_mut())
+            {
+                *v = output;
+            }
+            Ok(())
+        } else {
+            value.serialize(&mut **self)
+        }
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if let Some(Some(entries)) = self.map_entries.pop() {
+            self.write_map_entries(entries)?;
+        }
         Ok(())
     }
 }
 
+fn map_key_str(mut buf: &[u8]) -> &str {
+    // Buffered keys are always produced by `MapKeySerializer`, i.e. a length-prefixed UTF-8 string.
+    let _ = u64::decode_prefix_varint(&mut buf);
+    std::str::from_utf8(buf).unwrap_or_default()
+}
+
 impl<'a, W: Write> ser::SerializeStruct for &'a mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
+    // Struct fields are written positionally, so field names (including
+    // `#[serde(rename)]` / `#[serde(rename_all)]`) never reach the wire.
     fn serialize_field<T: ?Sized>(
         &mut self,
         _key: &'static str,
@@ -368,10 +1046,38 @@ impl<'a, W: Write> ser::SerializeStruct for &'a mut Serializer<W> {
     where
         T: Serialize,
     {
-        value.serialize(&mut **self)
+        if self.config.bitmap_structs {
+            let mut buf = self.buffer();
+            let mut present = true;
+            let result = value.serialize(PresenceSerializer {
+                ser: &mut buf,
+                present: &mut present,
+            });
+            let output = self.unbuffer(buf);
+            result?;
+            if let Some(bitmap) = self.struct_fields.last_mut() {
+                let bits = bitmap.bits.get_mut(bitmap.index / 8).ok_or_else(|| {
+                    Error::Message("struct has more fields than declared".to_string())
+                })?;
+                if present {
+                    *bits |= 1 << (bitmap.index % 8);
+                }
+                bitmap.index += 1;
+                bitmap.fields.extend(output);
+            }
+            Ok(())
+        } else {
+            value.serialize(&mut **self)
+        }
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.config.bitmap_structs {
+            if let Some(bitmap) = self.struct_fields.pop() {
+                self.write(&bitmap.bits)?;
+                self.write(&bitmap.fields)?;
+            }
+        }
         Ok(())
     }
 }
@@ -466,7 +1172,7 @@ impl<'a, W: Write> ser::Serializer for MapKeySerializer<'a, W> {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        self.ser.serialize_str(v)
+        self.ser.serialize_map_key(v)
     }
 
     fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Self::Error> {
@@ -574,91 +1280,373 @@ impl<'a, W: Write> ser::Serializer for MapKeySerializer<'a, W> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Serializer;
-    use crate::{ser::Error, PrefixVarint, ZigZag};
-    use serde::Serialize;
-    use serde_bytes::Bytes;
-    use std::collections::BTreeMap;
-
-    #[test]
-    fn serialize_bool() {
-        {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
+macro_rules! forward_serialize {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                self.ser.$method(v)
+            }
+        )*
+    };
+}
 
-            let body = true;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, [1]);
+// Everything except `Option`, plain structs and integers up to 64 bits is forwarded unchanged by
+// the wrappers below.
+macro_rules! forward_serialize_compound {
+    () => {
+        forward_serialize! {
+            serialize_bool(bool),
+            serialize_i128(i128),
+            serialize_u128(u128),
+            serialize_f32(f32),
+            serialize_f64(f64),
+            serialize_char(char),
+            serialize_str(&str),
+            serialize_bytes(&[u8]),
+            serialize_unit_struct(&'static str),
         }
 
-        {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
+        fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+            self.ser.serialize_unit()
+        }
 
-            let body = false;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, [0]);
+        fn serialize_unit_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+        ) -> Result<Self::Ok, Self::Error> {
+            self.ser
+                .serialize_unit_variant(name, variant_index, variant)
         }
-    }
 
-    #[test]
-    fn serialize_i8() {
+        fn serialize_newtype_struct<T>(
+            self,
+            name: &'static str,
+            value: &T,
+        ) -> Result<Self::Ok, Self::Error>
+        where
+            T: ?Sized + Serialize,
         {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
-            let body = i8::MIN;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, i8::MIN.to_le_bytes());
+            self.ser.serialize_newtype_struct(name, value)
         }
 
+        fn serialize_newtype_variant<T>(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            value: &T,
+        ) -> Result<Self::Ok, Self::Error>
+        where
+            T: ?Sized + Serialize,
         {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
-            let body = 0i8;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, [0]);
+            self.ser
+                .serialize_newtype_variant(name, variant_index, variant, value)
         }
 
-        {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
-            let body = i8::MAX;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, i8::MAX.to_le_bytes());
+        fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+            self.ser.serialize_seq(len)
         }
-    }
 
-    #[test]
-    fn serialize_i16() {
-        {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
-            let body = i16::MIN;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, i16::MIN.encode_zigzag().encode_prefix_varint_vec());
+        fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+            self.ser.serialize_tuple(len)
         }
 
-        {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
-            let body = 0i16;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, [0]);
+        fn serialize_tuple_struct(
+            self,
+            name: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+            self.ser.serialize_tuple_struct(name, len)
         }
 
-        {
-            let mut buf = Vec::new();
-            let mut serializer = Serializer::new(&mut buf);
-            let body = i16::MAX;
-            body.serialize(&mut serializer).unwrap();
-            assert_eq!(buf, i16::MAX.encode_zigzag().encode_prefix_varint_vec());
+        fn serialize_tuple_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+            self.ser
+                .serialize_tuple_variant(name, variant_index, variant, len)
+        }
+
+        fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+            self.ser.serialize_map(len)
+        }
+
+        fn serialize_struct_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeStructVariant, Self::Error> {
+            self.ser
+                .serialize_struct_variant(name, variant_index, variant, len)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+    };
+}
+
+// Writes a struct field in bitmap mode: a top-level `None` only clears the presence bit and a
+// top-level `Some` writes its value without a tag.
+struct PresenceSerializer<'a, W: Write> {
+    ser: &'a mut Serializer<W>,
+    present: &'a mut bool,
+}
+
+impl<'a, W: Write> ser::Serializer for PresenceSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = &'a mut Serializer<W>;
+    type SerializeTuple = &'a mut Serializer<W>;
+    type SerializeTupleStruct = &'a mut Serializer<W>;
+    type SerializeTupleVariant = &'a mut Serializer<W>;
+    type SerializeMap = &'a mut Serializer<W>;
+    type SerializeStruct = &'a mut Serializer<W>;
+    type SerializeStructVariant = &'a mut Serializer<W>;
+
+    forward_serialize_compound!();
+
+    forward_serialize! {
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        *self.present = false;
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self.ser)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.ser.serialize_struct(name, len)
+    }
+}
+
+macro_rules! delta_serialize {
+    ($($method:ident($ty:ty, $wide:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                if self.ser.config.rle_arrays {
+                    return self.ser.serialize_run(v as $wide as u64, |ser| ser.$method(v));
+                }
+                self.ser.serialize_delta(v as $wide as u64, |ser| ser.$method(v))
+            }
+        )*
+    };
+}
+
+// Writes an element of a columnar, delta-encoded or run-length encoded array: the fields of a
+// struct element are appended to the per-field columns of the enclosing array instead of being
+// written in place, and integers after the first are written as the zigzag difference from the
+// previous one or folded into runs of equal values.
+struct ElementSerializer<'a, W: Write> {
+    ser: &'a mut Serializer<W>,
+}
+
+impl<'a, W: Write> ser::Serializer for ElementSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = &'a mut Serializer<W>;
+    type SerializeTuple = &'a mut Serializer<W>;
+    type SerializeTupleStruct = &'a mut Serializer<W>;
+    type SerializeTupleVariant = &'a mut Serializer<W>;
+    type SerializeMap = &'a mut Serializer<W>;
+    type SerializeStruct = ElementStructSerializer<'a, W>;
+    type SerializeStructVariant = &'a mut Serializer<W>;
+
+    forward_serialize_compound!();
+
+    delta_serialize! {
+        serialize_i8(i8, i64),
+        serialize_i16(i16, i64),
+        serialize_i32(i32, i64),
+        serialize_i64(i64, i64),
+        serialize_u8(u8, u64),
+        serialize_u16(u16, u64),
+        serialize_u32(u32, u64),
+        serialize_u64(u64, u64),
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.serialize_none()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.ser.serialize_some(value)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        if !self.ser.config.columnar_arrays {
+            return Ok(ElementStructSerializer {
+                ser: self.ser.serialize_struct(name, len)?,
+                column: None,
+            });
+        }
+        Ok(ElementStructSerializer {
+            ser: self.ser,
+            column: Some(0),
+        })
+    }
+}
+
+struct ElementStructSerializer<'a, W: Write> {
+    ser: &'a mut Serializer<W>,
+    column: Option<usize>,
+}
+
+impl<'a, W: Write> ser::SerializeStruct for ElementStructSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let index = match self.column {
+            Some(index) => index,
+            None => return ser::SerializeStruct::serialize_field(&mut self.ser, key, value),
+        };
+        let mut buf = self.ser.buffer();
+        let result = value.serialize(&mut buf);
+        let output = self.ser.unbuffer(buf);
+        result?;
+        if let Some(columns) = self.ser.columns.last_mut() {
+            if columns.len() <= index {
+                columns.push(Vec::new());
+            }
+            columns[index].extend(output);
+        }
+        self.column = Some(index + 1);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        match self.column {
+            Some(_) => Ok(()),
+            None => ser::SerializeStruct::end(self.ser),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Serializer, SortMode};
+    use crate::{ser::Error, Config, Deserializer, Leb128, PrefixVarint, ZigZag};
+    use serde::{Deserialize, Serialize};
+    use serde_bytes::Bytes;
+    use std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashMap},
+        io::Cursor,
+    };
+
+    #[test]
+    fn serialize_bool() {
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+
+            let body = true;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, [1]);
+        }
+
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+
+            let body = false;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, [0]);
+        }
+    }
+
+    #[test]
+    fn serialize_i8() {
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = i8::MIN;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, i8::MIN.to_le_bytes());
         }
-    }
 
-    #[test]
-    fn serialize_i32() {
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = 0i8;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, [0]);
+        }
+
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = i8::MAX;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, i8::MAX.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn serialize_i16() {
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = i16::MIN;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, i16::MIN.encode_zigzag().encode_prefix_varint_vec());
+        }
+
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = 0i16;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, [0]);
+        }
+
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = i16::MAX;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, i16::MAX.encode_zigzag().encode_prefix_varint_vec());
+        }
+    }
+
+    #[test]
+    fn serialize_i32() {
         {
             let mut buf = Vec::new();
             let mut serializer = Serializer::new(&mut buf);
@@ -711,24 +1699,24 @@ mod tests {
         }
     }
 
-    // #[test]
-    // fn serialize_i128() {
-    //     {
-    //         let mut buf = Vec::new();
-    //         let mut serializer = Serializer::new(&mut buf);
-    //         let body = i128::MIN;
-    //         body.serialize(&mut serializer).unwrap();
-    //         assert_eq!(buf, i128::MIN.encode_zigzag().encode_leb128_vec());
-    //     }
+    #[test]
+    fn serialize_i128() {
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = i128::MIN;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, i128::MIN.encode_zigzag().encode_leb128_vec());
+        }
 
-    //     {
-    //         let mut buf = Vec::new();
-    //         let mut serializer = Serializer::new(&mut buf);
-    //         let body = i128::MAX;
-    //         body.serialize(&mut serializer).unwrap();
-    //         assert_eq!(buf, i128::MAX.encode_zigzag().encode_leb128_vec());
-    //     }
-    // }
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = i128::MAX;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, i128::MAX.encode_zigzag().encode_leb128_vec());
+        }
+    }
 
     #[test]
     fn serialize_u8() {
@@ -806,24 +1794,24 @@ mod tests {
         }
     }
 
-    // #[test]
-    // fn serialize_u128() {
-    //     {
-    //         let mut buf = Vec::new();
-    //         let mut serializer = Serializer::new(&mut buf);
-    //         let body = u128::MIN;
-    //         body.serialize(&mut serializer).unwrap();
-    //         assert_eq!(buf, u128::MIN.encode_leb128_vec());
-    //     }
+    #[test]
+    fn serialize_u128() {
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = u128::MIN;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, u128::MIN.encode_leb128_vec());
+        }
 
-    //     {
-    //         let mut buf = Vec::new();
-    //         let mut serializer = Serializer::new(&mut buf);
-    //         let body = u128::MAX;
-    //         body.serialize(&mut serializer).unwrap();
-    //         assert_eq!(buf, u128::MAX.encode_leb128_vec());
-    //     }
-    // }
+        {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf);
+            let body = u128::MAX;
+            body.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, u128::MAX.encode_leb128_vec());
+        }
+    }
 
     #[test]
     fn serialize_f32() {
@@ -927,6 +1915,116 @@ mod tests {
         }
     }
 
+    #[test]
+    fn serialize_float_slice() {
+        let f32s = [0f32, -1.5, f32::MAX, f32::MIN_POSITIVE, f32::INFINITY];
+        let f64s = (0..1000).map(|v| v as f64 / 7.0).collect::<Vec<_>>();
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        serializer.serialize_f32_slice(&f32s).unwrap();
+        serializer.serialize_f64_slice(&f64s).unwrap();
+        serializer.serialize_f64_slice(&[]).unwrap();
+        assert_eq!(serializer.written(), buf.len());
+
+        let mut expected = Vec::new();
+        (&f32s[..], &f64s, Vec::<f64>::new())
+            .serialize(&mut Serializer::new(&mut expected))
+            .unwrap();
+        assert_eq!(buf, expected);
+
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            Vec::<f32>::deserialize(&mut deserializer),
+            Ok(f32s.to_vec())
+        );
+        assert_eq!(Vec::<f64>::deserialize(&mut deserializer), Ok(f64s));
+        assert_eq!(Vec::<f64>::deserialize(&mut deserializer), Ok(vec![]));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).reject_non_finite_floats(true);
+        assert_eq!(
+            serializer.serialize_f32_slice(&f32s),
+            Err(Error::NonFiniteFloat)
+        );
+        assert_eq!(
+            serializer.serialize_f64_slice(&[1.0, f64::NAN]),
+            Err(Error::NonFiniteFloat)
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn serialize_u32_slice() {
+        let v = [0u32, 1, 1, 1, 127, 128, 300, 300, u32::MAX]
+            .iter()
+            .cycle()
+            .take(1000)
+            .copied()
+            .collect::<Vec<_>>();
+        for config in [
+            Config::default(),
+            Serializer::new(Vec::new())
+                .delta_encode_arrays(true)
+                .config(),
+            Serializer::new(Vec::new()).rle_arrays(true).config(),
+            Serializer::new(Vec::new()).columnar_arrays(true).config(),
+        ] {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::with_config(&mut buf, config);
+            serializer.serialize_u32_slice(&v).unwrap();
+            serializer.serialize_u32_slice(&[]).unwrap();
+            assert_eq!(serializer.written(), buf.len());
+
+            let mut expected = Vec::new();
+            (&v, Vec::<u32>::new())
+                .serialize(&mut Serializer::with_config(&mut expected, config))
+                .unwrap();
+            assert_eq!(buf, expected);
+
+            let mut reader = buf.as_slice();
+            let mut deserializer = Deserializer::with_config(&mut reader, config);
+            assert_eq!(Vec::<u32>::deserialize(&mut deserializer), Ok(v.clone()));
+            assert_eq!(Vec::<u32>::deserialize(&mut deserializer), Ok(vec![]));
+            assert_eq!(deserializer.end(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn serialize_reject_non_finite_floats() {
+        for v in [0f32, -1.1, f32::MAX, f32::MIN_POSITIVE] {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf).reject_non_finite_floats(true);
+            v.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, v.to_le_bytes());
+        }
+        for v in [0f64, -1.1, f64::MAX, f64::MIN_POSITIVE] {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf).reject_non_finite_floats(true);
+            v.serialize(&mut serializer).unwrap();
+            assert_eq!(buf, v.to_le_bytes());
+        }
+
+        for v in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf).reject_non_finite_floats(true);
+            assert_eq!(v.serialize(&mut serializer), Err(Error::NonFiniteFloat));
+            assert!(buf.is_empty());
+            v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        }
+        for v in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let mut buf = Vec::new();
+            let mut serializer = Serializer::new(&mut buf).reject_non_finite_floats(true);
+            assert_eq!(
+                (1u8, vec![v]).serialize(&mut serializer),
+                Err(Error::NonFiniteFloat)
+            );
+            v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        }
+    }
+
     #[test]
     fn serialize_char() {
         {
@@ -999,6 +2097,109 @@ mod tests {
         }
     }
 
+    #[test]
+    fn serialize_collect_str() {
+        struct Decimal(i64, u32);
+
+        impl std::fmt::Display for Decimal {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}.{:02}", self.0, self.1)
+            }
+        }
+
+        impl Serialize for Decimal {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        let mut buf = Vec::new();
+        Decimal(-12, 5)
+            .serialize(&mut Serializer::new(&mut buf))
+            .unwrap();
+        assert_eq!(buf, [6, b'-', b'1', b'2', b'.', b'0', b'5']);
+
+        #[derive(Serialize)]
+        struct Price {
+            amount: Decimal,
+            previous: Option<Decimal>,
+        }
+
+        let body = vec![
+            Price {
+                amount: Decimal(1, 50),
+                previous: Some(Decimal(1, 50)),
+            },
+            Price {
+                amount: Decimal(1, 50),
+                previous: None,
+            },
+        ];
+        #[derive(Serialize)]
+        struct PriceStr {
+            amount: &'static str,
+            previous: Option<&'static str>,
+        }
+
+        let expected = vec![
+            PriceStr {
+                amount: "1.50",
+                previous: Some("1.50"),
+            },
+            PriceStr {
+                amount: "1.50",
+                previous: None,
+            },
+        ];
+        for config in [
+            Config::default(),
+            Serializer::new(Vec::new()).string_interning(true).config(),
+            Serializer::new(Vec::new()).bitmap_structs(true).config(),
+            Serializer::new(Vec::new()).columnar_arrays(true).config(),
+        ] {
+            let mut buf = Vec::new();
+            body.serialize(&mut Serializer::with_config(&mut buf, config))
+                .unwrap();
+            let mut plain = Vec::new();
+            expected
+                .serialize(&mut Serializer::with_config(&mut plain, config))
+                .unwrap();
+            assert_eq!(buf, plain);
+        }
+    }
+
+    #[test]
+    fn serialize_binary_streaming() {
+        let data = (0..20_000u32).map(|v| v as u8).collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        serializer
+            .serialize_binary_streaming(data.len() as u64, Cursor::new(&data))
+            .unwrap();
+        assert_eq!(serializer.written(), buf.len());
+        let mut expected = Vec::new();
+        Bytes::new(&data)
+            .serialize(&mut Serializer::new(&mut expected))
+            .unwrap();
+        assert_eq!(buf, expected);
+
+        // Only `len` bytes are taken from a longer source.
+        let mut buf = Vec::new();
+        Serializer::new(&mut buf)
+            .serialize_binary_streaming(3, Cursor::new(&data))
+            .unwrap();
+        assert_eq!(buf, [3, 0, 1, 2]);
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            Serializer::new(&mut buf).serialize_binary_streaming(5, Cursor::new([1u8, 2])),
+            Err(Error::Source {
+                read: 2,
+                expected: 5
+            })
+        );
+    }
+
     #[test]
     fn serialize_none() {
         let mut buf = Vec::new();
@@ -1023,7 +2224,7 @@ mod tests {
         let mut serializer = Serializer::new(&mut buf);
         let body = ();
         body.serialize(&mut serializer).unwrap();
-        assert_eq!(buf, []);
+        assert!(buf.is_empty());
     }
 
     #[test]
@@ -1035,7 +2236,7 @@ mod tests {
         let mut serializer = Serializer::new(&mut buf);
         let body = Test;
         body.serialize(&mut serializer).unwrap();
-        assert_eq!(buf, []);
+        assert!(buf.is_empty());
     }
 
     #[test]
@@ -1273,6 +2474,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_struct_rename_all() {
+        #[derive(Serialize)]
+        struct Test {
+            first_field: bool,
+            second_field: u8,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RenamedTest {
+            first_field: bool,
+            #[serde(rename = "other")]
+            second_field: u8,
+        }
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        Test {
+            first_field: true,
+            second_field: 123,
+        }
+        .serialize(&mut serializer)
+        .unwrap();
+
+        let mut renamed_buf = Vec::new();
+        let mut serializer = Serializer::new(&mut renamed_buf);
+        RenamedTest {
+            first_field: true,
+            second_field: 123,
+        }
+        .serialize(&mut serializer)
+        .unwrap();
+
+        assert_eq!(buf, [1, 123]);
+        assert_eq!(buf, renamed_buf);
+    }
+
     #[test]
     fn serialize_struct_variant() {
         #[allow(dead_code)]
@@ -1353,4 +2592,447 @@ mod tests {
         body.serialize(&mut serializer).unwrap();
         assert_eq!(buf, [5, 0, 1, 2, 3, 255]);
     }
+
+    #[test]
+    fn serialize_write_error_reports_written() {
+        struct LimitedWriter {
+            buf: Vec<u8>,
+            limit: usize,
+        }
+
+        impl std::io::Write for LimitedWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(self.limit - self.buf.len());
+                if n == 0 {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::BrokenPipe,
+                        "limit reached",
+                    ));
+                }
+                self.buf.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = LimitedWriter {
+            buf: Vec::new(),
+            limit: 5,
+        };
+        let mut serializer = Serializer::new(&mut writer);
+        let body = (true, "test".to_string(), 123u8);
+
+        assert_eq!(
+            body.serialize(&mut serializer),
+            Err(Error::Write { written: 5 })
+        );
+        assert_eq!(serializer.written(), 5);
+        assert_eq!(writer.buf, [1, 4, b't', b'e', b's']);
+
+        // The header of a self-describing value counts towards the bytes written too.
+        let mut writer = LimitedWriter {
+            buf: Vec::new(),
+            limit: 3,
+        };
+        let mut serializer = Serializer::new(&mut writer);
+        assert_eq!(
+            serializer.serialize_self_describing(&body),
+            Err(Error::Write { written: 3 })
+        );
+    }
+
+    #[test]
+    fn serialize_written() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        (true, "test".to_string(), 123u8)
+            .serialize(&mut serializer)
+            .unwrap();
+        assert_eq!(serializer.written(), 7);
+        assert_eq!(buf.len(), 7);
+    }
+
+    #[test]
+    fn serialize_start_map() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        let mut map = serializer.start_map();
+        for i in (0..100u32).filter(|v| v % 7 == 3) {
+            map.entry(&i.to_string(), &(i * 2)).unwrap();
+        }
+        assert_eq!(map.len(), 14);
+        map.finish().unwrap();
+        true.serialize(&mut serializer).unwrap();
+
+        let expected = (0..100u32)
+            .filter(|v| v % 7 == 3)
+            .map(|v| (v.to_string(), v * 2))
+            .collect::<HashMap<_, _>>();
+        let mut reader = buf.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(
+            HashMap::<String, u32>::deserialize(&mut deserializer).unwrap(),
+            expected
+        );
+        assert!(bool::deserialize(&mut deserializer).unwrap());
+        assert_eq!(deserializer.end(), Ok(()));
+    }
+
+    #[test]
+    fn serialize_start_map_sorted() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).map_sort(SortMode::Bytes);
+        let mut map = serializer.start_map();
+        assert!(map.is_empty());
+        map.entry("b", &2u8).unwrap();
+        map.entry("a", &1u8).unwrap();
+        map.finish().unwrap();
+        assert_eq!(buf, [2, 1, b'a', 1, 1, b'b', 2]);
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        let mut map = serializer.start_map();
+        assert_eq!(map.entry(&1u8, &1u8), Err(Error::UnsupportedKeyType));
+        map.finish().unwrap();
+        assert_eq!(buf, [0]);
+    }
+
+    #[test]
+    fn serialize_bitmap_structs() {
+        #[derive(Serialize)]
+        struct Inner {
+            a: Option<u8>,
+            b: u8,
+        }
+
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u8>,
+            b: Inner,
+            c: Option<Option<u8>>,
+            d: Vec<Option<u8>>,
+        }
+
+        let body = Test {
+            a: None,
+            b: Inner { a: Some(1), b: 2 },
+            c: Some(None),
+            d: vec![None, Some(3)],
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf).bitmap_structs(true))
+            .unwrap();
+        assert_eq!(buf, [0b1110, 0b11, 1, 2, 0, 2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn serialize_columnar_arrays() {
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: bool,
+        }
+
+        let body = vec![Point { x: 1, y: true }, Point { x: 2, y: false }];
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf).columnar_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [2, 2, 1, 2, 2, 1, 0]);
+
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        assert_eq!(buf, [2, 1, 1, 2, 0]);
+
+        let mut buf = Vec::new();
+        vec![(1u8, true)]
+            .serialize(&mut Serializer::new(&mut buf).columnar_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [1, 1, 1]);
+    }
+
+    #[test]
+    fn serialize_delta_encode_arrays() {
+        let mut buf = Vec::new();
+        vec![10u32, 12, 11]
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [3, 10, 4, 1]);
+
+        let mut buf = Vec::new();
+        vec![-1i8, 127, -128]
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [3, 0xff, 0x80, 0x04, 0xbd, 0x07]);
+
+        let mut buf = Vec::new();
+        vec![(1u8, 2u8), (3, 4)]
+            .serialize(&mut Serializer::new(&mut buf).delta_encode_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [2, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn serialize_rle_arrays() {
+        let mut buf = Vec::new();
+        vec![7u8, 7, 7, 300u16 as u8, 7]
+            .serialize(&mut Serializer::new(&mut buf).rle_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [5, 7, 3, 44, 1, 7, 1]);
+
+        let mut buf = Vec::new();
+        vec![-1i32; 1000]
+            .serialize(&mut Serializer::new(&mut buf).rle_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [168, 15, 1, 168, 15]);
+
+        let mut buf = Vec::new();
+        vec![vec![0u16, 0], vec![]]
+            .serialize(&mut Serializer::new(&mut buf).rle_arrays(true))
+            .unwrap();
+        assert_eq!(buf, [2, 2, 0, 2, 0]);
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            vec![1u8].serialize(
+                &mut Serializer::new(&mut buf)
+                    .rle_arrays(true)
+                    .delta_encode_arrays(true)
+            ),
+            Err(Error::Message(
+                "delta encoding cannot be combined with run-length encoding".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn serialize_map_key_cache() {
+        let long_key = "k".repeat(super::MAX_CACHED_KEY_LEN + 1);
+        let records = (0..10_000u64)
+            .map(|i| {
+                BTreeMap::from([
+                    ("id", i),
+                    ("count", i % 7),
+                    ("timestamp", i * 1000),
+                    (long_key.as_str(), i),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        // The same records written one per serializer, so no key is ever found in the cache.
+        let mut naive = Vec::new();
+        records
+            .len()
+            .serialize(&mut Serializer::new(&mut naive))
+            .unwrap();
+        for record in records.iter() {
+            record.serialize(&mut Serializer::new(&mut naive)).unwrap();
+        }
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        records.serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.keys.len(), 3);
+        assert_eq!(buf, naive);
+
+        let mut sorted = Vec::new();
+        let mut serializer = Serializer::new(&mut sorted).map_sort(SortMode::Natural);
+        records.serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.keys.len(), 3);
+        assert_eq!(sorted, naive);
+
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            Vec::<BTreeMap<String, u64>>::deserialize(&mut Deserializer::new(&mut reader))
+                .map(|v| v.len()),
+            Ok(records.len())
+        );
+    }
+
+    #[test]
+    fn serialize_string_interning() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).string_interning(true);
+        vec!["abc"; 3].serialize(&mut serializer).unwrap();
+        assert_eq!(buf, [3, 6, b'a', b'b', b'c', 1, 1]);
+
+        let mut plain = Vec::new();
+        vec!["abc"; 3]
+            .serialize(&mut Serializer::new(&mut plain))
+            .unwrap();
+        assert!(buf.len() < plain.len());
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).string_interning(true);
+        let body = vec![BTreeMap::from([("a", "b")]), BTreeMap::from([("a", "a")])];
+        body.serialize(&mut serializer).unwrap();
+        assert_eq!(buf, [2, 1, 2, b'a', 2, b'b', 1, 1, 1]);
+    }
+
+    #[test]
+    fn serialize_string_interning_map_sort() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf)
+            .string_interning(true)
+            .map_sort(SortMode::Bytes);
+        assert_eq!(
+            BTreeMap::from([("a", 1u8)]).serialize(&mut serializer),
+            Err(Error::Message(
+                "string interning cannot be combined with map sorting".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn serialize_map_sort_custom() {
+        fn case_insensitive(a: &str, b: &str) -> Ordering {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        }
+
+        let body = {
+            let mut map = BTreeMap::new();
+            map.insert("b".to_string(), 1u8);
+            map.insert("A".to_string(), 2u8);
+            map.insert("c".to_string(), 3u8);
+            map.insert("B".to_string(), 4u8);
+            map
+        };
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).map_sort(SortMode::Custom(case_insensitive));
+        body.serialize(&mut serializer).unwrap();
+        assert_eq!(buf, [4, 1, b'A', 2, 1, b'B', 4, 1, b'b', 1, 1, b'c', 3]);
+
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            BTreeMap::<String, u8>::deserialize(&mut Deserializer::new(&mut reader)).unwrap(),
+            body
+        );
+    }
+
+    #[test]
+    fn serialize_map_sort_natural() {
+        let body = {
+            let mut map = HashMap::new();
+            map.insert("a10".to_string(), vec![()]);
+            map.insert("a2".to_string(), vec![]);
+            map.insert("a1".to_string(), vec![(), ()]);
+            map
+        };
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).map_sort(SortMode::Natural);
+        body.serialize(&mut serializer).unwrap();
+        assert_eq!(
+            buf,
+            [&[3, 2][..], b"a1", &[2, 2], b"a2", &[0, 3], b"a10", &[1]].concat()
+        );
+    }
+
+    #[test]
+    fn serialize_map_sort_bytes_nested() {
+        let body = {
+            let mut inner = HashMap::new();
+            inner.insert("y".to_string(), true);
+            inner.insert("x".to_string(), false);
+            let mut map = HashMap::new();
+            map.insert("b".to_string(), inner.clone());
+            map.insert("a".to_string(), inner);
+            map
+        };
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf).map_sort(SortMode::Bytes);
+        body.serialize(&mut serializer).unwrap();
+        assert_eq!(
+            buf,
+            [2, 1, b'a', 2, 1, b'x', 0, 1, b'y', 1, 1, b'b', 2, 1, b'x', 0, 1, b'y', 1]
+        );
+    }
+
+    #[test]
+    fn serialize_segmented_array() {
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        let mut array = serializer.start_segmented_array(3);
+        for v in 1..=7u8 {
+            array.element(&v).unwrap();
+        }
+        array.finish().unwrap();
+        assert_eq!(buf, [3, 1, 2, 3, 3, 4, 5, 6, 1, 7, 0]);
+
+        let mut buf = Vec::new();
+        let mut serializer = Serializer::new(&mut buf);
+        let mut array = serializer.start_segmented_array(100);
+        array.element("a").unwrap();
+        array.end_chunk().unwrap();
+        array.end_chunk().unwrap();
+        array.element("b").unwrap();
+        array.finish().unwrap();
+        assert_eq!(buf, [1, 1, b'a', 1, 1, b'b', 0]);
+
+        let mut buf = Vec::new();
+        Serializer::new(&mut buf)
+            .start_segmented_array(3)
+            .finish()
+            .unwrap();
+        assert_eq!(buf, [0]);
+    }
+
+    #[test]
+    fn serialize_canonical() {
+        fn canonical<T: Serialize>(v: &T) -> Vec<u8> {
+            let mut buf = Vec::new();
+            v.serialize(&mut Serializer::new(&mut buf).canonical())
+                .unwrap();
+            buf
+        }
+
+        let keys = (0..32).map(|i| format!("key{}", i)).collect::<Vec<_>>();
+        let forward = keys.iter().cloned().zip(0u8..).collect::<HashMap<_, _>>();
+        let mut backward = HashMap::with_capacity(256);
+        for (key, v) in keys.iter().cloned().zip(0u8..32).rev() {
+            backward.insert(key, v);
+        }
+        assert_eq!(canonical(&forward), canonical(&backward));
+        assert_eq!(
+            canonical(&forward),
+            canonical(&forward.into_iter().collect::<BTreeMap<_, _>>())
+        );
+
+        // 1.20 and 1.2 as decoded, without normalization.
+        let decimals = [(vec![120u8], 2i64), (vec![12u8], 1i64)]
+            .iter()
+            .map(|v| {
+                let mut buf = Vec::new();
+                v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                crate::BigDecimal::deserialize(&mut Deserializer::new(&mut buf.as_slice())).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_ne!(decimals[0], decimals[1]);
+        assert_eq!(canonical(&decimals[0]), canonical(&decimals[1]));
+
+        let nan = f64::from_bits(0xfff0_0000_0000_0001);
+        assert_eq!(canonical(&nan), f64::NAN.to_le_bytes());
+        assert_eq!(
+            canonical(&(-f32::NAN, vec![f32::from_bits(0x7f80_0001)])),
+            [0, 0, 0xc0, 0x7f, 1, 0, 0, 0xc0, 0x7f]
+        );
+        let mut buf = Vec::new();
+        Serializer::new(&mut buf)
+            .canonical()
+            .serialize_f64_slice(&[nan])
+            .unwrap();
+        assert_eq!(buf[1..], f64::NAN.to_le_bytes());
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            serde::Serializer::collect_map(
+                &mut Serializer::new(&mut buf).canonical(),
+                [("a", 1u8), ("a", 2)]
+            ),
+            Err(Error::Message(
+                "duplicate map key in canonical form".to_string()
+            ))
+        );
+    }
 }
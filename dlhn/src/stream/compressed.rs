@@ -0,0 +1,222 @@
+use super::Error;
+use crate::{Deserializer, PrefixVarint, Serializer};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    None,
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Codec {
+    pub fn id(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => 1,
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => 2,
+        }
+    }
+
+    fn compress(self, body: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self {
+            Codec::None => Ok(body),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => zstd::stream::encode_all(body.as_slice(), 0).or(Err(Error::Write)),
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(&body).or(Err(Error::Write))?;
+                encoder.finish().or(Err(Error::Write))
+            }
+        }
+    }
+
+    // Decompresses through the streaming decoders, reading at most one byte past `max_len`, so
+    // neither a forged size nor a highly compressible body can force a large allocation.
+    fn decompress(self, body: Vec<u8>, max_len: u64) -> Result<Vec<u8>, Error> {
+        let buf = match self {
+            Codec::None => body,
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => {
+                let mut buf = Vec::new();
+                zstd::stream::read::Decoder::new(body.as_slice())
+                    .and_then(|v| v.take(max_len.saturating_add(1)).read_to_end(&mut buf))
+                    .or(Err(Error::Read))?;
+                buf
+            }
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => {
+                let mut buf = Vec::new();
+                lz4_flex::frame::FrameDecoder::new(body.as_slice())
+                    .take(max_len.saturating_add(1))
+                    .read_to_end(&mut buf)
+                    .or(Err(Error::Read))?;
+                buf
+            }
+        };
+        if buf.len() as u64 > max_len {
+            return Err(Error::DecompressedLengthLimit);
+        }
+        Ok(buf)
+    }
+}
+
+impl TryFrom<u8> for Codec {
+    type Error = Error;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        match id {
+            0 => Ok(Codec::None),
+            #[cfg(feature = "zstd")]
+            1 => Ok(Codec::Zstd),
+            #[cfg(feature = "lz4")]
+            2 => Ok(Codec::Lz4),
+            id => Err(Error::Codec(id)),
+        }
+    }
+}
+
+// Writes the codec id, the compressed length and the compressed DLHN body.
+pub fn write<W: Write, T: Serialize>(writer: &mut W, value: &T, codec: Codec) -> Result<(), Error> {
+    let mut body = Vec::new();
+    value
+        .serialize(&mut Serializer::new(&mut body))
+        .map_err(Error::Serialize)?;
+    let body = codec.compress(body)?;
+
+    let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
+    let size = (body.len() as u64).encode_prefix_varint(&mut buf);
+    writer.write_all(&[codec.id()]).or(Err(Error::Write))?;
+    writer.write_all(&buf[..size]).or(Err(Error::Write))?;
+    writer.write_all(&body).or(Err(Error::Write))
+}
+
+// The decompressed body `read` accepts, the same as the default cap on a single string.
+pub const MAX_DECOMPRESSED_LEN: u64 = 16 << 20;
+
+pub fn read<R: Read, T: DeserializeOwned>(reader: &mut R) -> Result<T, Error> {
+    read_with_limit(reader, MAX_DECOMPRESSED_LEN)
+}
+
+// Like `read`, but fails with `Error::DecompressedLengthLimit` once the body decompresses to
+// more than `max_len` bytes.
+pub fn read_with_limit<R: Read, T: DeserializeOwned>(
+    reader: &mut R,
+    max_len: u64,
+) -> Result<T, Error> {
+    let mut id = [0u8; 1];
+    reader.read_exact(&mut id).or(Err(Error::Read))?;
+    let codec = Codec::try_from(id[0])?;
+    let len = u64::decode_prefix_varint(reader).or(Err(Error::Read))?;
+
+    let mut body = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut body)
+        .or(Err(Error::Read))?;
+    if body.len() as u64 != len {
+        return Err(Error::Read);
+    }
+    let body = codec.decompress(body, max_len)?;
+
+    let mut reader = body.as_slice();
+    let mut deserializer = Deserializer::new(&mut reader);
+    let value = T::deserialize(&mut deserializer).map_err(Error::Deserialize)?;
+    deserializer.end().map_err(Error::Deserialize)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, read_with_limit, write, Codec};
+    use crate::{stream::Error, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Log {
+        level: String,
+        message: String,
+        count: u32,
+    }
+
+    fn logs() -> Vec<Log> {
+        (0..200)
+            .map(|i| Log {
+                level: "info".to_string(),
+                message: "request handled successfully".to_string(),
+                count: i % 4,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compressed_roundtrip() {
+        let body = logs();
+        let mut plain = Vec::new();
+        body.serialize(&mut Serializer::new(&mut plain)).unwrap();
+
+        let mut codecs = vec![Codec::None];
+        #[cfg(feature = "zstd")]
+        codecs.push(Codec::Zstd);
+        #[cfg(feature = "lz4")]
+        codecs.push(Codec::Lz4);
+        for codec in codecs {
+            let mut buf = Vec::new();
+            write(&mut buf, &body, codec).unwrap();
+            assert_eq!(buf[0], codec.id());
+            if codec == Codec::None {
+                assert_eq!(buf[buf.len() - plain.len()..], plain);
+            } else {
+                assert!(buf.len() < plain.len() / 4);
+            }
+            assert_eq!(read::<_, Vec<Log>>(&mut buf.as_slice()), Ok(logs()));
+            assert_eq!(
+                read::<_, Vec<Log>>(&mut &buf[..buf.len() - 1]),
+                Err(Error::Read)
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_unknown_codec() {
+        let mut buf = Vec::new();
+        write(&mut buf, &true, Codec::None).unwrap();
+        assert_eq!(buf, [0, 1, 1]);
+        buf[0] = 255;
+        assert_eq!(read::<_, bool>(&mut buf.as_slice()), Err(Error::Codec(255)));
+    }
+
+    #[test]
+    fn compressed_length_limit() {
+        let body = serde_bytes::ByteBuf::from(vec![0u8; 1 << 20]);
+        let mut plain = Vec::new();
+        body.serialize(&mut Serializer::new(&mut plain)).unwrap();
+        let len = plain.len() as u64;
+        let mut codecs = vec![Codec::None];
+        #[cfg(feature = "zstd")]
+        codecs.push(Codec::Zstd);
+        #[cfg(feature = "lz4")]
+        codecs.push(Codec::Lz4);
+        for codec in codecs {
+            let mut buf = Vec::new();
+            write(&mut buf, &body, codec).unwrap();
+            assert!(
+                read_with_limit::<_, serde_bytes::ByteBuf>(&mut buf.as_slice(), len)
+                    .is_ok_and(|v| v == body)
+            );
+            assert_eq!(
+                read_with_limit::<_, serde_bytes::ByteBuf>(&mut buf.as_slice(), len - 1),
+                Err(Error::DecompressedLengthLimit)
+            );
+        }
+    }
+}
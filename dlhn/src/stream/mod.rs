@@ -0,0 +1,893 @@
+use crate::{de, ser, Config, Deserializer, PrefixVarint, Serializer};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    cmp::min,
+    convert::TryFrom,
+    fmt::{self, Display},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+};
+
+#[cfg(feature = "compression")]
+pub mod compressed;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Read,
+    Write,
+    Serialize(ser::Error),
+    Deserialize(de::Error),
+    Checksum { offset: u64 },
+    Codec(u8),
+    Truncated { buffered: usize },
+    DecompressedLengthLimit,
+    TruncatedRecord { offset: u64 },
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read => formatter.write_str("read error"),
+            Error::Write => formatter.write_str("write error"),
+            Error::Serialize(e) => Display::fmt(e, formatter),
+            Error::Deserialize(e) => Display::fmt(e, formatter),
+            Error::Checksum { offset } => {
+                write!(formatter, "checksum mismatch at offset {}", offset)
+            }
+            Error::Codec(id) => write!(formatter, "unknown compression codec: {}", id),
+            Error::Truncated { buffered } => write!(
+                formatter,
+                "stream ended inside a value with {} bytes buffered",
+                buffered
+            ),
+            Error::DecompressedLengthLimit => {
+                formatter.write_str("decompressed length limit exceeded")
+            }
+            Error::TruncatedRecord { offset } => {
+                write!(formatter, "record at offset {} is truncated", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct LogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LogWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_record<T: Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let mut body = Vec::new();
+        value
+            .serialize(&mut Serializer::new(&mut body))
+            .map_err(Error::Serialize)?;
+
+        let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
+        let size = (body.len() as u64).encode_prefix_varint(&mut buf);
+        self.writer.write_all(&buf[..size]).or(Err(Error::Write))?;
+        self.writer.write_all(&body).or(Err(Error::Write))?;
+        self.writer
+            .write_all(&crc32fast::hash(&body).to_le_bytes())
+            .or(Err(Error::Write))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct LogReader<R: Read> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: Read> LogReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, offset: 0 }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn read_record<T>(&mut self) -> Result<Option<T>, Error>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let offset = self.offset;
+        let truncated = |e: io::Error| match e.kind() {
+            ErrorKind::UnexpectedEof => Error::TruncatedRecord { offset },
+            _ => Error::Read,
+        };
+
+        // The end of the stream is only clean before the first byte of a record.
+        let mut first = [0u8; 1];
+        loop {
+            match self.reader.read(&mut first) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return Err(Error::Read),
+            }
+        }
+        let len = u64::decode_prefix_varint(&mut first.as_ref().chain(&mut self.reader))
+            .map_err(truncated)?;
+
+        let mut body = Vec::new();
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut body)
+            .or(Err(Error::Read))?;
+        if body.len() as u64 != len {
+            return Err(Error::TruncatedRecord { offset });
+        }
+        let mut crc = [0u8; 4];
+        self.reader.read_exact(&mut crc).map_err(truncated)?;
+
+        let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
+        self.offset += (len.encode_prefix_varint(&mut buf) + body.len() + crc.len()) as u64;
+        if crc32fast::hash(&body) != u32::from_le_bytes(crc) {
+            return Err(Error::Checksum { offset });
+        }
+
+        let mut reader = body.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let value = T::deserialize(&mut deserializer).map_err(Error::Deserialize)?;
+        deserializer.end().map_err(Error::Deserialize)?;
+        Ok(Some(value))
+    }
+}
+
+// Writes each record as `body || u32_le(len)`, so a reader can walk the records backward from
+// the end of the stream.
+pub struct FooterWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> FooterWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_record<T: Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let mut body = Vec::new();
+        value
+            .serialize(&mut Serializer::new(&mut body))
+            .map_err(Error::Serialize)?;
+        let len = u32::try_from(body.len()).or(Err(Error::Write))?;
+        self.writer.write_all(&body).or(Err(Error::Write))?;
+        self.writer
+            .write_all(&len.to_le_bytes())
+            .or(Err(Error::Write))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct FooterReader<R: Read + Seek> {
+    reader: R,
+    end: Option<u64>,
+}
+
+impl<R: Read + Seek> FooterReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, end: None }
+    }
+
+    // Reads the record ending where the previous one started, beginning at the end of the
+    // stream. Returns `None` once the start of the stream is reached.
+    pub fn read_last<T: DeserializeOwned>(&mut self) -> Result<Option<T>, Error> {
+        let end = match self.end {
+            Some(end) => end,
+            None => self.reader.seek(SeekFrom::End(0)).or(Err(Error::Read))?,
+        };
+        if end == 0 {
+            return Ok(None);
+        }
+        let mut footer = [0u8; 4];
+        let start = end.checked_sub(footer.len() as u64).ok_or(Error::Read)?;
+        self.reader
+            .seek(SeekFrom::Start(start))
+            .or(Err(Error::Read))?;
+        self.reader.read_exact(&mut footer).or(Err(Error::Read))?;
+        let len = u32::from_le_bytes(footer) as u64;
+        let start = start.checked_sub(len).ok_or(Error::Read)?;
+
+        self.reader
+            .seek(SeekFrom::Start(start))
+            .or(Err(Error::Read))?;
+        let mut body = Vec::new();
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut body)
+            .or(Err(Error::Read))?;
+        if body.len() as u64 != len {
+            return Err(Error::Read);
+        }
+        self.end = Some(start);
+
+        let mut reader = body.as_slice();
+        let mut deserializer = Deserializer::new(&mut reader);
+        let value = T::deserialize(&mut deserializer).map_err(Error::Deserialize)?;
+        deserializer.end().map_err(Error::Deserialize)?;
+        Ok(Some(value))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+// What `StreamDecoder::decode` got from the reader.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decoded<T> {
+    Value(T),
+    // The reader returned `WouldBlock` before a whole value arrived; call `decode` again later.
+    Pending,
+    // The stream ended cleanly between values.
+    Eof,
+}
+
+// Keeps the bytes of an incomplete value between calls, so a reader that returns `WouldBlock`
+// mid-value does not lose data. Each attempt restarts decoding from the first buffered byte.
+pub struct StreamDecoder<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    config: Config,
+}
+
+impl<R: Read> StreamDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_config(reader, Config::default())
+    }
+
+    pub fn with_config(reader: R, config: Config) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn decode<T: DeserializeOwned>(&mut self) -> Result<Decoded<T>, Error> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Some(value) = self.decode_buffered()? {
+                return Ok(Decoded::Value(value));
+            }
+            match self.reader.read(&mut chunk) {
+                // The end of the stream is only clean between values.
+                Ok(0) if self.buf.is_empty() => return Ok(Decoded::Eof),
+                Ok(0) => {
+                    return Err(Error::Truncated {
+                        buffered: self.buf.len(),
+                    })
+                }
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(Decoded::Pending),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return Err(Error::Read),
+            }
+        }
+    }
+
+    fn decode_buffered<T: DeserializeOwned>(&mut self) -> Result<Option<T>, Error> {
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let mut reader = self.buf.as_slice();
+        match T::deserialize(&mut Deserializer::with_config(&mut reader, self.config)) {
+            Ok(value) => {
+                let consumed = self.buf.len() - reader.len();
+                self.buf.drain(..consumed);
+                Ok(Some(value))
+            }
+            // Running out of buffered bytes only means the rest has not arrived yet.
+            Err(de::Error::Read { .. } | de::Error::TruncatedVarint { .. })
+                if reader.is_empty() =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(Error::Deserialize(e)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TeeSink {
+    First,
+    Second,
+}
+
+impl Display for TeeSink {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TeeSink::First => formatter.write_str("first sink"),
+            TeeSink::Second => formatter.write_str("second sink"),
+        }
+    }
+}
+
+// Writes every byte to both sinks. Once a sink fails the two may have diverged, so every later
+// write fails as well and `failed` reports which sink it was.
+pub struct TeeWriter<A: Write, B: Write> {
+    first: A,
+    second: B,
+    failed: Option<TeeSink>,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            failed: None,
+        }
+    }
+
+    pub fn failed(&self) -> Option<TeeSink> {
+        self.failed
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    fn check(&mut self, sink: TeeSink, result: io::Result<()>) -> io::Result<()> {
+        result.map_err(|e| {
+            self.failed = Some(sink);
+            io::Error::new(e.kind(), format!("{}: {}", sink, e))
+        })
+    }
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(sink) = self.failed {
+            return Err(io::Error::other(format!("{} failed earlier", sink)));
+        }
+        let result = self.first.write_all(buf);
+        self.check(TeeSink::First, result)?;
+        let result = self.second.write_all(buf);
+        self.check(TeeSink::Second, result)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.first.flush();
+        self.check(TeeSink::First, result)?;
+        let result = self.second.flush();
+        self.check(TeeSink::Second, result)
+    }
+}
+
+// Buffers everything written and, on `flush`, passes it through `transform` (e.g. encryption or
+// compression) and writes the result as one length-prefixed frame. Bytes still buffered when the
+// writer is dropped are flushed on a best-effort basis, but an error there is lost, so call
+// `flush` or `into_inner` once a value is complete.
+pub struct TransformWriter<W: Write, F: FnMut(&[u8]) -> Vec<u8>> {
+    // Only taken by `into_inner`.
+    writer: Option<W>,
+    transform: F,
+    buf: Vec<u8>,
+}
+
+impl<W: Write, F: FnMut(&[u8]) -> Vec<u8>> TransformWriter<W, F> {
+    pub fn new(writer: W, transform: F) -> Self {
+        Self {
+            writer: Some(writer),
+            transform,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.writer.take().unwrap())
+    }
+}
+
+impl<W: Write, F: FnMut(&[u8]) -> Vec<u8>> Drop for TransformWriter<W, F> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<W: Write, F: FnMut(&[u8]) -> Vec<u8>> Write for TransformWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        if !self.buf.is_empty() {
+            let frame = (self.transform)(&self.buf);
+            let mut buf = [0u8; u64::PREFIX_VARINT_BUF_SIZE];
+            let size = (frame.len() as u64).encode_prefix_varint(&mut buf);
+            writer.write_all(&buf[..size])?;
+            writer.write_all(&frame)?;
+            self.buf.clear();
+        }
+        writer.flush()
+    }
+}
+
+// Reads the frames written by `TransformWriter`, passing each one through `transform` (e.g.
+// decryption or decompression) before handing out its bytes.
+pub struct TransformReader<R: Read, F: FnMut(&[u8]) -> Vec<u8>> {
+    reader: R,
+    transform: F,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read, F: FnMut(&[u8]) -> Vec<u8>> TransformReader<R, F> {
+    pub fn new(reader: R, transform: F) -> Self {
+        Self {
+            reader,
+            transform,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    // Returns false at the end of the input.
+    fn read_frame(&mut self) -> io::Result<bool> {
+        let mut first = [0u8; 1];
+        if self.reader.read(&mut first)? == 0 {
+            return Ok(false);
+        }
+        let len = u64::decode_prefix_varint(&mut first.as_ref().chain(&mut self.reader))?;
+        let mut frame = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut frame)?;
+        if frame.len() as u64 != len {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "truncated frame"));
+        }
+        self.buf = (self.transform)(&frame);
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read, F: FnMut(&[u8]) -> Vec<u8>> Read for TransformReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if !self.read_frame()? {
+                return Ok(0);
+            }
+        }
+        let size = min(buf.len(), self.buf.len() - self.pos);
+        buf[..size].copy_from_slice(&self.buf[self.pos..self.pos + size]);
+        self.pos += size;
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Decoded, Error, FooterReader, FooterWriter, LogReader, LogWriter, StreamDecoder, TeeSink,
+        TeeWriter, TransformReader, TransformWriter,
+    };
+    use crate::{Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+    use std::io::{Cursor, Read, Write};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Test {
+        a: u32,
+        b: String,
+    }
+
+    #[test]
+    fn log_records() {
+        let mut writer = LogWriter::new(Vec::new());
+        writer
+            .write_record(&Test {
+                a: 1,
+                b: "first".to_string(),
+            })
+            .unwrap();
+        writer
+            .write_record(&Test {
+                a: 2,
+                b: "second".to_string(),
+            })
+            .unwrap();
+        let buf = writer.into_inner();
+
+        let mut reader = LogReader::new(buf.as_slice());
+        assert_eq!(
+            reader.read_record::<Test>().unwrap(),
+            Some(Test {
+                a: 1,
+                b: "first".to_string(),
+            })
+        );
+        assert_eq!(
+            reader.read_record::<Test>().unwrap(),
+            Some(Test {
+                a: 2,
+                b: "second".to_string(),
+            })
+        );
+        assert_eq!(reader.read_record::<Test>().unwrap(), None);
+        assert_eq!(reader.offset(), buf.len() as u64);
+    }
+
+    #[test]
+    fn log_corrupted_record() {
+        let mut writer = LogWriter::new(Vec::new());
+        writer
+            .write_record(&Test {
+                a: 1,
+                b: "first".to_string(),
+            })
+            .unwrap();
+        let first_len = writer.into_inner().len() as u64;
+
+        let mut writer = LogWriter::new(Vec::new());
+        writer
+            .write_record(&Test {
+                a: 1,
+                b: "first".to_string(),
+            })
+            .unwrap();
+        writer
+            .write_record(&Test {
+                a: 2,
+                b: "second".to_string(),
+            })
+            .unwrap();
+        let mut buf = writer.into_inner();
+        buf[first_len as usize + 2] ^= 0xff;
+
+        let mut reader = LogReader::new(buf.as_slice());
+        assert!(reader.read_record::<Test>().unwrap().is_some());
+        assert_eq!(
+            reader.read_record::<Test>(),
+            Err(Error::Checksum { offset: first_len })
+        );
+    }
+
+    #[test]
+    fn log_torn_record() {
+        let mut writer = LogWriter::new(Vec::new());
+        writer.write_record(&123u32).unwrap();
+        let mut buf = writer.into_inner();
+        buf.pop();
+
+        let mut reader = LogReader::new(buf.as_slice());
+        assert_eq!(
+            reader.read_record::<u32>(),
+            Err(Error::TruncatedRecord { offset: 0 })
+        );
+
+        // A record cut inside its body or its checksum reports where the record started.
+        let mut writer = LogWriter::new(Vec::new());
+        writer.write_record(&1u8).unwrap();
+        writer.write_record(&"torn").unwrap();
+        let buf = writer.into_inner();
+        for end in [7, 9, 12] {
+            let mut reader = LogReader::new(&buf[..end]);
+            assert_eq!(reader.read_record::<u8>(), Ok(Some(1)));
+            assert_eq!(
+                reader.read_record::<String>(),
+                Err(Error::TruncatedRecord { offset: 6 })
+            );
+        }
+    }
+
+    #[test]
+    fn log_trailing_bytes() {
+        let body = [serialize_u32(123), vec![0]].concat();
+        let mut buf = vec![body.len() as u8];
+        buf.extend_from_slice(&body);
+        buf.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+
+        let mut reader = LogReader::new(buf.as_slice());
+        assert_eq!(
+            reader.read_record::<u32>(),
+            Err(Error::Deserialize(crate::de::Error::TrailingBytes))
+        );
+    }
+
+    fn serialize_u32(v: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+
+    // Fails every other read with `Interrupted`.
+    struct Interrupting<'a> {
+        buf: &'a [u8],
+        interrupted: bool,
+    }
+
+    impl std::io::Read for Interrupting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            self.buf.read(buf)
+        }
+    }
+
+    #[test]
+    fn log_interrupted_read() {
+        let mut writer = LogWriter::new(Vec::new());
+        writer.write_record(&300u32).unwrap();
+        writer.write_record(&7u32).unwrap();
+        let buf = writer.into_inner();
+
+        let mut reader = LogReader::new(Interrupting {
+            buf: &buf,
+            interrupted: false,
+        });
+        assert_eq!(reader.read_record::<u32>(), Ok(Some(300)));
+        assert_eq!(reader.read_record::<u32>(), Ok(Some(7)));
+        assert_eq!(reader.read_record::<u32>(), Ok(None));
+    }
+
+    // Hands out one byte per read and reports `WouldBlock` in between.
+    struct Trickle {
+        buf: Vec<u8>,
+        pos: usize,
+        blocked: bool,
+    }
+
+    impl std::io::Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.blocked = !self.blocked;
+            if self.blocked {
+                return Err(std::io::ErrorKind::WouldBlock.into());
+            }
+            if self.pos == self.buf.len() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.buf[self.pos];
+            self.pos += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn stream_decoder_resume() {
+        let first = Test {
+            a: 300,
+            b: "first".to_string(),
+        };
+        let second = Test {
+            a: 2,
+            b: "second".to_string(),
+        };
+        let mut buf = Vec::new();
+        first
+            .serialize(&mut crate::Serializer::new(&mut buf))
+            .unwrap();
+        second
+            .serialize(&mut crate::Serializer::new(&mut buf))
+            .unwrap();
+        let len = buf.len();
+
+        let mut decoder = StreamDecoder::new(Trickle {
+            buf,
+            pos: 0,
+            blocked: false,
+        });
+        let mut retries = 0;
+        let mut decoded = Vec::new();
+        while decoded.len() < 2 {
+            match decoder.decode::<Test>().unwrap() {
+                Decoded::Value(value) => decoded.push(value),
+                Decoded::Pending => retries += 1,
+                Decoded::Eof => panic!("unexpected end of stream"),
+            }
+        }
+        assert_eq!(decoded, [first, second]);
+        assert_eq!(retries, len);
+        assert!(decoder.buffered().is_empty());
+    }
+
+    #[test]
+    fn stream_decoder_pending_and_eof() {
+        // A reader that would block is pending, not finished, even with nothing buffered.
+        let mut decoder = StreamDecoder::new(Trickle {
+            buf: vec![1],
+            pos: 0,
+            blocked: false,
+        });
+        assert_eq!(decoder.decode::<bool>(), Ok(Decoded::Pending));
+        assert_eq!(decoder.decode::<bool>(), Ok(Decoded::Value(true)));
+        assert_eq!(decoder.decode::<bool>(), Ok(Decoded::Pending));
+        assert_eq!(decoder.decode::<bool>(), Ok(Decoded::Eof));
+
+        let mut decoder = StreamDecoder::new([].as_ref());
+        assert_eq!(decoder.decode::<bool>(), Ok(Decoded::Eof));
+        assert_eq!(decoder.decode::<bool>(), Ok(Decoded::Eof));
+    }
+
+    #[test]
+    fn tee_writer() {
+        let body = Test {
+            a: 300,
+            b: "replicated".to_string(),
+        };
+        let mut tee = TeeWriter::new(Vec::new(), Vec::new());
+        body.serialize(&mut Serializer::new(&mut tee)).unwrap();
+        body.serialize(&mut Serializer::new(&mut tee)).unwrap();
+        assert_eq!(tee.failed(), None);
+        let (first, second) = tee.into_inner();
+        assert_eq!(first, second);
+
+        let mut expected = Vec::new();
+        (&body, &body)
+            .serialize(&mut Serializer::new(&mut expected))
+            .unwrap();
+        assert_eq!(first, expected);
+    }
+
+    #[test]
+    fn tee_writer_failed_sink() {
+        let mut small = [0u8; 4];
+        let mut tee = TeeWriter::new(Vec::new(), small.as_mut());
+        let body = Test {
+            a: 1,
+            b: "too long".to_string(),
+        };
+        assert!(body.serialize(&mut Serializer::new(&mut tee)).is_err());
+        assert_eq!(tee.failed(), Some(TeeSink::Second));
+        let error = tee.write(&[0]).unwrap_err();
+        assert_eq!(error.to_string(), "second sink failed earlier");
+
+        let mut empty = [0u8; 0];
+        let mut tee = TeeWriter::new(empty.as_mut(), Vec::new());
+        let error = tee.write(&[0]).unwrap_err();
+        assert_eq!(tee.failed(), Some(TeeSink::First));
+        assert_eq!(
+            error.to_string(),
+            "first sink: failed to write whole buffer"
+        );
+        assert!(tee.into_inner().1.is_empty());
+    }
+
+    #[test]
+    fn transform_roundtrip() {
+        fn xor(buf: &[u8]) -> Vec<u8> {
+            buf.iter().map(|v| v ^ 0x5a).collect()
+        }
+
+        let first = Test {
+            a: 300,
+            b: "secret".to_string(),
+        };
+        let second = Test {
+            a: 7,
+            b: "another secret".to_string(),
+        };
+        let mut writer = TransformWriter::new(Vec::new(), xor);
+        first.serialize(&mut Serializer::new(&mut writer)).unwrap();
+        writer.flush().unwrap();
+        second.serialize(&mut Serializer::new(&mut writer)).unwrap();
+        let buf = writer.into_inner().unwrap();
+        assert!(!buf.windows(6).any(|v| v == b"secret"));
+
+        let mut reader = TransformReader::new(buf.as_slice(), xor);
+        let mut deserializer = Deserializer::new(&mut reader);
+        assert_eq!(Test::deserialize(&mut deserializer), Ok(first));
+        assert_eq!(Test::deserialize(&mut deserializer), Ok(second));
+        assert_eq!(deserializer.end(), Ok(()));
+
+        let mut reader = TransformReader::new(&buf[..buf.len() - 1], xor);
+        let mut rest = Vec::new();
+        assert!(reader.read_to_end(&mut rest).is_err());
+
+        // A writer dropped without a flush still writes what it buffered.
+        let third = Test {
+            a: 1,
+            b: "dropped".to_string(),
+        };
+        let mut buf = Vec::new();
+        third
+            .serialize(&mut Serializer::new(TransformWriter::new(&mut buf, xor)))
+            .unwrap();
+        let mut reader = TransformReader::new(buf.as_slice(), xor);
+        assert_eq!(
+            Test::deserialize(&mut Deserializer::new(&mut reader)),
+            Ok(third)
+        );
+    }
+
+    #[test]
+    fn footer_read_last() {
+        let mut writer = FooterWriter::new(Vec::new());
+        writer
+            .write_record(&Test {
+                a: 1,
+                b: "first".to_string(),
+            })
+            .unwrap();
+        writer
+            .write_record(&Test {
+                a: 2,
+                b: "second".to_string(),
+            })
+            .unwrap();
+        let buf = writer.into_inner();
+        assert_eq!(buf[buf.len() - 4..], [8, 0, 0, 0]);
+
+        let mut reader = FooterReader::new(Cursor::new(buf.as_slice()));
+        assert_eq!(
+            reader.read_last::<Test>(),
+            Ok(Some(Test {
+                a: 2,
+                b: "second".to_string(),
+            }))
+        );
+        assert_eq!(
+            reader.read_last::<Test>(),
+            Ok(Some(Test {
+                a: 1,
+                b: "first".to_string(),
+            }))
+        );
+        assert_eq!(reader.read_last::<Test>(), Ok(None));
+
+        let mut reader = FooterReader::new(Cursor::new(&buf[1..buf.len() - 1]));
+        assert_eq!(reader.read_last::<Test>(), Err(Error::Read));
+    }
+
+    #[test]
+    fn stream_decoder_truncated() {
+        let mut buf = Vec::new();
+        for a in [1, 300] {
+            Test {
+                a,
+                b: "cut".to_string(),
+            }
+            .serialize(&mut Serializer::new(&mut buf))
+            .unwrap();
+        }
+        buf.pop();
+
+        let mut decoder = StreamDecoder::new(buf.as_slice());
+        assert_eq!(
+            decoder.decode::<Test>(),
+            Ok(Decoded::Value(Test {
+                a: 1,
+                b: "cut".to_string(),
+            }))
+        );
+        let buffered = decoder.buffered().len();
+        assert!(buffered > 0);
+        assert_eq!(decoder.decode::<Test>(), Err(Error::Truncated { buffered }));
+    }
+
+    #[test]
+    fn stream_decoder_invalid() {
+        let mut decoder = StreamDecoder::new([2u8, 0].as_ref());
+        assert_eq!(
+            decoder.decode::<bool>(),
+            Err(Error::Deserialize(crate::de::Error::Read { offset: 0 }))
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use dlhn::{de::Error, Deserializer, Serializer};
+use serde::{Deserialize, Serialize};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+struct Counter;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static MAX_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ENABLED.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            MAX_SIZE.fetch_max(layout.size(), Ordering::SeqCst);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if ENABLED.load(Ordering::SeqCst) {
+            REALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            MAX_SIZE.fetch_max(new_size, Ordering::SeqCst);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counter = Counter;
+
+// Returns the result of `f` with the allocations, reallocations and largest request in bytes
+// made while running it.
+fn count<T>(f: impl FnOnce() -> T) -> (T, usize, usize, usize) {
+    ALLOCATIONS.store(0, Ordering::SeqCst);
+    REALLOCATIONS.store(0, Ordering::SeqCst);
+    MAX_SIZE.store(0, Ordering::SeqCst);
+    ENABLED.store(true, Ordering::SeqCst);
+    let result = f();
+    ENABLED.store(false, Ordering::SeqCst);
+    (
+        result,
+        ALLOCATIONS.load(Ordering::SeqCst),
+        REALLOCATIONS.load(Ordering::SeqCst),
+        MAX_SIZE.load(Ordering::SeqCst),
+    )
+}
+
+// A single test, so no other test thread allocates while counting.
+#[test]
+fn deserialize_seq_preallocation() {
+    let body = (0..1000u32).collect::<Vec<_>>();
+    let mut buf = Vec::new();
+    body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+
+    let mut reader = buf.as_slice();
+    let (result, allocations, reallocations, _) =
+        count(|| Vec::<u32>::deserialize(&mut Deserializer::new(&mut reader)));
+    assert_eq!(result, Ok(body));
+    assert_eq!(allocations, 1);
+    assert_eq!(reallocations, 0);
+
+    let mut buf = Vec::new();
+    (1u64 << 40)
+        .serialize(&mut Serializer::new(&mut buf))
+        .unwrap();
+    buf.extend([1, 2, 3]);
+    let mut reader = buf.as_slice();
+    let (result, _, _, max_size) =
+        count(|| Vec::<u32>::deserialize(&mut Deserializer::new(&mut reader)));
+    assert!(result.is_err());
+    assert!(max_size <= 4096 * std::mem::size_of::<u32>());
+
+    // A forged 4 GiB string length is rejected before anything is allocated for it.
+    let mut buf = Vec::new();
+    (1u64 << 32)
+        .serialize(&mut Serializer::new(&mut buf))
+        .unwrap();
+    buf.extend(b"abc");
+    let mut reader = buf.as_slice();
+    let (result, allocations, reallocations, _) =
+        count(|| String::deserialize(&mut Deserializer::new(&mut reader)));
+    assert_eq!(result, Err(Error::StringLengthLimit));
+    assert_eq!(allocations, 0);
+    assert_eq!(reallocations, 0);
+}
@@ -160,3 +160,193 @@ fn deserialize_header() {
         );
     }
 }
+
+#[test]
+fn derive_serialize_header_empty_enum() {
+    #[allow(dead_code)]
+    #[derive(SerializeHeader)]
+    enum Never {}
+
+    let mut buf = Vec::new();
+    Never::serialize_header(&mut buf).unwrap();
+    assert_eq!(buf, [24, 0]);
+    assert_eq!(
+        Cursor::new(buf).deserialize_header().unwrap(),
+        Header::Enum(Vec::new())
+    );
+}
+
+#[test]
+fn derive_serialize_header_with_rename() {
+    #[allow(dead_code)]
+    #[derive(SerializeHeader)]
+    #[serde(rename_all = "camelCase")]
+    struct Test {
+        first_field: bool,
+        #[serde(rename = "other")]
+        second_field: u8,
+    }
+
+    #[allow(dead_code)]
+    #[derive(SerializeHeader)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Enum {
+        #[serde(rename = "first")]
+        FirstVariant(bool),
+        SecondVariant {
+            inner_field: u8,
+        },
+    }
+
+    let mut buf = Vec::new();
+    Test::serialize_header(&mut buf).unwrap();
+    assert_eq!(buf, [21, 2, 2, 3]);
+
+    let mut buf = Vec::new();
+    Enum::serialize_header(&mut buf).unwrap();
+    assert_eq!(buf, [24, 2, 2, 3]);
+}
+
+#[cfg(feature = "either")]
+#[test]
+fn derive_serialize_header_either() {
+    use dlhn::{Body, Deserializer, Serializer};
+    use either::Either;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, SerializeHeader, Serialize, Deserialize)]
+    struct Test {
+        a: Either<u8, String>,
+        b: Either<bool, (u16, u32)>,
+    }
+
+    let mut buf = Vec::new();
+    Test::serialize_header(&mut buf).unwrap();
+    assert_eq!(buf, [21, 2, 24, 2, 3, 18, 24, 2, 2, 21, 2, 4, 5]);
+    let header = Cursor::new(buf).deserialize_header().unwrap();
+
+    for body in [
+        Test {
+            a: Either::Left(1),
+            b: Either::Right((2, 3)),
+        },
+        Test {
+            a: Either::Right("right".to_string()),
+            b: Either::Left(true),
+        },
+    ] {
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+
+        let mut reader = buf.as_slice();
+        let decoded = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+        assert!(decoded.validate(&header));
+        assert!(reader.is_empty());
+
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            Test::deserialize(&mut Deserializer::new(&mut reader)).unwrap(),
+            body
+        );
+    }
+}
+
+#[test]
+fn derive_serialize_header_struct_variant_field_order() {
+    use dlhn::{Body, Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, SerializeHeader, Serialize, Deserialize)]
+    enum Event {
+        Ping,
+        Move {
+            z: i32,
+            x: String,
+            a: bool,
+            m: Option<u16>,
+        },
+        Single {
+            only: u8,
+        },
+    }
+
+    let mut buf = Vec::new();
+    Event::serialize_header(&mut buf).unwrap();
+    let header = Cursor::new(buf).deserialize_header().unwrap();
+    assert_eq!(
+        header,
+        Header::Enum(vec![
+            Header::Unit,
+            Header::Tuple(vec![
+                Header::Int32,
+                Header::String,
+                Header::Boolean,
+                Header::Optional(Box::new(Header::UInt16)),
+            ]),
+            Header::UInt8,
+        ])
+    );
+
+    for (body, expected) in [
+        (
+            Event::Move {
+                z: -1,
+                x: "x".to_string(),
+                a: true,
+                m: Some(7),
+            },
+            Body::Enum(
+                1,
+                Box::new(Body::Tuple(vec![
+                    Body::Int32(-1),
+                    Body::String("x".to_string()),
+                    Body::Boolean(true),
+                    Body::Optional(Some(Box::new(Body::UInt16(7)))),
+                ])),
+            ),
+        ),
+        (
+            Event::Single { only: 9 },
+            Body::Enum(2, Box::new(Body::UInt8(9))),
+        ),
+        (Event::Ping, Body::Enum(0, Box::new(Body::Unit))),
+    ] {
+        let mut buf = Vec::new();
+        body.serialize(&mut Serializer::new(&mut buf)).unwrap();
+
+        let mut reader = buf.as_slice();
+        let decoded = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+        assert!(reader.is_empty());
+        assert!(decoded.validate(&header));
+        assert_eq!(decoded, expected);
+
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            Event::deserialize(&mut Deserializer::new(&mut reader)).unwrap(),
+            body
+        );
+    }
+}
+
+#[test]
+fn derive_serialize_header_newtype_struct() {
+    use dlhn::{Body, Deserializer, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, SerializeHeader, Serialize, Deserialize)]
+    struct W(u32);
+
+    let mut buf = Vec::new();
+    W::serialize_header(&mut buf).unwrap();
+    let header = Cursor::new(buf).deserialize_header().unwrap();
+    assert_eq!(header, Header::UInt32);
+
+    let mut buf = Vec::new();
+    W(300).serialize(&mut Serializer::new(&mut buf)).unwrap();
+    let mut reader = buf.as_slice();
+    assert_eq!(
+        Body::deserialize(&header, &mut Deserializer::new(&mut reader)),
+        Ok(Body::UInt32(300))
+    );
+    assert!(reader.is_empty());
+}
@@ -0,0 +1,305 @@
+use dlhn::{Deserializer, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PrimitivesPacked {
+    unsigned8: u8,
+    _pad0: [u8; 1],
+    unsigned16: u16,
+    unsigned32: u32,
+    unsigned64: u64,
+    signed8: i8,
+    _pad1: [u8; 1],
+    signed16: i16,
+    signed32: i32,
+    signed64: i64,
+    float32: f32,
+    _pad2: [u8; 4],
+    float64: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Primitives {
+    boolean: bool,
+    character: char,
+    unsigned8: u8,
+    unsigned16: u16,
+    unsigned32: u32,
+    unsigned64: u64,
+    unsigned128: u128,
+    signed8: i8,
+    signed16: i16,
+    signed32: i32,
+    signed64: i64,
+    signed128: i128,
+    unsignedsize: usize,
+    signedsize: isize,
+    float32: f32,
+    float64: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Allocated {
+    string: String,
+    #[serde(with = "serde_bytes")]
+    bytes: Vec<u8>,
+    string_map: HashMap<String, u64>,
+    string_set: HashSet<String>,
+    string_btree: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Tuples {
+    u0: (),
+    u1: (bool,),
+    u2: (bool, u8),
+    u3: (bool, u8, u32),
+    u4: (bool, u8, u32, u64),
+    u5: (bool, u8, u32, u64, f32),
+    u6: (bool, u8, u32, u64, f32, f64),
+    i0: (),
+    i1: (bool,),
+    i2: (bool, i8),
+    i3: (bool, i8, i32),
+    i4: (bool, i8, i32, i64),
+    i5: (bool, i8, i32, i64, f32),
+    i6: (bool, i8, i32, i64, f32, f64),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum MediumEnum {
+    String(String),
+    Numbered(u64),
+    EmptyTuple(),
+    NamedEmpty {},
+    Named {
+        a: u32,
+        primitives: PrimitivesPacked,
+        b: u64,
+    },
+    Unnamed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct LargeStruct {
+    primitives: Vec<Primitives>,
+    packed: Vec<PrimitivesPacked>,
+    allocated: Vec<Allocated>,
+    tuples: Vec<(Tuples, Tuples)>,
+    medium_vec: Vec<MediumEnum>,
+    medium_map: HashMap<String, MediumEnum>,
+    string_keys: HashMap<String, u64>,
+    number_vec: Vec<(u32, u64)>,
+    optional: Option<Box<LargeStruct>>,
+}
+
+fn primitives_packed(seed: u64) -> PrimitivesPacked {
+    PrimitivesPacked {
+        unsigned8: seed as u8,
+        _pad0: [0],
+        unsigned16: (seed * 257) as u16,
+        unsigned32: (seed * 65_537) as u32,
+        unsigned64: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+        signed8: (seed as i8).wrapping_neg(),
+        _pad1: [0],
+        signed16: ((seed * 257) as i16).wrapping_neg(),
+        signed32: ((seed * 65_537) as i32).wrapping_neg(),
+        signed64: i64::MIN + seed as i64,
+        float32: seed as f32 / 3.0,
+        _pad2: [0; 4],
+        float64: -(seed as f64) / 7.0,
+    }
+}
+
+fn primitives(seed: u64) -> Primitives {
+    Primitives {
+        boolean: seed & 1 == 0,
+        character: ['a', 'é', '字', '🦀'][seed as usize % 4],
+        unsigned8: u8::MAX - seed as u8,
+        unsigned16: u16::MAX - seed as u16,
+        unsigned32: u32::MAX - seed as u32,
+        unsigned64: u64::MAX - seed,
+        unsigned128: u128::MAX - seed as u128,
+        signed8: i8::MIN + seed as i8,
+        signed16: i16::MIN + seed as i16,
+        signed32: i32::MIN + seed as i32,
+        signed64: i64::MIN + seed as i64,
+        signed128: i128::MIN + seed as i128,
+        unsignedsize: usize::MAX - seed as usize,
+        signedsize: isize::MIN + seed as isize,
+        float32: f32::MAX / (seed + 1) as f32,
+        float64: f64::MIN_POSITIVE * seed as f64,
+    }
+}
+
+fn allocated(seed: u64) -> Allocated {
+    Allocated {
+        string: "x".repeat(seed as usize * 37),
+        bytes: (0..seed * 41).map(|v| v as u8).collect(),
+        string_map: (0..seed).map(|v| (format!("key{}", v), v * v)).collect(),
+        string_set: (0..seed).map(|v| format!("set{}", v)).collect(),
+        string_btree: (0..seed).map(|v| (format!("btree{}", v), v)).collect(),
+    }
+}
+
+fn tuples(seed: u64) -> Tuples {
+    let b = seed % 3 != 1;
+    let f32 = seed as f32 * 0.5;
+    let f64 = seed as f64 * -0.25;
+    Tuples {
+        u0: (),
+        u1: (b,),
+        u2: (b, seed as u8),
+        u3: (b, seed as u8, seed as u32 * 1000),
+        u4: (b, seed as u8, seed as u32 * 1000, seed << 40),
+        u5: (b, seed as u8, seed as u32 * 1000, seed << 40, f32),
+        u6: (b, seed as u8, seed as u32 * 1000, seed << 40, f32, f64),
+        i0: (),
+        i1: (!b,),
+        i2: (!b, (seed as i8).wrapping_neg()),
+        i3: (!b, (seed as i8).wrapping_neg(), -(seed as i32) * 1000),
+        i4: (
+            !b,
+            (seed as i8).wrapping_neg(),
+            -(seed as i32) * 1000,
+            -((seed << 40) as i64),
+        ),
+        i5: (
+            !b,
+            (seed as i8).wrapping_neg(),
+            -(seed as i32) * 1000,
+            -((seed << 40) as i64),
+            -f32,
+        ),
+        i6: (
+            !b,
+            (seed as i8).wrapping_neg(),
+            -(seed as i32) * 1000,
+            -((seed << 40) as i64),
+            -f32,
+            -f64,
+        ),
+    }
+}
+
+fn medium_enum(seed: u64) -> MediumEnum {
+    match seed % 6 {
+        0 => MediumEnum::String(format!("variant{}", seed)),
+        1 => MediumEnum::Numbered(seed << 50),
+        2 => MediumEnum::EmptyTuple(),
+        3 => MediumEnum::NamedEmpty {},
+        4 => MediumEnum::Named {
+            a: seed as u32,
+            primitives: primitives_packed(seed),
+            b: !seed,
+        },
+        _ => MediumEnum::Unnamed,
+    }
+}
+
+fn large_struct(seed: u64, depth: usize) -> LargeStruct {
+    LargeStruct {
+        primitives: (0..seed * 3).map(primitives).collect(),
+        packed: (0..seed * 2).map(primitives_packed).collect(),
+        allocated: (0..seed).map(allocated).collect(),
+        tuples: (0..seed * 2).map(|v| (tuples(v), tuples(v + 1))).collect(),
+        medium_vec: (0..seed * 6).map(medium_enum).collect(),
+        medium_map: (0..seed * 6)
+            .map(|v| (format!("medium{}", v), medium_enum(v)))
+            .collect(),
+        string_keys: (0..seed * 4).map(|v| (v.to_string(), v)).collect(),
+        number_vec: (0..seed * 10).map(|v| (v as u32, v << 20)).collect(),
+        optional: if depth > 0 {
+            Some(Box::new(large_struct(seed + 1, depth - 1)))
+        } else {
+            None
+        },
+    }
+}
+
+fn roundtrip(value: &LargeStruct) -> Vec<u8> {
+    let mut buf = Vec::new();
+    value.serialize(&mut Serializer::new(&mut buf)).unwrap();
+    let mut reader = buf.as_slice();
+    let mut deserializer = Deserializer::new(&mut reader);
+    assert_eq!(&LargeStruct::deserialize(&mut deserializer).unwrap(), value);
+    assert_eq!(deserializer.end(), Ok(()));
+    buf
+}
+
+#[test]
+fn large_struct_empty() {
+    roundtrip(&large_struct(0, 0));
+}
+
+#[test]
+fn large_struct_roundtrip() {
+    for seed in 1..8 {
+        roundtrip(&large_struct(seed, 2));
+    }
+}
+
+#[test]
+fn large_struct_big_collections() {
+    let mut value = large_struct(1, 0);
+    value.allocated = vec![Allocated {
+        string: "y".repeat(3_000_000),
+        bytes: vec![7; 5_000_000],
+        string_map: (0..20_000).map(|v| (format!("key{}", v), v)).collect(),
+        string_set: HashSet::new(),
+        string_btree: BTreeMap::new(),
+    }];
+    value.medium_vec = (0..70_000).map(medium_enum).collect();
+    value.number_vec = (0..100_000)
+        .map(|v| (v as u32 * 40_000, (v as u64) << 33))
+        .collect();
+    roundtrip(&value);
+}
+
+const FIXTURE: &[u8] = &[
+    1, 1, 1, 97, 255, 192, 255, 255, 240, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+    255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+    255, 3, 128, 192, 255, 255, 240, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+    255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+    3, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+    255, 255, 127, 127, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255, 255,
+    255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 1, 0, 129, 4, 193,
+    0, 8, 255, 21, 124, 74, 127, 185, 121, 55, 158, 255, 0, 129, 8, 193, 0, 16, 255, 253, 255, 255,
+    255, 255, 255, 255, 255, 171, 170, 170, 62, 0, 0, 0, 0, 146, 36, 73, 146, 36, 73, 194, 191, 1,
+    0, 0, 0, 0, 0, 1, 1, 1, 0, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 0, 0, 0, 0,
+    0, 0, 0, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 168, 15, 0, 1, 168, 15, 248, 0, 0, 0, 0,
+    64, 0, 1, 168, 15, 248, 0, 0, 0, 0, 64, 0, 0, 0, 63, 0, 1, 168, 15, 248, 0, 0, 0, 0, 64, 0, 0,
+    0, 63, 0, 0, 0, 0, 0, 0, 208, 191, 1, 1, 255, 1, 255, 143, 31, 1, 255, 143, 31, 251, 255, 255,
+    255, 255, 127, 1, 255, 143, 31, 251, 255, 255, 255, 255, 127, 0, 0, 0, 191, 1, 255, 143, 31,
+    251, 255, 255, 255, 255, 127, 0, 0, 0, 191, 0, 0, 0, 0, 0, 0, 208, 63, 6, 0, 8, 118, 97, 114,
+    105, 97, 110, 116, 48, 1, 254, 0, 0, 0, 0, 0, 0, 4, 2, 3, 4, 4, 4, 0, 132, 16, 196, 0, 32, 255,
+    84, 240, 41, 253, 229, 230, 221, 120, 252, 0, 135, 32, 199, 0, 64, 255, 247, 255, 255, 255,
+    255, 255, 255, 255, 171, 170, 170, 63, 0, 0, 0, 0, 146, 36, 73, 146, 36, 73, 226, 191, 255,
+    251, 255, 255, 255, 255, 255, 255, 255, 5, 0, 0, 10, 0, 0, 1, 192, 0, 128, 2, 224, 0, 0, 2, 3,
+    224, 0, 0, 3, 4, 224, 0, 0, 4, 5, 224, 0, 0, 5, 6, 224, 0, 0, 6, 7, 224, 0, 0, 7, 8, 224, 0, 0,
+    8, 9, 224, 0, 0, 9, 0,
+];
+
+fn fixture() -> LargeStruct {
+    let mut value = large_struct(1, 0);
+    value.primitives.truncate(1);
+    value.tuples.truncate(1);
+    value.medium_map.clear();
+    value.string_keys.clear();
+    value
+}
+
+#[test]
+fn large_struct_fixture() {
+    assert_eq!(roundtrip(&fixture()), FIXTURE);
+
+    let mut reader = FIXTURE;
+    let mut deserializer = Deserializer::new(&mut reader);
+    assert_eq!(
+        LargeStruct::deserialize(&mut deserializer).unwrap(),
+        fixture()
+    );
+    assert_eq!(deserializer.end(), Ok(()));
+}
@@ -0,0 +1,110 @@
+use dlhn::{Body, DeserializeHeader, Deserializer, Header, SerializeHeader, Serializer};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Debug};
+
+fn assert_roundtrip<T>(value: T)
+where
+    T: Serialize + DeserializeOwned + SerializeHeader + PartialEq + Debug,
+{
+    let mut buf = Vec::new();
+    value.serialize(&mut Serializer::new(&mut buf)).unwrap();
+
+    let mut reader = buf.as_slice();
+    let result = T::deserialize(&mut Deserializer::new(&mut reader)).unwrap();
+    assert!(reader.is_empty());
+    assert_eq!(result, value);
+
+    let header = {
+        let mut buf = Vec::new();
+        T::serialize_header(&mut buf).unwrap();
+        buf.as_slice().deserialize_header().unwrap()
+    };
+    let mut reader = buf.as_slice();
+    let body = Body::deserialize(&header, &mut Deserializer::new(&mut reader)).unwrap();
+    assert!(reader.is_empty());
+    assert!(body.validate(&header));
+
+    let mut body_buf = Vec::new();
+    body.serialize(&mut Serializer::new(&mut body_buf)).unwrap();
+    assert_eq!(body_buf, buf);
+}
+
+#[test]
+fn option_vec_option_string() {
+    assert_roundtrip::<Option<Vec<Option<String>>>>(None);
+    assert_roundtrip::<Option<Vec<Option<String>>>>(Some(Vec::new()));
+    assert_roundtrip::<Option<Vec<Option<String>>>>(Some(vec![None, None, None]));
+    assert_roundtrip::<Option<Vec<Option<String>>>>(Some(vec![
+        Some("a".to_string()),
+        None,
+        Some(String::new()),
+    ]));
+}
+
+#[test]
+fn vec_option_vec_u8() {
+    assert_roundtrip::<Vec<Option<Vec<u8>>>>(Vec::new());
+    assert_roundtrip::<Vec<Option<Vec<u8>>>>(vec![None, None]);
+    assert_roundtrip::<Vec<Option<Vec<u8>>>>(vec![Some(Vec::new()), None, Some(vec![0, 255])]);
+}
+
+#[test]
+fn btree_map_option_bool() {
+    assert_roundtrip::<BTreeMap<String, Option<bool>>>(BTreeMap::new());
+    assert_roundtrip({
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), None::<bool>);
+        map.insert("b".to_string(), None);
+        map
+    });
+    assert_roundtrip({
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Some(true));
+        map.insert("b".to_string(), None);
+        map.insert("c".to_string(), Some(false));
+        map
+    });
+}
+
+#[test]
+fn nested_body() {
+    let header = Header::Optional(Box::new(Header::Array(Box::new(Header::Optional(
+        Box::new(Header::String),
+    )))));
+    let mut buf = Vec::new();
+    Some(vec![Some("a".to_string()), None])
+        .serialize(&mut Serializer::new(&mut buf))
+        .unwrap();
+    assert_eq!(
+        Body::deserialize(&header, &mut Deserializer::new(&mut buf.as_slice())).unwrap(),
+        Body::Optional(Some(Box::new(Body::Array(vec![
+            Body::Optional(Some(Box::new(Body::String("a".to_string())))),
+            Body::Optional(None),
+        ]))))
+    );
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, SerializeHeader)]
+struct Nested {
+    a: Option<Vec<Option<String>>>,
+    b: Vec<Option<Vec<u8>>>,
+    c: BTreeMap<String, Option<bool>>,
+}
+
+#[test]
+fn nested_struct() {
+    assert_roundtrip(Nested {
+        a: None,
+        b: Vec::new(),
+        c: BTreeMap::new(),
+    });
+    assert_roundtrip(Nested {
+        a: Some(vec![None, Some("x".to_string())]),
+        b: vec![None, Some(vec![1, 2, 3])],
+        c: {
+            let mut map = BTreeMap::new();
+            map.insert("k".to_string(), None);
+            map
+        },
+    });
+}
@@ -0,0 +1,35 @@
+use dlhn::{Body, Deserializer, SerializeHeader, Serializer};
+use serde::Serialize;
+
+#[test]
+fn self_describing_struct() {
+    #[derive(Serialize, SerializeHeader)]
+    struct Test {
+        a: bool,
+        b: Option<u8>,
+        c: Vec<String>,
+    }
+
+    let mut buf = Vec::new();
+    Serializer::new(&mut buf)
+        .serialize_self_describing(&Test {
+            a: true,
+            b: None,
+            c: vec!["test".to_string()],
+        })
+        .unwrap();
+
+    let mut reader = buf.as_slice();
+    let (header, body) = Deserializer::new(&mut reader)
+        .deserialize_self_describing()
+        .unwrap();
+    assert!(body.validate(&header));
+    assert_eq!(
+        body,
+        Body::Tuple(vec![
+            Body::Boolean(true),
+            Body::Optional(None),
+            Body::Array(vec![Body::String("test".to_string())]),
+        ])
+    );
+}
@@ -0,0 +1,236 @@
+use dlhn::{de::Error, Deserializer, Header, Serializer};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Stores a `SystemTime` as signed milliseconds relative to the Unix epoch.
+mod timestamp_millis {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    pub fn serialize<S: Serializer>(v: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let millis = match v.duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_millis() as i64,
+            Err(e) => -(e.duration().as_millis() as i64),
+        };
+        millis.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let millis = i64::deserialize(deserializer)?;
+        let offset = Duration::from_millis(millis.unsigned_abs());
+        Ok(if millis < 0 {
+            UNIX_EPOCH - offset
+        } else {
+            UNIX_EPOCH + offset
+        })
+    }
+}
+
+// Stores a number as its decimal string and parses it back through `deserialize_str`.
+mod decimal_string {
+    use serde::{de, Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(v: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&v.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        struct DecimalVisitor;
+
+        impl<'de> de::Visitor<'de> for DecimalVisitor {
+            type Value = u64;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a decimal string")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(DecimalVisitor)
+    }
+}
+
+fn roundtrip<T: Serialize + for<'de> Deserialize<'de>>(v: &T) -> Result<T, Error> {
+    let mut buf = Vec::new();
+    v.serialize(&mut Serializer::new(&mut buf)).unwrap();
+    let mut reader = buf.as_slice();
+    let result = T::deserialize(&mut Deserializer::new(&mut reader));
+    if result.is_ok() {
+        assert!(reader.is_empty());
+    }
+    result
+}
+
+#[test]
+fn serde_with_timestamp() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Event {
+        name: String,
+        #[serde(with = "timestamp_millis")]
+        at: SystemTime,
+    }
+
+    for at in [
+        UNIX_EPOCH,
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+        UNIX_EPOCH - Duration::from_millis(86_400_000),
+    ] {
+        let event = Event {
+            name: "deploy".to_string(),
+            at,
+        };
+        assert_eq!(roundtrip(&event), Ok(event));
+    }
+
+    // The field is an ordinary zigzag varint on the wire.
+    let mut buf = Vec::new();
+    Event {
+        name: String::new(),
+        at: UNIX_EPOCH - Duration::from_millis(1),
+    }
+    .serialize(&mut Serializer::new(&mut buf))
+    .unwrap();
+    assert_eq!(buf, [0, 1]);
+}
+
+#[test]
+fn serde_with_visit_str() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        #[serde(with = "decimal_string")]
+        value: u64,
+    }
+
+    let counter = Counter { value: u64::MAX };
+    assert_eq!(roundtrip(&counter), Ok(counter));
+}
+
+#[test]
+fn serde_with_not_self_describing() {
+    // Modules that inspect the input, or skip it, need a Header to know what comes next.
+    #[derive(Debug, PartialEq)]
+    struct Any;
+
+    impl<'de> Deserialize<'de> for Any {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct AnyVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for AnyVisitor {
+                type Value = Any;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("anything")
+                }
+            }
+
+            deserializer.deserialize_any(AnyVisitor)
+        }
+    }
+
+    let buf = [1u8];
+    assert_eq!(
+        Any::deserialize(&mut Deserializer::new(&mut buf.as_ref())),
+        Err(Error::NotSelfDescribing)
+    );
+    assert_eq!(
+        IgnoredAny::deserialize(&mut Deserializer::new(&mut buf.as_ref())),
+        Err(Error::NotSelfDescribing)
+    );
+}
+
+#[test]
+fn serde_adjacently_tagged() {
+    // The tag is written as the variant index and the content follows it, the same bytes as the
+    // default externally tagged representation.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        Ping,
+        Text(String),
+        Move(i32, i32),
+        Rename { from: String },
+    }
+
+    #[derive(Serialize)]
+    enum External {
+        Ping,
+        Text(String),
+        Move(i32, i32),
+        Rename { from: String },
+    }
+
+    let header = Header::Enum(vec![
+        Header::Unit,
+        Header::String,
+        Header::Tuple(vec![Header::Int32, Header::Int32]),
+        Header::Tuple(vec![Header::String]),
+    ]);
+    let values = [
+        (Adjacent::Ping, External::Ping, vec![0]),
+        (
+            Adjacent::Text("a".to_string()),
+            External::Text("a".to_string()),
+            vec![1, 1, 97],
+        ),
+        (Adjacent::Move(-1, 2), External::Move(-1, 2), vec![2, 1, 4]),
+        (
+            Adjacent::Rename {
+                from: "b".to_string(),
+            },
+            External::Rename {
+                from: "b".to_string(),
+            },
+            vec![3, 1, 98],
+        ),
+    ];
+    for (adjacent, external, bytes) in values {
+        let mut buf = Vec::new();
+        adjacent.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        let mut expected = Vec::new();
+        external
+            .serialize(&mut Serializer::new(&mut expected))
+            .unwrap();
+        assert_eq!(buf, expected);
+        assert_eq!(buf, bytes);
+
+        // The header proves the enum small, so the tag is a single `u8`, the same byte as the
+        // varint for these indices.
+        let mut compact = Vec::new();
+        Serializer::new(&mut compact)
+            .compact_enum_tags(true)
+            .serialize_with_header(&adjacent, &header)
+            .unwrap();
+        assert_eq!(compact, bytes);
+
+        // serde decodes the content of unit and struct variants through `deserialize_any`, which
+        // the header does not reach either.
+        let mut reader = buf.as_slice();
+        let plain = Adjacent::deserialize(&mut Deserializer::new(&mut reader));
+        let mut compact_reader = compact.as_slice();
+        let guided = Deserializer::new(&mut compact_reader)
+            .compact_enum_tags(true)
+            .deserialize_guided::<Adjacent>(&header);
+        for result in [plain, guided] {
+            match adjacent {
+                Adjacent::Ping | Adjacent::Rename { .. } => {
+                    assert_eq!(result, Err(Error::NotSelfDescribing))
+                }
+                _ => assert_eq!(result.as_ref(), Ok(&adjacent)),
+            }
+        }
+        if !matches!(adjacent, Adjacent::Ping | Adjacent::Rename { .. }) {
+            assert!(reader.is_empty());
+            assert!(compact_reader.is_empty());
+        }
+    }
+    assert!(Error::NotSelfDescribing
+        .to_string()
+        .contains("adjacently tagged enums can only have newtype and tuple variants"));
+}
@@ -41,6 +41,21 @@ pub fn derive_serialize_header(input: TokenStream) -> TokenStream {
                 }
             }
 
+            // serde serializes a newtype struct as its inner value, so its header is the inner
+            // type's header rather than a one-element tuple.
+            if let (syn::Fields::Unnamed(fields), [ty]) = (&data.fields, types.as_slice()) {
+                if fields.unnamed.len() == 1 {
+                    return quote! {
+                        impl dlhn::header::ser::SerializeHeader for #type_name {
+                            fn serialize_header<W: std::io::Write>(writer: &mut W) -> std::io::Result<()> {
+                                <#ty>::serialize_header(writer)
+                            }
+                        }
+                    }
+                    .into();
+                }
+            }
+
             let fields_count = (types.len() as u16)
                 .encode_prefix_varint_vec()
                 .iter()